@@ -0,0 +1,56 @@
+use std::path::Path;
+use std::process::Command;
+
+/*
+ * Records the commit iDrop is built from for model fingerprints, with -dirty
+ * if the checkout had changes. Rerun-if-changed lines replace cargo's default
+ * of rerunning on any change in the package, so the sources are listed next
+ * to git's HEAD, index and current branch to keep both parts current.
+ */
+fn main() {
+	println!("cargo:rerun-if-changed=build.rs");
+	println!("cargo:rerun-if-changed=Cargo.toml");
+	println!("cargo:rerun-if-changed=src");
+
+	let git_dir = match git(&["rev-parse", "--git-dir"]) {
+		Some(dir) => dir,
+		None => return,
+	};
+	let git_dir = Path::new(&git_dir);
+	println!("cargo:rerun-if-changed={}", git_dir.join("HEAD").display());
+	println!("cargo:rerun-if-changed={}", git_dir.join("index").display());
+
+	// a commit moves the branch HEAD points to, not HEAD itself
+	if let Some(branch) = git(&["symbolic-ref", "-q", "HEAD"]) {
+		println!("cargo:rerun-if-changed={}", git_dir.join(branch).display());
+		println!(
+			"cargo:rerun-if-changed={}",
+			git_dir.join("packed-refs").display()
+		);
+	}
+
+	let commit = match git(&["rev-parse", "HEAD"]) {
+		Some(commit) => commit,
+		None => return,
+	};
+	let dirty = Command::new("git")
+		.args(["diff", "--quiet", "HEAD"])
+		.status()
+		.is_ok_and(|status| status.code() == Some(1));
+
+	if dirty {
+		println!("cargo:rustc-env=IDROP_COMMIT={commit}-dirty");
+	} else {
+		println!("cargo:rustc-env=IDROP_COMMIT={commit}");
+	}
+}
+
+// Trimmed output of a successful git command
+fn git(args: &[&str]) -> Option<String> {
+	let output = Command::new("git").args(args).output().ok()?;
+	if !output.status.success() {
+		return None;
+	}
+
+	Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -1,6 +1,14 @@
+import json
+import os
+import platform
+import random
+
+import numpy as np
+import onnx
+import onnxmltools
+import tensorflow as tf
 from keras import layers
 from tensorflow import keras
-import onnxmltools
 
 # for reading and parsing csv
 from numpy import genfromtxt
@@ -8,7 +16,33 @@ from numpy import genfromtxt
 csv_path = ""
 
 
-def new_model(csv_path, model_name="new_model"):
+def seed_everything(seed):
+    """Makes training repeatable: the same seed and data give the same model"""
+    os.environ["PYTHONHASHSEED"] = str(seed)
+    random.seed(seed)
+    np.random.seed(seed)
+    tf.random.set_seed(seed)
+
+    # deterministic kernels, slower but free of the order of parallel sums
+    if hasattr(tf.config.experimental, "enable_op_determinism"):
+        tf.config.experimental.enable_op_determinism()
+
+
+def versions():
+    return {
+        "python": platform.python_version(),
+        "tensorflow": tf.__version__,
+        "keras": keras.__version__,
+        "numpy": np.__version__,
+        "onnx": onnx.__version__,
+        "onnxmltools": onnxmltools.__version__,
+    }
+
+
+def new_model(csv_path, model_name="new_model", seed=0):
+    """Trains and saves model_name.onnx, returns the metrics and package versions as JSON"""
+    seed_everything(seed)
+
     data = genfromtxt(
         csv_path,
         delimiter=",",
@@ -45,3 +79,13 @@ def new_model(csv_path, model_name="new_model"):
     # save the newly created model
     # default location: ../assets/models/new_model.onnx
     onnxmltools.utils.save_model(onnx_model, model_name + ".onnx")
+
+    return json.dumps(
+        {
+            "metrics": {
+                "loss": history.history["loss"][-1],
+                "val_loss": history.history["val_loss"][-1],
+            },
+            "versions": versions(),
+        }
+    )
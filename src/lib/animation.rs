@@ -0,0 +1,205 @@
+// Simulated stream of droplets travelling down the channel
+use iced::{
+	alignment,
+	canvas::{Cursor, Frame, Geometry, Path, Stroke},
+	pure::widget::canvas::{self, Program},
+	Point, Rectangle, Vector,
+};
+
+use crate::{physics::Channel, styling::Theme, views::SceneMessage};
+
+// Slow-motion factor the animation is played back with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlaybackSpeed(pub u32);
+
+impl PlaybackSpeed {
+	pub const ALL: [Self; 5] = [
+		PlaybackSpeed(1),
+		PlaybackSpeed(10),
+		PlaybackSpeed(100),
+		PlaybackSpeed(1000),
+		PlaybackSpeed(10000),
+	];
+
+	pub fn factor(&self) -> f32 {
+		1.0 / self.0 as f32
+	}
+}
+
+impl Default for PlaybackSpeed {
+	fn default() -> Self {
+		PlaybackSpeed(1000)
+	}
+}
+
+impl std::fmt::Display for PlaybackSpeed {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.0 {
+			1 => write!(f, "Real time"),
+			n => write!(f, "{n}× slower"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DropletTrain {
+	pub length: f32,    // µm
+	pub height: f32,    // µm
+	pub frequency: f32, // Hz
+	pub velocity: f32,  // µm/s
+	pub channel: Channel,
+}
+
+impl DropletTrain {
+	/*
+	 * Droplets travel with the mean velocity of the total flow through the
+	 * channel cross-section. Returns None when the prediction gives no
+	 * forward flow or no droplets.
+	 */
+	pub fn new(
+		[length, height, frequency]: [f32; 3],
+		flows: [f32; 2],
+		channel: Channel,
+	) -> Option<Self> {
+		let velocity = channel.mean_velocity(flows[0] + flows[1]) * 1e6;
+
+		let valid = |value: f32| value.is_finite() && value > 0.0;
+		if !(valid(velocity) && valid(frequency) && valid(length)) {
+			return None;
+		}
+
+		Some(DropletTrain {
+			length,
+			height,
+			frequency,
+			velocity,
+			channel,
+		})
+	}
+
+	// Distance between the centres of consecutive droplets in µm
+	pub fn spacing(&self) -> f32 {
+		self.velocity / self.frequency
+	}
+
+	// Length of channel shown, a few droplets long
+	pub fn span(&self) -> f32 {
+		let width = self.channel.width;
+
+		(4.0 * self.spacing().max(self.length)).clamp(5.0 * width, 40.0 * width)
+	}
+
+	/*
+	 * Centres of the droplets inside the shown stretch of channel at a time
+	 * in seconds. Droplet k pinches off at the inlet at k / frequency.
+	 */
+	pub fn positions(&self, time: f32) -> Vec<f32> {
+		const MAX_DROPLETS: usize = 500;
+
+		let span = self.span();
+		let newest = (time * self.frequency).floor() as i64;
+
+		(0..=newest)
+			.rev()
+			.map(|k| self.velocity * (time - k as f32 / self.frequency) - self.length / 2.0)
+			.take_while(|x| x - self.length / 2.0 <= span)
+			.take(MAX_DROPLETS)
+			.collect()
+	}
+}
+
+// -------------------------------------------------- CANVAS
+pub struct Train {
+	pub train: DropletTrain,
+	pub time: f32, // s
+	pub theme: Theme,
+}
+
+impl Program<SceneMessage> for Train {
+	type State = ();
+
+	fn draw(&self, _state: &Self::State, bounds: Rectangle, _cursor: Cursor) -> Vec<Geometry> {
+		let mut frame = Frame::new(bounds.size());
+		let train = &self.train;
+
+		let scale = frame.width() / train.span();
+		let center_y = frame.height() / 2.0;
+		let half_width = (train.channel.width * scale / 2.0).min(center_y - 2.0);
+
+		let palette = self.theme.extended_palette();
+		let drop_fill = palette.primary.weak.color;
+		let outline = Stroke {
+			width: 1.0,
+			color: palette.background.strong.color,
+			..Stroke::default()
+		};
+
+		for wall in [center_y - half_width, center_y + half_width] {
+			frame.stroke(
+				&Path::line(Point::new(0.0, wall), Point::new(frame.width(), wall)),
+				Stroke {
+					width: 2.0,
+					..outline
+				},
+			);
+		}
+
+		let radii = Vector::new(
+			train.length * scale / 2.0,
+			(train.height * scale / 2.0).min(half_width),
+		);
+
+		for x in train.positions(self.time) {
+			let droplet = Path::new(|path| {
+				path.ellipse(canvas::path::arc::Elliptical {
+					center: Point::new(x * scale, center_y),
+					radii,
+					rotation: 0.0,
+					start_angle: 0.0,
+					end_angle: 2.0 * std::f32::consts::PI,
+				})
+			});
+
+			frame.fill(&droplet, drop_fill);
+			frame.stroke(&droplet, outline);
+		}
+
+		frame.fill_text(canvas::Text {
+			content: format!("{:.0} µm", train.span()),
+			position: Point::new(frame.width() - 2.0, frame.height() - 2.0),
+			size: 13.0,
+			color: palette.background.base.text,
+			horizontal_alignment: alignment::Horizontal::Right,
+			vertical_alignment: alignment::Vertical::Bottom,
+			..canvas::Text::default()
+		});
+
+		vec![frame.into_geometry()]
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn droplets_spawn_at_frequency() {
+		let channel = Channel {
+			width: 100.0,
+			height: 100.0,
+		};
+
+		// 6 µL/min through 100x100 µm is 10 mm/s
+		let train = DropletTrain::new([50.0, 50.0, 10.0], [4.0, 2.0], channel).unwrap();
+		assert!((train.velocity - 10_000.0).abs() < 1.0);
+		assert!((train.spacing() - 1000.0).abs() < 0.1);
+
+		// at 0.25 s three droplets have formed, the oldest 2.5 mm downstream
+		let positions = train.positions(0.25);
+		assert_eq!(positions.len(), 3);
+		assert!((positions[0] - 475.0).abs() < 0.1);
+		assert!((positions[2] - 2475.0).abs() < 0.1);
+
+		assert!(DropletTrain::new([50.0, 50.0, 10.0], [0.0, 0.0], channel).is_none());
+	}
+}
@@ -1,59 +1,192 @@
 use iced::{
-	pure::{button, column, container, horizontal_space, row, scrollable, Element, Sandbox},
-	ContentFit, Length, Settings,
+	executor,
+	pure::{column, container, horizontal_space, row, scrollable, text, Application, Element},
+	time::Duration,
+	Alignment, Color, Command, ContentFit, Length, Settings, Subscription,
 };
+use iced_native::{window, Event};
+use native_dialog::FileDialog;
+use std::{error::Error, path::PathBuf};
 
 use super::{
-	styling::{btn, logo, Theme},
-	views::{Message, Scenes},
+	config::Config,
+	project::{Project, RecentProjects, MANIFEST},
+	shortcuts::{self, Shortcut},
+	storage::{data_dir, set_project_dir},
+	styling::{btn, drop_down, logo, nav_btn, tglr, Theme, ThemePreference, UiScale, BOLD},
+	views::{Message, Scene, SceneMessage, Scenes},
 };
 
 pub struct App {
 	scenes: Scenes,
+	config: Config,
 	theme: Theme,
+	project: Option<Project>,
+	recent: RecentProjects,
+	shortcuts_toggle: bool,
+	error: Result<(), Box<dyn Error>>,
 }
 
 impl App {
 	pub fn launch() -> Result<(), iced::Error> {
+		// App::new reports a config that fails to load
+		let text_size = Config::load().unwrap_or_default().text_size;
+
 		App::run(Settings {
 			default_font: Some(include_bytes!(
 				"../../assets/fonts/Poppins/Poppins-Regular.ttf"
 			)),
 			antialiasing: true,
-			default_text_size: 24,
+			default_text_size: text_size.0,
 			..Settings::default()
 		})
 	}
 }
 
-impl Sandbox for App {
+impl Application for App {
+	type Executor = executor::Default;
 	type Message = Message;
+	type Flags = ();
 
-	fn new() -> Self {
-		App {
-			scenes: Scenes::new(),
-			theme: Theme::Light,
-		}
+	fn new(_flags: ()) -> (Self, Command<Message>) {
+		let (config, error) = match Config::load() {
+			Ok(config) => {
+				let valid = config.validate();
+				(config, valid)
+			}
+			Err(e) => (Config::default(), Err(e)),
+		};
+
+		let mut scenes = Scenes::new();
+		scenes.broadcast(SceneMessage::ConfigChanged(config.clone()));
+
+		(
+			App {
+				scenes,
+				theme: config.theme(),
+				config,
+				project: None,
+				recent: RecentProjects::load()
+					.map(|mut recent| {
+						recent.prune();
+						recent
+					})
+					.unwrap_or_default(),
+				shortcuts_toggle: false,
+				error,
+			},
+			Command::none(),
+		)
 	}
 
 	fn title(&self) -> String {
-		format!("{} - iDrop", self.scenes.title())
+		match &self.project {
+			Some(project) => format!(
+				"{} - {} - iDrop",
+				self.scenes.title(),
+				project.manifest.name
+			),
+			None => format!("{} - iDrop", self.scenes.title()),
+		}
 	}
 
-	fn update(&mut self, event: Message) {
+	fn update(&mut self, event: Message) -> Command<Message> {
 		match event {
-			Message::BackPressed => {
-				if self.scenes.has_previous() {
-					self.scenes.current -= 1;
+			Message::SceneSelected(index) => self.scenes.select(index),
+			Message::OpenProject => {
+				if let Ok(Some(dir)) = FileDialog::new().show_open_single_dir() {
+					self.error = self.open_project(dir);
 				}
 			}
-			Message::NextPressed => {
-				if self.scenes.can_continue() {
-					self.scenes.current += 1;
+			Message::RecentProjectSelected(recent) => self.error = self.open_project(recent.0),
+			Message::SaveProject => match &self.project {
+				Some(project) => self.error = self.save_project(project.dir.clone()),
+				None => return self.update(Message::SaveProjectAs),
+			},
+			Message::SaveProjectAs => {
+				if let Ok(Some(dir)) = FileDialog::new().show_open_single_dir() {
+					self.error = self.save_project(dir);
 				}
 			}
+			Message::CloseProject => {
+				log::info!("Closed the project");
+				set_project_dir(None);
+				self.project = None;
+				self.reload_scenes();
+				self.error = Ok(());
+			}
+			Message::DarkThemeToggled(dark) => {
+				self.error = self.set_theme(if dark {
+					ThemePreference::Dark
+				} else {
+					ThemePreference::Light
+				});
+			}
+			Message::FollowSystemToggled(follow) => {
+				self.error = self.set_theme(match (follow, self.theme) {
+					(true, _) => ThemePreference::System,
+					(false, Theme::Dark) => ThemePreference::Dark,
+					(false, _) => ThemePreference::Light,
+				});
+			}
+			Message::PaletteSelected(name) => {
+				self.error = self.set_theme(ThemePreference::Custom(name));
+			}
+			Message::HighContrastToggled(high_contrast) => {
+				self.error = self.apply_config(self.config.clone().high_contrast(high_contrast));
+			}
+			Message::SystemThemeChecked => self.theme = self.config.theme(),
+			Message::UiScaleSelected(scale) => {
+				self.error = self.apply_config(Config {
+					ui_scale: scale,
+					..self.config.clone()
+				});
+			}
+			Message::ShortcutsToggled(value) => self.shortcuts_toggle = value,
+			Message::Shortcut(shortcut) => return self.shortcut(shortcut),
+			Message::FileDropped(path) => {
+				log::info!("Dropped {}", path.display());
+				self.error = if path.join(MANIFEST).exists() {
+					self.open_project(path)
+				} else {
+					self.scenes.drop_file(path)
+				};
+			}
+			Message::SceneMessage(SceneMessage::SaveSettings(config)) => {
+				self.error = self.apply_config(config);
+			}
 			Message::SceneMessage(scene_event) => self.scenes.update(scene_event),
 		}
+
+		Command::none()
+	}
+
+	fn subscription(&self) -> Subscription<Message> {
+		let mut subscriptions = vec![
+			self.scenes.subscription().map(Message::SceneMessage),
+			iced_native::subscription::events_with(shortcuts::listen),
+			iced_native::subscription::events_with(|event, _status| match event {
+				Event::Window(window::Event::FileDropped(path)) => Some(Message::FileDropped(path)),
+				_ => None,
+			}),
+		];
+
+		// the desktop has no change notification we can listen to, so poll it
+		if self.config.theme == ThemePreference::System {
+			subscriptions.push(
+				iced::time::every(Duration::from_secs(5)).map(|_| Message::SystemThemeChecked),
+			);
+		}
+
+		Subscription::batch(subscriptions)
+	}
+
+	fn background_color(&self) -> Color {
+		self.theme.palette().background
+	}
+
+	fn scale_factor(&self) -> f64 {
+		self.config.ui_scale.factor()
 	}
 
 	fn view(&self) -> iced::pure::Element<'_, Self::Message> {
@@ -61,33 +194,288 @@ impl Sandbox for App {
 
 		let theme = self.theme;
 
-		let mut controls = row().push(horizontal_space(Length::Fill));
+		let sidebar = scenes.list.iter().enumerate().fold(
+			column()
+				.spacing(10)
+				.padding(20)
+				.width(Length::Units(300))
+				.push(logo(40, ContentFit::Contain)),
+			|sidebar, (index, scene)| {
+				sidebar.push(nav_btn(
+					theme,
+					scene.icon(),
+					scene.title(),
+					index == scenes.current,
+					Message::SceneSelected(index),
+				))
+			},
+		);
 
-		if scenes.has_previous() {
-			controls = controls
-				.push(btn("Back", Message::BackPressed))
-				.push(horizontal_space(Length::Units(15)));
+		let mut content = column()
+			.push(self.project_bar())
+			.push(scenes.view(theme).map(Message::SceneMessage));
+
+		// appearance is applied by the app, so the settings scene leaves it out
+		if matches!(scenes.list[scenes.current], Scene::Settings(_)) {
+			content = content.push(self.appearance());
 		}
 
-		if scenes.can_continue() {
-			controls = controls.push(button("Next").style(theme).on_press(Message::NextPressed));
+		let content: Element<_> = content.max_width(540).spacing(20).padding(20).into();
+
+		let scrollable = scrollable(container(content).width(Length::Fill).center_x());
+
+		container(row().push(sidebar).push(scrollable))
+			.width(Length::Fill)
+			.height(Length::Fill)
+			.style(theme)
+			.into()
+	}
+}
+
+// -------------------- THEME
+impl App {
+	fn set_theme(&mut self, preference: ThemePreference) -> Result<(), Box<dyn Error>> {
+		self.apply_config(Config {
+			theme: preference,
+			..self.config.clone()
+		})
+	}
+
+	// Saves a new config and hands it to the scenes, which apply what they use
+	fn apply_config(&mut self, config: Config) -> Result<(), Box<dyn Error>> {
+		config.validate()?;
+
+		log::info!("Applying settings");
+		self.config = config;
+		self.theme = self.config.theme();
+		self.scenes
+			.broadcast(SceneMessage::ConfigChanged(self.config.clone()));
+
+		self.config.save()
+	}
+
+	fn appearance(&self) -> Element<'_, Message> {
+		let theme = self.theme;
+		let follow = self.config.theme == ThemePreference::System;
+		let selected = match &self.config.theme {
+			ThemePreference::Custom(name) => Some(name.clone()),
+			_ => None,
+		};
+
+		let mut toggles = row().spacing(20).push(
+			tglr(theme, "Follow system", follow, Message::FollowSystemToggled)
+				.width(Length::Shrink),
+		);
+
+		if !follow {
+			toggles = toggles
+				.push(
+					tglr(
+						theme,
+						"Dark theme",
+						theme == Theme::Dark,
+						Message::DarkThemeToggled,
+					)
+					.width(Length::Shrink),
+				)
+				.push(
+					tglr(
+						theme,
+						"High contrast",
+						theme == Theme::HighContrast,
+						Message::HighContrastToggled,
+					)
+					.width(Length::Shrink),
+				);
 		}
 
-		controls = controls
-			.push(horizontal_space(Length::Fill))
-			.push(logo(40, ContentFit::Contain));
-		// .push(horizontal_space(Length::Fill));
+		let mut view = column().spacing(10).push(toggles).push(
+			row()
+				.push(text("Interface size"))
+				.push(horizontal_space(Length::Fill))
+				.push(
+					drop_down(
+						theme,
+						&UiScale::ALL[..],
+						Some(self.config.ui_scale),
+						Message::UiScaleSelected,
+					)
+					.width(Length::Units(200)),
+				)
+				.align_items(Alignment::Center),
+		);
 
-		let content: Element<_> = column()
-			.push(scenes.view().map(Message::SceneMessage))
-			.push(controls)
-			.max_width(540)
-			.spacing(20)
-			.padding(20)
-			.into();
+		if !self.config.palettes.is_empty() {
+			let names: Vec<String> = self
+				.config
+				.palettes
+				.iter()
+				.map(|custom| custom.name.clone())
+				.collect();
 
-		let scrollable = scrollable(container(content).width(Length::Fill).center_x());
+			view = view.push(
+				row()
+					.push(text("Custom palette"))
+					.push(horizontal_space(Length::Fill))
+					.push(
+						drop_down(theme, names, selected, Message::PaletteSelected)
+							.placeholder("Choose a palette...")
+							.width(Length::Units(200)),
+					)
+					.align_items(Alignment::Center),
+			);
+		}
+
+		if let Theme::Custom(palette) = theme {
+			for warning in palette.readability_warnings() {
+				view = view.push(text(warning).size(16));
+			}
+		}
+
+		view = view.push(
+			tglr(
+				theme,
+				"Keyboard shortcuts",
+				self.shortcuts_toggle,
+				Message::ShortcutsToggled,
+			)
+			.width(Length::Shrink),
+		);
+
+		if self.shortcuts_toggle {
+			// the scene moves the focus itself, so Tab is listed without being a shortcut
+			let keys = Shortcut::ALL
+				.iter()
+				.map(|shortcut| (shortcut.to_string(), shortcut.keys()))
+				.chain([(String::from("Next / previous field"), "Tab / Shift+Tab")]);
+
+			for (action, keys) in keys {
+				view = view.push(
+					row()
+						.push(text(action).size(16))
+						.push(horizontal_space(Length::Fill))
+						.push(text(keys).size(16).font(BOLD)),
+				);
+			}
+		}
+
+		view.into()
+	}
+}
+
+// -------------------- SHORTCUTS
+impl App {
+	fn shortcut(&mut self, shortcut: Shortcut) -> Command<Message> {
+		let prediction = |scene: &Scene| matches!(scene, Scene::Prediction(_));
+		let training = |scene: &Scene| matches!(scene, Scene::Training(_));
+
+		match shortcut {
+			Shortcut::Back => self.scenes.step(false),
+			Shortcut::Next => self.scenes.step(true),
+			Shortcut::Run => self.scenes.update(SceneMessage::GoPressed),
+			Shortcut::Predict => self.scenes.show(prediction, SceneMessage::GoPressed),
+			Shortcut::Train => self.scenes.show(training, SceneMessage::GoPressed),
+			Shortcut::SelectModel => self.scenes.show(prediction, SceneMessage::SelectModel),
+			Shortcut::SelectTrainingData => self.scenes.show(training, SceneMessage::SelectCSV),
+			Shortcut::OpenProject => return self.update(Message::OpenProject),
+			Shortcut::SaveProject => return self.update(Message::SaveProject),
+			Shortcut::SaveProjectAs => return self.update(Message::SaveProjectAs),
+		}
+
+		Command::none()
+	}
+}
+
+// -------------------- PROJECTS
+impl App {
+	// Scenes read their state from the data directory when created, and the config from the app
+	fn reload_scenes(&mut self) {
+		let current = self.scenes.current;
+		self.scenes = Scenes::new();
+		self.scenes
+			.broadcast(SceneMessage::ConfigChanged(self.config.clone()));
+		self.scenes.current = current;
+	}
+
+	fn remember(&mut self, project: Project) -> Result<(), Box<dyn Error>> {
+		set_project_dir(Some(project.dir.clone()));
+		self.recent.add(&project.dir);
+		self.project = Some(project);
+
+		self.recent.save()
+	}
+
+	fn open_project(&mut self, dir: PathBuf) -> Result<(), Box<dyn Error>> {
+		let project = Project::open(&dir)?;
+		log::info!("Opened project {}", dir.display());
+		self.remember(project)?;
+		self.reload_scenes();
+
+		Ok(())
+	}
+
+	/*
+	 * The scenes keep their state, which is now written to the project, with
+	 * the models of this session and those the open project already has.
+	 */
+	fn save_project(&mut self, dir: PathBuf) -> Result<(), Box<dyn Error>> {
+		let mut models = self.scenes.models();
+		if let Some(project) = &self.project {
+			models.extend(
+				project
+					.manifest
+					.models
+					.iter()
+					.map(|entry| project.dir.join(&entry.model)),
+			);
+		}
+
+		let project = Project::save(&dir, &data_dir(), &models)?;
+		log::info!("Saved project {}", dir.display());
+		self.remember(project)
+	}
+
+	fn project_bar(&self) -> Element<'_, Message> {
+		let theme = self.theme;
+		let name = match &self.project {
+			Some(project) => project.manifest.name.clone(),
+			None => String::from("No project"),
+		};
+
+		let mut buttons = row()
+			.spacing(10)
+			.push(btn(theme, "Open", Message::OpenProject))
+			.push(btn(theme, "Save", Message::SaveProject))
+			.push(btn(theme, "Save as", Message::SaveProjectAs));
+
+		if self.project.is_some() {
+			buttons = buttons.push(btn(theme, "Close", Message::CloseProject));
+		}
+
+		let mut bar = column()
+			.spacing(10)
+			.push(
+				row()
+					.push(text(name).font(BOLD))
+					.push(horizontal_space(Length::Fill))
+					.push(
+						drop_down(
+							theme,
+							&self.recent.0[..],
+							None,
+							Message::RecentProjectSelected,
+						)
+						.placeholder("Recent projects")
+						.width(Length::Units(200)),
+					)
+					.align_items(Alignment::Center),
+			)
+			.push(buttons);
+
+		if let Err(e) = &self.error {
+			bar = bar.push(text(e.to_string()).size(16));
+		}
 
-		container(scrollable).center_y().into()
+		bar.into()
 	}
 }
@@ -2,6 +2,8 @@
 use iced_style::Color;
 use lazy_static::lazy_static;
 use palette::{FromColor, Hsl, Mix, RelativeContrast, Srgb};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Palette {
@@ -56,8 +58,97 @@ impl Palette {
 			0x3F as f32 / 255.0,
 		),
 	};
+
+	/*
+	 * White on black, with the light palette's accents lightened until they
+	 * stand out from the background as much as text has to.
+	 */
+	pub fn high_contrast() -> Self {
+		let accent = |mut color: Color| {
+			while !is_readable(color, Color::BLACK) && to_hsl(color).lightness < 1.0 {
+				color = lighten(color, 0.05);
+			}
+			color
+		};
+
+		Self {
+			background: Color::BLACK,
+			text: Color::WHITE,
+			primary: accent(Self::LIGHT.primary),
+			success: accent(Self::LIGHT.success),
+			danger: accent(Self::LIGHT.danger),
+		}
+	}
+
+	// One line per color the text is hard to read on
+	pub fn readability_warnings(&self) -> Vec<String> {
+		[
+			("background", self.background),
+			("primary", self.primary),
+			("success", self.success),
+			("danger", self.danger),
+		]
+		.into_iter()
+		.filter(|(_, color)| !is_readable(*color, self.text))
+		.map(|(name, color)| {
+			format!(
+				"Text is hard to read on the {name} color, {} text is used on it instead",
+				if is_dark(color) { "white" } else { "black" }
+			)
+		})
+		.collect()
+	}
 }
 
+// Palette as written in the config file, colors as "#rrggbb" or "#rgb"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomPalette {
+	pub name: String,
+	pub background: String,
+	pub text: String,
+	pub primary: String,
+	pub success: String,
+	pub danger: String,
+}
+
+impl CustomPalette {
+	pub fn palette(&self) -> Result<Palette, Box<dyn Error>> {
+		let color = |field: &str, value: &str| {
+			parse_hex(value).ok_or_else(|| {
+				format!(
+					"Palette {}: {field} `{value}` is not a hex color",
+					self.name
+				)
+			})
+		};
+
+		Ok(Palette {
+			background: color("background", &self.background)?,
+			text: color("text", &self.text)?,
+			primary: color("primary", &self.primary)?,
+			success: color("success", &self.success)?,
+			danger: color("danger", &self.danger)?,
+		})
+	}
+}
+
+pub fn parse_hex(value: &str) -> Option<Color> {
+	let hex = value.trim().strip_prefix('#')?;
+	let digits: Vec<u8> = hex
+		.chars()
+		.map(|c| c.to_digit(16).map(|d| d as u8))
+		.collect::<Option<_>>()?;
+
+	let [r, g, b] = match digits[..] {
+		[r, g, b] => [r * 17, g * 17, b * 17],
+		[r1, r2, g1, g2, b1, b2] => [r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2],
+		_ => return None,
+	};
+
+	Some(Color::from_rgb8(r, g, b))
+}
+
+#[derive(Debug, Clone, Copy)]
 pub struct Extended {
 	pub background: Background,
 	pub primary: Primary,
@@ -70,6 +161,7 @@ pub struct Extended {
 lazy_static! {
 	pub static ref EXTENDED_LIGHT: Extended = Extended::generate(Palette::LIGHT);
 	pub static ref EXTENDED_DARK: Extended = Extended::generate(Palette::DARK);
+	pub static ref EXTENDED_HIGH_CONTRAST: Extended = Extended::generate(Palette::high_contrast());
 }
 
 impl Extended {
@@ -99,6 +191,7 @@ impl Pair {
 	}
 }
 
+#[derive(Debug, Clone, Copy)]
 pub struct Background {
 	pub base: Pair,
 	pub weak: Pair,
@@ -118,6 +211,7 @@ impl Background {
 	}
 }
 
+#[derive(Debug, Clone, Copy)]
 pub struct Primary {
 	pub base: Pair,
 	pub weak: Pair,
@@ -137,6 +231,7 @@ impl Primary {
 	}
 }
 
+#[derive(Debug, Clone, Copy)]
 pub struct Secondary {
 	pub base: Pair,
 	pub weak: Pair,
@@ -157,6 +252,7 @@ impl Secondary {
 	}
 }
 
+#[derive(Debug, Clone, Copy)]
 pub struct Success {
 	pub base: Pair,
 	pub weak: Pair,
@@ -176,6 +272,7 @@ impl Success {
 	}
 }
 
+#[derive(Debug, Clone, Copy)]
 pub struct Danger {
 	pub base: Pair,
 	pub weak: Pair,
@@ -263,3 +360,47 @@ fn to_hsl(color: Color) -> Hsl {
 fn from_hsl(hsl: Hsl) -> Color {
 	Srgb::from_color(hsl).into()
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn custom_palettes() {
+		assert_eq!(parse_hex("#fff"), Some(Color::WHITE));
+		assert_eq!(parse_hex("#000000"), Some(Color::BLACK));
+		assert_eq!(parse_hex("#12345"), None);
+		assert_eq!(parse_hex("ffffff"), None);
+
+		let custom = CustomPalette {
+			name: String::from("Institute"),
+			background: String::from("#ffffff"),
+			text: String::from("#000000"),
+			primary: String::from("#003366"),
+			success: String::from("#7fe0b0"),
+			danger: String::from("#ff9090"),
+		};
+		let palette = custom.palette().unwrap();
+
+		// black text on dark blue is flagged, and swapped for white
+		let warnings = palette.readability_warnings();
+		assert_eq!(warnings.len(), 1);
+		assert!(warnings[0].contains("primary"));
+		assert_eq!(Extended::generate(palette).primary.base.text, Color::WHITE);
+
+		let high_contrast = Palette::high_contrast();
+		for accent in [
+			high_contrast.primary,
+			high_contrast.success,
+			high_contrast.danger,
+		] {
+			assert!(is_readable(accent, high_contrast.background));
+		}
+
+		let broken = CustomPalette {
+			primary: String::from("blue"),
+			..custom
+		};
+		assert!(broken.palette().is_err());
+	}
+}
@@ -0,0 +1,187 @@
+// Application preferences, kept in the platform's config directory
+use serde::{Deserialize, Serialize};
+use std::{
+	error::Error,
+	fs,
+	path::{Path, PathBuf},
+};
+
+use crate::{
+	colors::CustomPalette,
+	storage::{load_json, save_json, user_dir},
+	styling::{system_theme, TextSize, Theme, ThemePreference, UiScale},
+	units::{FlowUnit, LengthUnit},
+	views::prediction_ui::Device,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+	pub theme: ThemePreference,
+	// restored when high contrast is turned off
+	pub before_high_contrast: Option<ThemePreference>,
+	pub palettes: Vec<CustomPalette>,
+	pub ui_scale: UiScale,
+	// read at launch, so changes show after a restart
+	pub text_size: TextSize,
+	// where models are saved and looked for first
+	pub model_dir: Option<PathBuf>,
+	pub flow_unit: FlowUnit,
+	pub length_unit: LengthUnit,
+	// selected when the prediction scene opens
+	pub default_device: Option<Device>,
+	// whose packages training uses, instead of the embedded Python's
+	pub python: Option<PathBuf>,
+}
+
+impl Config {
+	// e.g. ~/.config/iDrop/config.json on Linux
+	pub fn path() -> PathBuf {
+		dirs_next::config_dir()
+			.map(|dir| dir.join("iDrop"))
+			.unwrap_or_else(user_dir)
+			.join("config.json")
+	}
+
+	pub fn load() -> Result<Self, Box<dyn Error>> {
+		load_json(&Self::path())
+	}
+
+	pub fn save(&self) -> Result<(), Box<dyn Error>> {
+		save_json(&Self::path(), self)
+	}
+
+	// Reads a config exported from another machine, which must be valid
+	pub fn import(path: &Path) -> Result<Self, Box<dyn Error>> {
+		let config: Config = serde_json::from_str(&fs::read_to_string(path)?)?;
+		config.validate()?;
+
+		Ok(config)
+	}
+
+	pub fn export(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+		save_json(path, self)
+	}
+
+	// Palettes with bad colors or a duplicate name, unusable sizes and missing paths
+	pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+		if !(50..=300).contains(&self.ui_scale.0) {
+			return Err(format!("UI scale {} is not between 50% and 300%", self.ui_scale).into());
+		}
+
+		if !(12..=48).contains(&self.text_size.0) {
+			return Err(format!("Text size {} is not between 12 and 48 px", self.text_size).into());
+		}
+
+		if let Some(dir) = &self.model_dir {
+			if !dir.is_dir() {
+				return Err(format!("Model directory {} does not exist", dir.display()).into());
+			}
+		}
+
+		if let Some(python) = &self.python {
+			if !python.is_file() {
+				return Err(
+					format!("Python interpreter {} does not exist", python.display()).into(),
+				);
+			}
+		}
+
+		for (i, custom) in self.palettes.iter().enumerate() {
+			custom.palette()?;
+
+			if self.palettes[..i]
+				.iter()
+				.any(|other| other.name == custom.name)
+			{
+				return Err(format!("Palette {} is defined twice", custom.name).into());
+			}
+		}
+
+		Ok(())
+	}
+
+	// Theme to draw with, Light if the chosen custom palette is missing or invalid
+	pub fn theme(&self) -> Theme {
+		match &self.theme {
+			ThemePreference::Light => Theme::Light,
+			ThemePreference::Dark => Theme::Dark,
+			ThemePreference::HighContrast => Theme::HighContrast,
+			ThemePreference::System => system_theme().unwrap_or_default(),
+			ThemePreference::Custom(name) => self
+				.palettes
+				.iter()
+				.find(|custom| &custom.name == name)
+				.and_then(|custom| custom.palette().ok())
+				.map_or(Theme::Light, Theme::Custom),
+		}
+	}
+
+	// Turning high contrast off goes back to the theme chosen before it, or Light
+	pub fn high_contrast(self, on: bool) -> Self {
+		let (theme, before_high_contrast) = match (on, self.theme) {
+			(true, ThemePreference::HighContrast) => {
+				(ThemePreference::HighContrast, self.before_high_contrast)
+			}
+			(true, before) => (ThemePreference::HighContrast, Some(before)),
+			(false, ThemePreference::HighContrast) => (
+				self.before_high_contrast.unwrap_or(ThemePreference::Light),
+				None,
+			),
+			(false, theme) => (theme, self.before_high_contrast),
+		};
+
+		Config {
+			theme,
+			before_high_contrast,
+			..self
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn imports_exported_config() {
+		let path = std::env::temp_dir().join("idrop-config-test.json");
+
+		let config = Config {
+			flow_unit: FlowUnit::MicrolitresPerHour,
+			default_device: Some(Device::CH190),
+			text_size: TextSize(18),
+			..Config::default()
+		};
+		config.export(&path).unwrap();
+		assert_eq!(Config::import(&path).unwrap(), config);
+
+		// an invalid config is refused rather than applied
+		Config {
+			model_dir: Some(PathBuf::from("/no/such/models")),
+			..config
+		}
+		.export(&path)
+		.unwrap();
+		assert!(Config::import(&path).is_err());
+	}
+
+	#[test]
+	fn restores_theme_after_high_contrast() {
+		let config = Config {
+			theme: ThemePreference::Custom(String::from("Lab")),
+			..Config::default()
+		};
+
+		let high_contrast = config.clone().high_contrast(true).high_contrast(true);
+		assert_eq!(high_contrast.theme, ThemePreference::HighContrast);
+		assert_eq!(high_contrast.high_contrast(false), config);
+
+		// a config saved in high contrast before this was remembered
+		let saved = Config {
+			theme: ThemePreference::HighContrast,
+			..Config::default()
+		};
+		assert_eq!(saved.high_contrast(false).theme, ThemePreference::Light);
+	}
+}
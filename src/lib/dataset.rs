@@ -0,0 +1,226 @@
+// Training data as consumed by `create_model.py`: dim_a,dim_b,flow1,flow2,freq
+use std::{error::Error, fmt::Write, fs, path::Path};
+
+use crate::units::{parse_symbol, FlowUnit, FrequencyUnit, LengthUnit, Units};
+
+pub const HEADER: [&str; 5] = ["dim_a", "dim_b", "flow1", "flow2", "freq"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+	pub dim_a: f32, // µm
+	pub dim_b: f32, // µm
+	pub flow1: f32, // µL/min, continuous phase
+	pub flow2: f32, // µL/min, dispersed phase
+	pub freq: f32,  // Hz
+}
+
+impl Sample {
+	pub fn inputs(&self) -> [f32; 3] {
+		[self.dim_a, self.dim_b, self.freq]
+	}
+
+	// In the order of HEADER
+	pub fn columns(&self) -> [f32; 5] {
+		[self.dim_a, self.dim_b, self.flow1, self.flow2, self.freq]
+	}
+}
+
+// (min, max) of dim_a, dim_b and freq, the region a model was trained on
+pub fn input_bounds(samples: &[Sample]) -> Option<[(f32, f32); 3]> {
+	let first = samples.first()?.inputs();
+	let mut bounds = first.map(|value| (value, value));
+
+	for sample in samples {
+		for (bound, value) in bounds.iter_mut().zip(sample.inputs()) {
+			*bound = (bound.0.min(value), bound.1.max(value));
+		}
+	}
+
+	Some(bounds)
+}
+
+// Column names with their units, e.g. `dim_a [µm]`
+pub fn header(units: &Units) -> String {
+	HEADER
+		.iter()
+		.zip(units.symbols())
+		.map(|(name, symbol)| format!("{name} [{symbol}]"))
+		.collect::<Vec<_>>()
+		.join(",")
+}
+
+// Samples are in the base units, which the header records
+pub fn to_csv(samples: &[Sample]) -> String {
+	let mut csv = header(&Units::default());
+
+	for sample in samples {
+		let Sample {
+			dim_a,
+			dim_b,
+			flow1,
+			flow2,
+			freq,
+		} = sample;
+		let _ = write!(csv, "\n{dim_a},{dim_b},{flow1},{flow2},{freq}");
+	}
+
+	csv
+}
+
+pub fn write_csv(path: &Path, samples: &[Sample]) -> Result<(), Box<dyn Error>> {
+	fs::write(path, to_csv(samples))?;
+	Ok(())
+}
+
+pub fn read_csv(path: &Path) -> Result<Vec<Sample>, Box<dyn Error>> {
+	parse_csv(&fs::read_to_string(path)?)
+}
+
+/*
+ * Columns are looked up by name so their order in the file does not matter.
+ * A column may give its unit in brackets, `dim_a [mm]`, values are converted
+ * to the base units. Columns without one are taken to be in the base units.
+ */
+pub fn parse_csv(contents: &str) -> Result<Vec<Sample>, Box<dyn Error>> {
+	Ok(parse_columns(contents, HEADER)?
+		.into_iter()
+		.map(|[dim_a, dim_b, flow1, flow2, freq]| Sample {
+			dim_a,
+			dim_b,
+			flow1,
+			flow2,
+			freq,
+		})
+		.collect())
+}
+
+// Inputs of predictions, (dim_a, dim_b, freq), from a CSV with or without flows
+pub fn read_inputs(path: &Path) -> Result<Vec<[f32; 3]>, Box<dyn Error>> {
+	parse_columns(&fs::read_to_string(path)?, INPUTS)
+}
+
+// Whether a CSV with the input columns also has the flows training needs
+pub fn has_flows(path: &Path) -> Result<bool, Box<dyn Error>> {
+	let contents = fs::read_to_string(path)?;
+	let names: Vec<&str> = contents
+		.lines()
+		.next()
+		.ok_or("CSV file is empty")?
+		.split(',')
+		.map(column_name)
+		.collect();
+
+	if let Some(missing) = INPUTS.iter().find(|name| !names.contains(name)) {
+		return Err(format!("CSV file has no `{missing}` column").into());
+	}
+
+	Ok(names.contains(&"flow1") && names.contains(&"flow2"))
+}
+
+const INPUTS: [&str; 3] = ["dim_a", "dim_b", "freq"];
+
+// `dim_a [mm]` -> `dim_a`
+fn column_name(header: &str) -> &str {
+	header.split('[').next().unwrap_or("").trim()
+}
+
+// Values of the named columns on every row, in the base units
+fn parse_columns<const N: usize>(
+	contents: &str,
+	names: [&str; N],
+) -> Result<Vec<[f32; N]>, Box<dyn Error>> {
+	let mut lines = contents.lines().filter(|line| !line.trim().is_empty());
+
+	let header: Vec<&str> = lines
+		.next()
+		.ok_or("CSV file is empty")?
+		.split(',')
+		.map(str::trim)
+		.collect();
+
+	let mut columns = Vec::with_capacity(N);
+	for name in names {
+		let column = header
+			.iter()
+			.position(|h| column_name(h) == name)
+			.ok_or_else(|| format!("CSV file has no `{name}` column"))?;
+
+		let symbol = header[column]
+			.split_once('[')
+			.map(|(_, unit)| unit.trim_end_matches(']'));
+
+		columns.push((column, converter(name, symbol)?));
+	}
+
+	lines
+		.enumerate()
+		.map(|(i, line)| {
+			let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+			let mut row = [0.0; N];
+
+			for (value, (column, to_base)) in row.iter_mut().zip(&columns) {
+				*value = fields
+					.get(*column)
+					.and_then(|field| field.parse::<f32>().ok())
+					.map(to_base)
+					.ok_or_else(|| format!("invalid value on row {}", i + 1))?;
+			}
+
+			Ok(row)
+		})
+		.collect()
+}
+
+type ToBase = Box<dyn Fn(f32) -> f32>;
+
+// Conversion of a column's values to the base unit, which it is in without a symbol
+fn converter(name: &str, symbol: Option<&str>) -> Result<ToBase, Box<dyn Error>> {
+	fn unit<T: Copy + Default + std::fmt::Display + 'static>(
+		all: &[T],
+		name: &str,
+		symbol: Option<&str>,
+		to_base: fn(T, f32) -> f32,
+	) -> Result<ToBase, Box<dyn Error>> {
+		let unit = match symbol {
+			Some(symbol) => parse_symbol(all, symbol)
+				.ok_or_else(|| format!("`{name}` has the unknown unit {symbol}"))?,
+			None => T::default(),
+		};
+
+		Ok(Box::new(move |value| to_base(unit, value)))
+	}
+
+	match name {
+		"dim_a" | "dim_b" => unit(&LengthUnit::ALL, name, symbol, LengthUnit::to_base),
+		"flow1" | "flow2" => unit(&FlowUnit::ALL, name, symbol, FlowUnit::to_base),
+		_ => unit(&FrequencyUnit::ALL, name, symbol, FrequencyUnit::to_base),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn converts_columns_to_base_units() {
+		let csv = "freq [kHz],dim_b [mm],dim_a,flow1 [uL/h],flow2\n0.5,0.08,120,600,5";
+		let samples = parse_csv(csv).unwrap();
+
+		assert_eq!(samples[0].dim_a, 120.0);
+		assert_eq!(samples[0].dim_b, 80.0);
+		assert_eq!(samples[0].freq, 500.0);
+		assert_eq!(samples[0].flow1, 10.0);
+		assert_eq!(samples[0].flow2, 5.0);
+
+		// written back in the base units, in the order training expects
+		assert!(to_csv(&samples).starts_with("dim_a [µm],dim_b [µm],flow1 [µL/min]"));
+		assert_eq!(parse_csv(&to_csv(&samples)).unwrap(), samples);
+
+		assert!(parse_csv("dim_a [in],dim_b,flow1,flow2,freq\n1,2,3,4,5").is_err());
+
+		assert_eq!(
+			parse_columns("freq,dim_b,dim_a [mm]\n200,80,0.12", INPUTS).unwrap(),
+			[[120.0, 80.0, 200.0]]
+		);
+	}
+}
@@ -0,0 +1,227 @@
+// Log of measured experiments, aggregated into training data per device
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::BTreeMap,
+	error::Error,
+	path::{Path, PathBuf},
+};
+
+use crate::{
+	dataset::{read_csv, write_csv, Sample},
+	storage::{data_dir, load_json, save_json},
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Run {
+	pub session: String,
+	pub device: String,
+	pub flow1: f32, // µL/min, continuous phase
+	pub flow2: f32, // µL/min, dispersed phase
+	pub dim_a: f32, // µm
+	pub dim_b: f32, // µm
+	pub freq: f32,  // Hz
+	// files the run was measured or imported from, empty when typed in
+	pub sources: Vec<PathBuf>,
+}
+
+impl Run {
+	pub fn sample(&self) -> Sample {
+		Sample {
+			dim_a: self.dim_a,
+			dim_b: self.dim_b,
+			flow1: self.flow1,
+			flow2: self.flow2,
+			freq: self.freq,
+		}
+	}
+
+	// Values rounded to 0.001, runs with the same key are duplicates
+	fn key(&self) -> [i64; 5] {
+		[self.dim_a, self.dim_b, self.flow1, self.flow2, self.freq]
+			.map(|value| (value * 1000.0).round() as i64)
+	}
+}
+
+// Where a row of the exported training data came from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Provenance {
+	pub session: String,
+	pub sources: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+	pub sample: Sample,
+	pub provenance: Vec<Provenance>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExperimentLog {
+	pub runs: Vec<Run>,
+}
+
+impl ExperimentLog {
+	pub fn path() -> PathBuf {
+		data_dir().join("experiments.json")
+	}
+
+	pub fn load() -> Result<Self, Box<dyn Error>> {
+		load_json(&Self::path())
+	}
+
+	pub fn save(&self) -> Result<(), Box<dyn Error>> {
+		save_json(&Self::path(), self)
+	}
+
+	// Adds a run unless the same session already logged it, returns if added
+	pub fn add(&mut self, run: Run) -> bool {
+		let duplicate = self.runs.iter().any(|logged| {
+			logged.session == run.session
+				&& logged.device == run.device
+				&& logged.key() == run.key()
+		});
+
+		if !duplicate {
+			self.runs.push(run);
+		}
+
+		!duplicate
+	}
+
+	pub fn remove(&mut self, index: usize) {
+		if index < self.runs.len() {
+			self.runs.remove(index);
+		}
+	}
+
+	// Every row of a training CSV becomes a run of the session, returns how many were added
+	pub fn import_csv(
+		&mut self,
+		path: &Path,
+		device: &str,
+		session: &str,
+	) -> Result<usize, Box<dyn Error>> {
+		let mut added = 0;
+
+		for sample in read_csv(path)? {
+			let run = Run {
+				session: session.to_owned(),
+				device: device.to_owned(),
+				flow1: sample.flow1,
+				flow2: sample.flow2,
+				dim_a: sample.dim_a,
+				dim_b: sample.dim_b,
+				freq: sample.freq,
+				sources: vec![path.to_owned()],
+			};
+
+			if self.add(run) {
+				added += 1;
+			}
+		}
+
+		Ok(added)
+	}
+
+	pub fn sessions(&self, device: &str) -> Vec<String> {
+		let mut sessions: Vec<String> = self
+			.runs
+			.iter()
+			.filter(|run| run.device == device)
+			.map(|run| run.session.clone())
+			.collect();
+
+		sessions.sort();
+		sessions.dedup();
+		sessions
+	}
+
+	/*
+	 * All sessions of a device merged into one set of samples. Runs with the
+	 * same values, for example one experiment imported twice, become a single
+	 * entry that keeps the provenance of each of them.
+	 */
+	pub fn aggregate(&self, device: &str) -> Vec<Entry> {
+		let mut entries: BTreeMap<[i64; 5], Entry> = BTreeMap::new();
+
+		for run in self.runs.iter().filter(|run| run.device == device) {
+			let provenance = Provenance {
+				session: run.session.clone(),
+				sources: run.sources.clone(),
+			};
+
+			let entry = entries.entry(run.key()).or_insert_with(|| Entry {
+				sample: run.sample(),
+				provenance: Vec::new(),
+			});
+
+			if !entry.provenance.contains(&provenance) {
+				entry.provenance.push(provenance);
+			}
+		}
+
+		entries.into_values().collect()
+	}
+
+	/*
+	 * Writes the CSV `create_model` trains on, with the provenance of every
+	 * row next to it in `name.json`. Returns the number of rows.
+	 */
+	pub fn export(&self, device: &str, path: &Path) -> Result<usize, Box<dyn Error>> {
+		let entries = self.aggregate(device);
+		if entries.is_empty() {
+			return Err(format!("No runs logged for device {device}").into());
+		}
+
+		let samples: Vec<Sample> = entries.iter().map(|entry| entry.sample).collect();
+		let provenance: Vec<&Vec<Provenance>> =
+			entries.iter().map(|entry| &entry.provenance).collect();
+
+		write_csv(path, &samples)?;
+		save_json(&path.with_extension("json"), &provenance)?;
+
+		Ok(entries.len())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn run(session: &str, dim_a: f32) -> Run {
+		Run {
+			session: session.to_owned(),
+			device: String::from("100"),
+			flow1: 10.0,
+			flow2: 5.0,
+			dim_a,
+			dim_b: 80.0,
+			freq: 200.0,
+			sources: vec![PathBuf::from(format!("{session}.png"))],
+		}
+	}
+
+	#[test]
+	fn deduplicates_and_merges_sessions() {
+		let mut log = ExperimentLog::default();
+
+		assert!(log.add(run("monday", 120.0)));
+		assert!(!log.add(run("monday", 120.0)));
+		assert!(log.add(run("monday", 130.0)));
+		assert!(log.add(run("tuesday", 120.0)));
+
+		assert_eq!(log.sessions("100"), ["monday", "tuesday"]);
+
+		let entries = log.aggregate("100");
+		assert_eq!(entries.len(), 2);
+
+		// the repeated experiment keeps both sessions as its sources
+		let repeated = entries
+			.iter()
+			.find(|entry| entry.sample.dim_a == 120.0)
+			.unwrap();
+		assert_eq!(repeated.provenance.len(), 2);
+
+		assert!(log.aggregate("190").is_empty());
+	}
+}
@@ -0,0 +1,897 @@
+/*
+ * Trained models as plain weights, for collaborators without an ONNX
+ * runtime: JSON, NumPy .npz, or a C header that runs the network on a pump
+ * controller. Each export comes with a Markdown model card.
+ */
+use serde::Serialize;
+use std::{
+	error::Error,
+	fmt::Write as _,
+	fs,
+	path::{Path, PathBuf},
+};
+use tract_onnx::{pb::ModelProto, prelude::*};
+
+use crate::{
+	dataset::{input_bounds, read_csv, Sample, HEADER},
+	history::file_hash,
+	ml::ModelInfo,
+	units::{format_value, Units},
+	views::prediction_ui::Device,
+};
+
+const INPUTS: [&str; 3] = ["dim_a", "dim_b", "freq"];
+const OUTPUTS: [&str; 2] = ["flow1", "flow2"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+	Json,
+	Npz,
+	CHeader,
+}
+
+impl ExportFormat {
+	pub const ALL: [Self; 3] = [ExportFormat::Json, ExportFormat::Npz, ExportFormat::CHeader];
+
+	pub fn extension(&self) -> &'static str {
+		match self {
+			ExportFormat::Json => "json",
+			ExportFormat::Npz => "npz",
+			ExportFormat::CHeader => "h",
+		}
+	}
+
+	// name.weights.json, as name.json is the model's info sidecar
+	pub fn file_name(&self, model: &Path) -> String {
+		let stem = model
+			.file_stem()
+			.and_then(|stem| stem.to_str())
+			.unwrap_or("model");
+
+		match self {
+			ExportFormat::Json => format!("{stem}.weights.json"),
+			_ => format!("{stem}.{}", self.extension()),
+		}
+	}
+}
+
+impl std::fmt::Display for ExportFormat {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				ExportFormat::Json => "JSON weights",
+				ExportFormat::Npz => "NumPy .npz",
+				ExportFormat::CHeader => "C header",
+			}
+		)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Activation {
+	Linear,
+	Relu,
+	Sigmoid,
+	Tanh,
+}
+
+impl std::fmt::Display for Activation {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				Activation::Linear => "linear",
+				Activation::Relu => "relu",
+				Activation::Sigmoid => "sigmoid",
+				Activation::Tanh => "tanh",
+			}
+		)
+	}
+}
+
+// Dense layer, outputs = activation(inputs · weights + bias)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+	pub inputs: usize,
+	pub outputs: usize,
+	// row-major, inputs × outputs
+	pub weights: Vec<f32>,
+	pub bias: Vec<f32>,
+	pub activation: Activation,
+}
+
+impl Layer {
+	fn rows(&self) -> impl Iterator<Item = &[f32]> {
+		self.weights.chunks(self.outputs)
+	}
+}
+
+// Layers of the dense network a model.onnx holds, as trained by iDrop
+pub fn read_layers(path: &Path) -> Result<Vec<Layer>, Box<dyn Error>> {
+	layers(&tract_onnx::onnx().proto_model_for_path(path)?)
+}
+
+/*
+ * Walks the graph's nodes in order, Keras' Dense layers being a MatMul (or
+ * a Gemm) with an Add of the bias and an optional activation.
+ */
+fn layers(model: &ModelProto) -> Result<Vec<Layer>, Box<dyn Error>> {
+	let graph = model.graph.as_ref().ok_or("The model has no graph")?;
+	let constant = |name: &str| -> Result<Option<Tensor>, Box<dyn Error>> {
+		match graph.initializer.iter().find(|tensor| tensor.name == name) {
+			Some(tensor) => Ok(Some(
+				Tensor::try_from(tensor)?.cast_to::<f32>()?.into_owned(),
+			)),
+			None => Ok(None),
+		}
+	};
+	let not_dense = |op: &str| format!("Only dense networks can be exported, the model has a {op}");
+
+	let mut layers: Vec<Layer> = Vec::new();
+
+	for node in &graph.node {
+		let op = node.op_type.as_str();
+
+		match op {
+			"MatMul" | "Gemm" => {
+				let weights = match node.input.get(1) {
+					Some(name) => constant(name)?,
+					None => None,
+				}
+				.ok_or_else(|| not_dense(op))?;
+
+				let mut weights = match weights.shape() {
+					[_, _] => weights,
+					_ => return Err(not_dense(op).into()),
+				};
+				if op == "Gemm" && node.get_attr_opt::<i64>("transB")? == Some(1) {
+					weights = weights.permute_axes(&[1, 0])?;
+				}
+
+				let (inputs, outputs) = (weights.shape()[0], weights.shape()[1]);
+				let bias = match node.input.get(2) {
+					Some(name) => {
+						constant(name)?.map(|bias| bias.as_slice::<f32>().map(<[f32]>::to_vec))
+					}
+					None => None,
+				}
+				.transpose()?
+				.unwrap_or_else(|| vec![0.0; outputs]);
+
+				layers.push(Layer {
+					inputs,
+					outputs,
+					weights: weights.as_slice::<f32>()?.to_vec(),
+					bias,
+					activation: Activation::Linear,
+				});
+			}
+			"Add" => {
+				let layer = layers.last_mut().ok_or_else(|| not_dense(op))?;
+				let bias = node
+					.input
+					.iter()
+					.find_map(|name| constant(name).transpose())
+					.transpose()?
+					.ok_or_else(|| not_dense(op))?;
+				let bias = bias.as_slice::<f32>()?;
+
+				if bias.len() != layer.outputs {
+					return Err(not_dense(op).into());
+				}
+
+				for (sum, value) in layer.bias.iter_mut().zip(bias) {
+					*sum += value;
+				}
+			}
+			"Relu" | "Sigmoid" | "Tanh" => {
+				let layer = layers.last_mut().ok_or_else(|| not_dense(op))?;
+				layer.activation = match op {
+					"Relu" => Activation::Relu,
+					"Sigmoid" => Activation::Sigmoid,
+					_ => Activation::Tanh,
+				};
+			}
+			"Identity" | "Cast" => {}
+			_ => return Err(not_dense(op).into()),
+		}
+	}
+
+	match (layers.first(), layers.last()) {
+		(Some(first), Some(last))
+			if first.inputs == INPUTS.len() && last.outputs == OUTPUTS.len() =>
+		{
+			Ok(layers)
+		}
+		_ => Err("The model does not map 3 inputs to 2 flows".into()),
+	}
+}
+
+/*
+ * Writes the model's weights to target in the format, and its model card
+ * next to it as target.md. Returns the model card's path. The model and its
+ * info sidecar are never overwritten.
+ */
+pub fn export(
+	model: &Path,
+	format: ExportFormat,
+	target: &Path,
+) -> Result<PathBuf, Box<dyn Error>> {
+	if target == model || target == ModelInfo::sidecar(model) {
+		return Err(format!(
+			"Export to another file than {}, which belongs to the model",
+			target.display()
+		)
+		.into());
+	}
+
+	let layers = read_layers(model)?;
+	let info = ModelInfo::load(model).unwrap_or_default();
+	let name = model
+		.file_stem()
+		.and_then(|stem| stem.to_str())
+		.unwrap_or("model");
+
+	log::info!(
+		"Exporting {} as {format} to {}",
+		model.display(),
+		target.display()
+	);
+
+	let card = PathBuf::from(format!("{}.md", target.display()));
+	let card_name = card
+		.file_name()
+		.and_then(|name| name.to_str())
+		.unwrap_or_default();
+
+	match format {
+		ExportFormat::Json => fs::write(target, to_json(name, &info.units, &layers)?)?,
+		ExportFormat::Npz => fs::write(target, to_npz(&layers))?,
+		ExportFormat::CHeader => {
+			fs::write(target, to_c_header(name, card_name, &info.units, &layers)?)?
+		}
+	}
+
+	fs::write(&card, model_card(model, &info, &layers, format, target))?;
+
+	Ok(card)
+}
+
+// -------------------------------------------------- JSON
+#[derive(Serialize)]
+struct Port<'a> {
+	name: &'a str,
+	unit: String,
+}
+
+#[derive(Serialize)]
+struct JsonLayer<'a> {
+	activation: Activation,
+	// inputs rows of outputs values
+	weights: Vec<&'a [f32]>,
+	bias: &'a [f32],
+}
+
+#[derive(Serialize)]
+struct JsonModel<'a> {
+	name: &'a str,
+	inputs: Vec<Port<'a>>,
+	outputs: Vec<Port<'a>>,
+	layers: Vec<JsonLayer<'a>>,
+}
+
+fn to_json(name: &str, units: &Units, layers: &[Layer]) -> Result<String, Box<dyn Error>> {
+	let (inputs, outputs) = ports(units);
+
+	Ok(serde_json::to_string_pretty(&JsonModel {
+		name,
+		inputs,
+		outputs,
+		layers: layers
+			.iter()
+			.map(|layer| JsonLayer {
+				activation: layer.activation,
+				weights: layer.rows().collect(),
+				bias: &layer.bias,
+			})
+			.collect(),
+	})?)
+}
+
+fn ports(units: &Units) -> (Vec<Port<'static>>, Vec<Port<'static>>) {
+	let [dim_a, dim_b, flow1, flow2, freq] = units.symbols();
+	let port = |name, unit| Port { name, unit };
+
+	(
+		vec![
+			port(INPUTS[0], dim_a),
+			port(INPUTS[1], dim_b),
+			port(INPUTS[2], freq),
+		],
+		vec![port(OUTPUTS[0], flow1), port(OUTPUTS[1], flow2)],
+	)
+}
+
+// -------------------------------------------------- NPZ
+/*
+ * layer0_weights, layer0_bias, ... and the activations as a string array,
+ * the .npy files in an uncompressed zip, which is what numpy.savez writes.
+ */
+fn to_npz(layers: &[Layer]) -> Vec<u8> {
+	let mut arrays: Vec<(String, Vec<u8>)> = Vec::new();
+
+	for (i, layer) in layers.iter().enumerate() {
+		arrays.push((
+			format!("layer{i}_weights.npy"),
+			npy(
+				"<f4",
+				&[layer.inputs, layer.outputs],
+				&le_bytes(&layer.weights),
+			),
+		));
+		arrays.push((
+			format!("layer{i}_bias.npy"),
+			npy("<f4", &[layer.outputs], &le_bytes(&layer.bias)),
+		));
+	}
+
+	// fixed-width UTF-32 strings, numpy's unicode dtype
+	let names: Vec<String> = layers
+		.iter()
+		.map(|layer| layer.activation.to_string())
+		.collect();
+	let width = names.iter().map(String::len).max().unwrap_or(1);
+	let text: Vec<u8> = names
+		.iter()
+		.flat_map(|name| {
+			let padded = name.chars().chain(std::iter::repeat('\0')).take(width);
+			padded
+				.flat_map(|c| (c as u32).to_le_bytes())
+				.collect::<Vec<_>>()
+		})
+		.collect();
+	arrays.push((
+		String::from("activations.npy"),
+		npy(&format!("<U{width}"), &[names.len()], &text),
+	));
+
+	zip_stored(&arrays)
+}
+
+fn le_bytes(values: &[f32]) -> Vec<u8> {
+	values
+		.iter()
+		.flat_map(|value| value.to_le_bytes())
+		.collect()
+}
+
+// NPY format version 1.0, the header padded to a multiple of 64 bytes
+fn npy(descr: &str, shape: &[usize], data: &[u8]) -> Vec<u8> {
+	let shape = match shape {
+		[length] => format!("({length},)"),
+		_ => format!(
+			"({})",
+			shape
+				.iter()
+				.map(usize::to_string)
+				.collect::<Vec<_>>()
+				.join(", ")
+		),
+	};
+	let mut header = format!("{{'descr': '{descr}', 'fortran_order': False, 'shape': {shape}, }}");
+	let padding = 64 - (10 + header.len() + 1) % 64;
+	header.push_str(&" ".repeat(padding % 64));
+	header.push('\n');
+
+	let mut npy = b"\x93NUMPY\x01\x00".to_vec();
+	npy.extend((header.len() as u16).to_le_bytes());
+	npy.extend(header.as_bytes());
+	npy.extend(data);
+
+	npy
+}
+
+// Zip archive of uncompressed files, dated 1980-01-01
+fn zip_stored(files: &[(String, Vec<u8>)]) -> Vec<u8> {
+	const DATE: u16 = (1 << 5) | 1;
+
+	let mut zip = Vec::new();
+	let mut directory = Vec::new();
+
+	for (name, data) in files {
+		let offset = zip.len() as u32;
+		let crc = crc32fast::hash(data);
+		let size = data.len() as u32;
+
+		// version needed, flags, method, time, date, crc, sizes, name length, extra length
+		let mut fields = Vec::new();
+		fields.extend(20u16.to_le_bytes());
+		fields.extend(0u16.to_le_bytes());
+		fields.extend(0u16.to_le_bytes());
+		fields.extend(0u16.to_le_bytes());
+		fields.extend(DATE.to_le_bytes());
+		fields.extend(crc.to_le_bytes());
+		fields.extend(size.to_le_bytes());
+		fields.extend(size.to_le_bytes());
+		fields.extend((name.len() as u16).to_le_bytes());
+		fields.extend(0u16.to_le_bytes());
+
+		zip.extend(0x04034b50u32.to_le_bytes());
+		zip.extend(&fields);
+		zip.extend(name.as_bytes());
+		zip.extend(data);
+
+		// version made by, then the same fields, comment length, disk, attributes, offset
+		directory.extend(0x02014b50u32.to_le_bytes());
+		directory.extend(20u16.to_le_bytes());
+		directory.extend(&fields);
+		directory.extend(0u16.to_le_bytes());
+		directory.extend(0u16.to_le_bytes());
+		directory.extend(0u16.to_le_bytes());
+		directory.extend(0u32.to_le_bytes());
+		directory.extend(offset.to_le_bytes());
+		directory.extend(name.as_bytes());
+	}
+
+	let offset = zip.len() as u32;
+	let count = files.len() as u16;
+
+	zip.extend(&directory);
+	zip.extend(0x06054b50u32.to_le_bytes());
+	zip.extend(0u16.to_le_bytes());
+	zip.extend(0u16.to_le_bytes());
+	zip.extend(count.to_le_bytes());
+	zip.extend(count.to_le_bytes());
+	zip.extend((directory.len() as u32).to_le_bytes());
+	zip.extend(offset.to_le_bytes());
+	zip.extend(0u16.to_le_bytes());
+
+	zip
+}
+
+// -------------------------------------------------- C HEADER
+/*
+ * Weights as const arrays and a forward pass in plain C99, prefixed with the
+ * model's name so headers of several models can be included together.
+ */
+// card is the model card's file name, mentioned in the header's comment
+fn to_c_header(
+	name: &str,
+	card: &str,
+	units: &Units,
+	layers: &[Layer],
+) -> Result<String, Box<dyn Error>> {
+	// NaN and inf have no C literal
+	if layers
+		.iter()
+		.flat_map(|layer| layer.weights.iter().chain(&layer.bias))
+		.any(|value| !value.is_finite())
+	{
+		return Err("The model has weights that are not finite numbers".into());
+	}
+
+	let prefix: String = name
+		.chars()
+		.map(|c| {
+			if c.is_ascii_alphanumeric() {
+				c.to_ascii_lowercase()
+			} else {
+				'_'
+			}
+		})
+		.collect();
+	let prefix = match prefix.chars().next() {
+		Some(c) if c.is_ascii_digit() => format!("model_{prefix}"),
+		_ => prefix,
+	};
+	let upper = prefix.to_uppercase();
+	let widest = layers.iter().map(|layer| layer.outputs).max().unwrap_or(0);
+	let (inputs, outputs) = ports(units);
+	let describe = |ports: &[Port]| {
+		ports
+			.iter()
+			.map(|port| format!("{} [{}]", port.name, port.unit))
+			.collect::<Vec<_>>()
+			.join(", ")
+	};
+
+	let mut header = String::new();
+	let _ = writeln!(header, "/*");
+	let _ = writeln!(
+		header,
+		" * {name}, exported by iDrop. {card} is its model card."
+	);
+	let _ = writeln!(header, " * Inputs: {}", describe(&inputs));
+	let _ = writeln!(header, " * Outputs: {}", describe(&outputs));
+	let _ = writeln!(header, " */");
+	let _ = writeln!(header, "#ifndef {upper}_H");
+	let _ = writeln!(header, "#define {upper}_H\n");
+	let _ = writeln!(header, "#include <math.h>\n");
+	let _ = writeln!(header, "#define {upper}_INPUTS {}", INPUTS.len());
+	let _ = writeln!(header, "#define {upper}_OUTPUTS {}", OUTPUTS.len());
+	let _ = writeln!(header, "#define {upper}_WIDEST {widest}\n");
+
+	for (i, layer) in layers.iter().enumerate() {
+		let _ = writeln!(
+			header,
+			"/* layer {i}, {} × {}, row-major */",
+			layer.inputs, layer.outputs
+		);
+		header.push_str(&c_array(&format!("{prefix}_w{i}"), &layer.weights));
+		header.push_str(&c_array(&format!("{prefix}_b{i}"), &layer.bias));
+	}
+
+	let _ = writeln!(
+		header,
+		"static void {prefix}_dense(const float *in, int n_in, float *out, int n_out,"
+	);
+	let _ = writeln!(
+		header,
+		"\tconst float *weights, const float *bias, int activation)\n{{"
+	);
+	header.push_str(
+		"\tfor (int j = 0; j < n_out; j++) {\n\
+		 \t\tfloat sum = bias[j];\n\
+		 \t\tfor (int i = 0; i < n_in; i++)\n\
+		 \t\t\tsum += in[i] * weights[i * n_out + j];\n\
+		 \t\tswitch (activation) {\n\
+		 \t\tcase 1: sum = sum > 0.0f ? sum : 0.0f; break;\n\
+		 \t\tcase 2: sum = 1.0f / (1.0f + expf(-sum)); break;\n\
+		 \t\tcase 3: sum = tanhf(sum); break;\n\
+		 \t\t}\n\
+		 \t\tout[j] = sum;\n\
+		 \t}\n}\n\n",
+	);
+
+	let _ = writeln!(
+		header,
+		"/* Not reentrant, the layers share two static buffers */"
+	);
+	let _ = writeln!(
+		header,
+		"static void {prefix}_predict(const float input[{upper}_INPUTS], float output[{upper}_OUTPUTS])\n{{"
+	);
+	let _ = writeln!(header, "\tstatic float buffers[2][{upper}_WIDEST];");
+
+	for (i, layer) in layers.iter().enumerate() {
+		let input = match i {
+			0 => String::from("input"),
+			_ => format!("buffers[{}]", (i - 1) % 2),
+		};
+		let output = match i + 1 == layers.len() {
+			true => String::from("output"),
+			false => format!("buffers[{}]", i % 2),
+		};
+		let activation = match layer.activation {
+			Activation::Linear => 0,
+			Activation::Relu => 1,
+			Activation::Sigmoid => 2,
+			Activation::Tanh => 3,
+		};
+
+		let _ = writeln!(
+			header,
+			"\t{prefix}_dense({input}, {}, {output}, {}, {prefix}_w{i}, {prefix}_b{i}, {activation});",
+			layer.inputs, layer.outputs
+		);
+	}
+
+	let _ = writeln!(header, "}}\n\n#endif");
+
+	Ok(header)
+}
+
+fn c_array(name: &str, values: &[f32]) -> String {
+	let rows: Vec<String> = values
+		.chunks(8)
+		.map(|row| {
+			let row: Vec<String> = row.iter().map(|value| format!("{value:e}f")).collect();
+			format!("\t{},", row.join(", "))
+		})
+		.collect();
+
+	format!(
+		"static const float {name}[{}] = {{\n{}\n}};\n\n",
+		values.len(),
+		rows.join("\n")
+	)
+}
+
+// -------------------------------------------------- MODEL CARD
+fn model_card(
+	model: &Path,
+	info: &ModelInfo,
+	layers: &[Layer],
+	format: ExportFormat,
+	target: &Path,
+) -> String {
+	let file_name = |path: &Path| {
+		path.file_name()
+			.unwrap_or_default()
+			.to_string_lossy()
+			.into_owned()
+	};
+	let stem = model.file_stem().unwrap_or_default().to_string_lossy();
+	// bundled models are the devices' own
+	let device = info.device.or_else(|| {
+		Device::ALL
+			.into_iter()
+			.find(|device| device.model_path() == model)
+	});
+
+	let mut card = String::new();
+	let _ = writeln!(card, "# Model card: {stem}\n");
+	let _ = writeln!(
+		card,
+		"Predicts the pump flows that produce droplets of a given size at a given \
+		 frequency. Exported by iDrop from `{}` as {format}, `{}`.\n",
+		file_name(model),
+		file_name(target)
+	);
+
+	let _ = writeln!(card, "## Intended use\n");
+	let _ = writeln!(
+		card,
+		"- Device: {}",
+		device.map_or(String::from("not recorded"), |device| device.to_string())
+	);
+	let _ = writeln!(
+		card,
+		"- Fluids: {}",
+		info.fluid_pair
+			.as_ref()
+			.map_or(String::from("not recorded"), |pair| format!(
+				"{} in {}",
+				pair.dispersed, pair.continuous
+			))
+	);
+	let _ = writeln!(
+		card,
+		"- Predictions outside the valid domain below are extrapolations, and \
+		 other devices or fluids need a model trained on their data.\n"
+	);
+
+	let _ = writeln!(card, "## Inputs and outputs\n");
+	let (inputs, outputs) = ports(&info.units);
+	let _ = writeln!(card, "| | Name | Unit |\n|---|---|---|");
+	for port in &inputs {
+		let _ = writeln!(card, "| Input | {} | {} |", port.name, port.unit);
+	}
+	for port in &outputs {
+		let _ = writeln!(card, "| Output | {} | {} |", port.name, port.unit);
+	}
+
+	let _ = writeln!(card, "\n## Architecture\n");
+	let _ = writeln!(
+		card,
+		"| Layer | Inputs | Outputs | Activation |\n|---|---|---|---|"
+	);
+	for (i, layer) in layers.iter().enumerate() {
+		let _ = writeln!(
+			card,
+			"| {i} | {} | {} | {} |",
+			layer.inputs, layer.outputs, layer.activation
+		);
+	}
+	let parameters: usize = layers
+		.iter()
+		.map(|layer| layer.weights.len() + layer.bias.len())
+		.sum();
+	let _ = writeln!(card, "\n{parameters} parameters.\n");
+
+	let fingerprint = info.fingerprint.as_ref();
+	let data = match fingerprint {
+		Some(fingerprint) => Some(fingerprint.data.clone()),
+		None => device.map(|device| device.data_path()),
+	};
+
+	let _ = writeln!(card, "## Training data\n");
+	match data.as_deref().map(|path| {
+		(
+			path,
+			training_data(path, fingerprint.map(|f| f.data_hash.as_str())),
+		)
+	}) {
+		Some((path, Ok(samples))) => {
+			let _ = writeln!(card, "`{}`, {} samples.\n", path.display(), samples.len());
+
+			// samples are in the base units
+			let symbols = Units::default().symbols();
+			let _ = writeln!(card, "| Column | Min | Max | Unit |\n|---|---|---|---|");
+			for (i, name) in HEADER.iter().enumerate() {
+				let values = samples.iter().map(|sample| sample.columns()[i]);
+				let min = values.clone().fold(f32::INFINITY, f32::min);
+				let max = values.fold(f32::NEG_INFINITY, f32::max);
+				let _ = writeln!(
+					card,
+					"| {name} | {} | {} | {} |",
+					format_value(min),
+					format_value(max),
+					symbols[i]
+				);
+			}
+
+			let _ = writeln!(card, "\n## Valid domain\n");
+			if let Some(bounds) = input_bounds(&samples) {
+				let [dim_a, _, _, _, freq] = &symbols;
+				let units = [dim_a, dim_a, freq];
+				for ((name, (min, max)), unit) in INPUTS.iter().zip(bounds).zip(units) {
+					let _ = writeln!(
+						card,
+						"- {name}: {} to {} {unit}",
+						format_value(min),
+						format_value(max)
+					);
+				}
+			}
+			let _ = writeln!(card);
+		}
+		Some((path, Err(e))) => {
+			let _ = writeln!(card, "`{}`, not summarized: {e}\n", path.display());
+			let _ = writeln!(
+				card,
+				"## Valid domain\n\nUnknown without the training data.\n"
+			);
+		}
+		None => {
+			let _ = writeln!(
+				card,
+				"Not recorded.\n\n## Valid domain\n\nUnknown without the training data.\n"
+			);
+		}
+	}
+
+	let _ = writeln!(card, "## Metrics\n");
+	match fingerprint {
+		Some(fingerprint) => {
+			let _ = writeln!(
+				card,
+				"Mean absolute error of the last epoch, in µL/min.\n\n\
+				 - Training loss: {}\n- Validation loss: {}\n",
+				format_value(fingerprint.metrics.loss),
+				format_value(fingerprint.metrics.val_loss)
+			);
+			let _ = writeln!(card, "## Reproducibility\n");
+			let _ = writeln!(card, "- Trained: {}", fingerprint.trained);
+			let _ = writeln!(card, "- Seed: {}", fingerprint.seed);
+			let _ = writeln!(card, "- Data SHA-256: `{}`", fingerprint.data_hash);
+			let _ = writeln!(
+				card,
+				"- iDrop: {}",
+				fingerprint.idrop.as_deref().unwrap_or("unknown")
+			);
+			for (package, version) in &fingerprint.versions {
+				let _ = writeln!(card, "- {package}: {version}");
+			}
+		}
+		None => {
+			let _ = writeln!(
+				card,
+				"Not recorded, the model was trained before iDrop kept them."
+			);
+		}
+	}
+
+	card
+}
+
+// Samples of the training data, unless it changed since training
+fn training_data(path: &Path, hash: Option<&str>) -> Result<Vec<Sample>, Box<dyn Error>> {
+	if let Some(hash) = hash {
+		if file_hash(path)? != hash {
+			return Err("it changed since training".into());
+		}
+	}
+
+	read_csv(path)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tract_onnx::pb::{GraphProto, NodeProto, TensorProto};
+
+	fn constant(name: &str, dims: &[i64], values: &[f32]) -> TensorProto {
+		TensorProto {
+			name: name.to_owned(),
+			dims: dims.to_vec(),
+			data_type: 1, // float
+			float_data: values.to_vec(),
+			..TensorProto::default()
+		}
+	}
+
+	fn node(op: &str, inputs: &[&str]) -> NodeProto {
+		NodeProto {
+			op_type: op.to_owned(),
+			input: inputs.iter().map(|input| input.to_string()).collect(),
+			..NodeProto::default()
+		}
+	}
+
+	#[test]
+	fn exports_dense_layers() {
+		let model = ModelProto {
+			graph: Some(GraphProto {
+				node: vec![
+					node("MatMul", &["x", "w0"]),
+					node("Add", &["h", "b0"]),
+					node("Relu", &["h"]),
+					node("MatMul", &["h", "w1"]),
+					node("Add", &["y", "b1"]),
+				],
+				initializer: vec![
+					constant("w0", &[3, 1], &[1.0, 2.0, 3.0]),
+					constant("b0", &[1], &[0.5]),
+					constant("w1", &[1, 2], &[1.0, -1.0]),
+					constant("b1", &[2], &[0.0, 0.25]),
+				],
+				..GraphProto::default()
+			}),
+			..ModelProto::default()
+		};
+
+		let layers = layers(&model).unwrap();
+		assert_eq!(layers.len(), 2);
+		assert_eq!(layers[0].activation, Activation::Relu);
+		assert_eq!(layers[1].bias, [0.0, 0.25]);
+		assert_eq!(layers[1].rows().next().unwrap(), [1.0, -1.0]);
+
+		let json: serde_json::Value =
+			serde_json::from_str(&to_json("test", &Units::default(), &layers).unwrap()).unwrap();
+		assert_eq!(json["layers"][0]["weights"][2][0], 3.0);
+		assert_eq!(json["inputs"][2]["unit"], "Hz");
+
+		// header of a 1-D float array, padded so the data starts 64-byte aligned
+		let bias = npy("<f4", &[2], &le_bytes(&[0.0, 0.25]));
+		assert_eq!((bias.len() - 8) % 64, 0);
+		assert!(String::from_utf8_lossy(&bias).contains("'shape': (2,)"));
+
+		let npz = to_npz(&layers);
+		assert_eq!(&npz[..4], b"PK\x03\x04");
+		assert_eq!(&npz[npz.len() - 22..npz.len() - 18], b"PK\x05\x06");
+		// two arrays per layer and the activations
+		assert_eq!(npz[npz.len() - 12], 5);
+
+		let header = to_c_header("100", "100.h.md", &Units::default(), &layers).unwrap();
+		assert!(header.contains(" * 100, exported by iDrop. 100.h.md is its model card."));
+		assert!(header.contains("#define MODEL_100_WIDEST 2"));
+		assert!(header.contains("static const float model_100_w0[3] = {\n\t1e0f, 2e0f, 3e0f,\n};"));
+		assert!(header
+			.contains("model_100_dense(buffers[0], 1, output, 2, model_100_w1, model_100_b1, 0);"));
+
+		let mut diverged = layers.clone();
+		diverged[1].bias[0] = f32::NAN;
+		assert!(to_c_header("100", "100.h.md", &Units::default(), &diverged).is_err());
+
+		// the weights would replace the model's info sidecar
+		assert_eq!(
+			ExportFormat::Json.file_name(Path::new("models/100.onnx")),
+			"100.weights.json"
+		);
+		assert!(export(
+			Path::new("models/100.onnx"),
+			ExportFormat::Json,
+			Path::new("models/100.json")
+		)
+		.unwrap_err()
+		.to_string()
+		.contains("belongs to the model"));
+
+		let unsupported = ModelProto {
+			graph: Some(GraphProto {
+				node: vec![node("Conv", &["x", "w0"])],
+				..GraphProto::default()
+			}),
+			..ModelProto::default()
+		};
+		assert!(super::layers(&unsupported)
+			.unwrap_err()
+			.to_string()
+			.contains("Conv"));
+	}
+}
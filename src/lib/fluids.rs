@@ -0,0 +1,196 @@
+// Library of the continuous and dispersed phases used on the bench
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, error::Error, path::PathBuf};
+
+use crate::{
+	physics::FluidProperties,
+	storage::{data_dir, load_json, save_json},
+};
+
+const KELVIN: f32 = 273.15;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fluid {
+	pub name: String,
+	pub viscosity: f32,             // mPa·s at the reference temperature
+	pub density: f32,               // kg/m³ at the reference temperature
+	pub reference_temperature: f32, // °C
+	/*
+	 * Temperature dependence:
+	 * viscosity follows Andrade's law, µ(T) = µ_ref·exp(B·(1/T - 1/T_ref)),
+	 * density expands linearly, ρ(T) = ρ_ref·(1 - β·(T - T_ref)).
+	 */
+	pub viscosity_activation: f32, // B, K
+	pub thermal_expansion: f32,    // β, 1/K
+}
+
+impl Fluid {
+	pub fn properties_at(&self, temperature: f32) -> FluidProperties {
+		let (t, t_ref) = (temperature + KELVIN, self.reference_temperature + KELVIN);
+
+		FluidProperties {
+			viscosity: self.viscosity * (self.viscosity_activation * (1.0 / t - 1.0 / t_ref)).exp(),
+			density: self.density
+				* (1.0 - self.thermal_expansion * (temperature - self.reference_temperature)),
+		}
+	}
+}
+
+// Continuous and dispersed phase, referenced by fluid name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FluidPair {
+	pub continuous: String,
+	pub dispersed: String,
+}
+
+impl std::fmt::Display for FluidPair {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{} / {}", self.continuous, self.dispersed)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InterfacialTension {
+	pub pair: FluidPair,
+	pub tension: f32, // mN/m
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FluidLibrary {
+	pub fluids: Vec<Fluid>,
+	pub tensions: Vec<InterfacialTension>,
+	// fluid pair each device was characterised with, keyed by device name
+	pub devices: BTreeMap<String, FluidPair>,
+}
+
+impl Default for FluidLibrary {
+	fn default() -> Self {
+		let pbs_fluosurf = FluidPair {
+			continuous: String::from("PBS"),
+			dispersed: String::from("FluoSurf"),
+		};
+
+		FluidLibrary {
+			fluids: vec![
+				Fluid {
+					name: String::from("PBS"),
+					viscosity: 1.02,
+					density: 1005.0,
+					reference_temperature: 20.0,
+					viscosity_activation: 1900.0,
+					thermal_expansion: 2.1e-4,
+				},
+				Fluid {
+					name: String::from("FluoSurf"),
+					viscosity: 1.24,
+					density: 1614.0,
+					reference_temperature: 20.0,
+					viscosity_activation: 1100.0,
+					thermal_expansion: 1.3e-3,
+				},
+			],
+			tensions: vec![InterfacialTension {
+				pair: pbs_fluosurf.clone(),
+				tension: 3.0,
+			}],
+			devices: ["100", "190", "275"]
+				.iter()
+				.map(|device| (device.to_string(), pbs_fluosurf.clone()))
+				.collect(),
+		}
+	}
+}
+
+impl FluidLibrary {
+	pub fn path() -> PathBuf {
+		data_dir().join("fluids.json")
+	}
+
+	pub fn load() -> Result<Self, Box<dyn Error>> {
+		load_json(&Self::path())
+	}
+
+	pub fn save(&self) -> Result<(), Box<dyn Error>> {
+		save_json(&Self::path(), self)
+	}
+
+	pub fn fluid(&self, name: &str) -> Option<&Fluid> {
+		self.fluids.iter().find(|fluid| fluid.name == name)
+	}
+
+	pub fn names(&self) -> Vec<String> {
+		self.fluids.iter().map(|fluid| fluid.name.clone()).collect()
+	}
+
+	// Adds the fluid or replaces the one with the same name
+	pub fn upsert_fluid(&mut self, fluid: Fluid) {
+		match self.fluids.iter_mut().find(|f| f.name == fluid.name) {
+			Some(existing) => *existing = fluid,
+			None => self.fluids.push(fluid),
+		}
+	}
+
+	pub fn remove_fluid(&mut self, name: &str) {
+		self.fluids.retain(|fluid| fluid.name != name);
+		self.tensions
+			.retain(|entry| entry.pair.continuous != name && entry.pair.dispersed != name);
+	}
+
+	// Interfacial tension does not depend on which phase is dispersed
+	pub fn tension(&self, pair: &FluidPair) -> Option<f32> {
+		self.tensions
+			.iter()
+			.find(|entry| Self::same_fluids(&entry.pair, pair))
+			.map(|entry| entry.tension)
+	}
+
+	pub fn set_tension(&mut self, pair: FluidPair, tension: f32) {
+		match self
+			.tensions
+			.iter_mut()
+			.find(|entry| Self::same_fluids(&entry.pair, &pair))
+		{
+			Some(entry) => entry.tension = tension,
+			None => self.tensions.push(InterfacialTension { pair, tension }),
+		}
+	}
+
+	pub fn device_pair(&self, device: &str) -> Option<&FluidPair> {
+		self.devices.get(device)
+	}
+
+	fn same_fluids(a: &FluidPair, b: &FluidPair) -> bool {
+		(a.continuous == b.continuous && a.dispersed == b.dispersed)
+			|| (a.continuous == b.dispersed && a.dispersed == b.continuous)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn temperature_dependence() {
+		let library = FluidLibrary::default();
+		let pbs = library.fluid("PBS").unwrap();
+
+		let reference = pbs.properties_at(20.0);
+		assert!((reference.viscosity - pbs.viscosity).abs() < 1e-6);
+		assert!((reference.density - pbs.density).abs() < 1e-3);
+
+		let warm = pbs.properties_at(37.0);
+		assert!(warm.viscosity < reference.viscosity);
+		assert!(warm.density < reference.density);
+	}
+
+	#[test]
+	fn tension_is_symmetric() {
+		let library = FluidLibrary::default();
+		let reversed = FluidPair {
+			continuous: String::from("FluoSurf"),
+			dispersed: String::from("PBS"),
+		};
+
+		assert_eq!(library.tension(&reversed), Some(3.0));
+	}
+}
@@ -0,0 +1,220 @@
+// Tab and Shift+Tab move the keyboard focus between the text inputs of a scene
+use iced_native::{
+	event, keyboard, layout, mouse, overlay, renderer, widget::text_input, Clipboard, Event,
+	Layout, Length, Point, Rectangle, Shell,
+};
+use iced_pure::{
+	widget::tree::{Tag, Tree},
+	Element, Widget,
+};
+
+/*
+ * Pure widgets keep their state in the widget tree, out of the scenes' reach,
+ * so the scope finds the text inputs in its part of the tree and moves the
+ * focus itself. Inputs are visited in the order they are laid out.
+ */
+pub struct FocusScope<'a, Message, Renderer> {
+	content: Element<'a, Message, Renderer>,
+}
+
+pub fn focus_scope<'a, Message, Renderer>(
+	content: impl Into<Element<'a, Message, Renderer>>,
+) -> FocusScope<'a, Message, Renderer> {
+	FocusScope {
+		content: content.into(),
+	}
+}
+
+// Focuses the input after (or before) the focused one, wrapping around at either end
+pub fn move_focus(tree: &mut Tree, forward: bool) {
+	let mut inputs = Vec::new();
+	text_inputs(tree, &mut inputs);
+
+	let count = inputs.len();
+	if count == 0 {
+		return;
+	}
+
+	let next = match inputs.iter().position(|input| input.is_focused()) {
+		Some(i) => {
+			inputs[i].unfocus();
+			if forward {
+				(i + 1) % count
+			} else {
+				(i + count - 1) % count
+			}
+		}
+		None if forward => 0,
+		None => count - 1,
+	};
+
+	inputs[next].focus();
+	inputs[next].move_cursor_to_end();
+}
+
+fn text_inputs<'a>(tree: &'a mut Tree, inputs: &mut Vec<&'a mut text_input::State>) {
+	let Tree {
+		tag,
+		state,
+		children,
+	} = tree;
+
+	if *tag == Tag::of::<text_input::State>() {
+		inputs.push(state.downcast_mut());
+	}
+
+	for child in children {
+		text_inputs(child, inputs);
+	}
+}
+
+impl<'a, Message, Renderer> Widget<Message, Renderer> for FocusScope<'a, Message, Renderer>
+where
+	Renderer: iced_native::Renderer,
+{
+	fn children(&self) -> Vec<Tree> {
+		vec![Tree::new(&self.content)]
+	}
+
+	fn diff(&self, tree: &mut Tree) {
+		tree.diff_children(std::slice::from_ref(&self.content))
+	}
+
+	fn width(&self) -> Length {
+		self.content.as_widget().width()
+	}
+
+	fn height(&self) -> Length {
+		self.content.as_widget().height()
+	}
+
+	fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+		self.content.as_widget().layout(renderer, limits)
+	}
+
+	fn on_event(
+		&mut self,
+		tree: &mut Tree,
+		event: Event,
+		layout: Layout<'_>,
+		cursor_position: Point,
+		renderer: &Renderer,
+		clipboard: &mut dyn Clipboard,
+		shell: &mut Shell<'_, Message>,
+	) -> event::Status {
+		if let Event::Keyboard(keyboard::Event::KeyPressed {
+			key_code: keyboard::KeyCode::Tab,
+			modifiers,
+		}) = event
+		{
+			if !modifiers.command() && !modifiers.alt() {
+				move_focus(&mut tree.children[0], !modifiers.shift());
+				return event::Status::Captured;
+			}
+		}
+
+		self.content.as_widget_mut().on_event(
+			&mut tree.children[0],
+			event,
+			layout,
+			cursor_position,
+			renderer,
+			clipboard,
+			shell,
+		)
+	}
+
+	fn mouse_interaction(
+		&self,
+		tree: &Tree,
+		layout: Layout<'_>,
+		cursor_position: Point,
+		viewport: &Rectangle,
+		renderer: &Renderer,
+	) -> mouse::Interaction {
+		self.content.as_widget().mouse_interaction(
+			&tree.children[0],
+			layout,
+			cursor_position,
+			viewport,
+			renderer,
+		)
+	}
+
+	fn draw(
+		&self,
+		tree: &Tree,
+		renderer: &mut Renderer,
+		style: &renderer::Style,
+		layout: Layout<'_>,
+		cursor_position: Point,
+		viewport: &Rectangle,
+	) {
+		self.content.as_widget().draw(
+			&tree.children[0],
+			renderer,
+			style,
+			layout,
+			cursor_position,
+			viewport,
+		)
+	}
+
+	fn overlay<'b>(
+		&'b self,
+		tree: &'b mut Tree,
+		layout: Layout<'_>,
+		renderer: &Renderer,
+	) -> Option<overlay::Element<'b, Message, Renderer>> {
+		self.content
+			.as_widget()
+			.overlay(&mut tree.children[0], layout, renderer)
+	}
+}
+
+impl<'a, Message, Renderer> From<FocusScope<'a, Message, Renderer>>
+	for Element<'a, Message, Renderer>
+where
+	Message: 'a,
+	Renderer: 'a + iced_native::Renderer,
+{
+	fn from(scope: FocusScope<'a, Message, Renderer>) -> Self {
+		Element::new(scope)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use iced_native::renderer::Null;
+	use iced_pure::widget::{Column, Row, TextInput};
+
+	fn focused(tree: &mut Tree) -> Vec<bool> {
+		let mut inputs = Vec::new();
+		text_inputs(tree, &mut inputs);
+		inputs.iter().map(|input| input.is_focused()).collect()
+	}
+
+	#[test]
+	fn tabs_through_nested_inputs() {
+		let input = || TextInput::<(), Null>::new("", "", |_| ());
+		let form: Element<(), Null> = Column::new()
+			.push(input())
+			.push(Row::new().push(input()).push(input()))
+			.into();
+		let mut tree = Tree::new(&form);
+
+		move_focus(&mut tree, true);
+		assert_eq!(focused(&mut tree), [true, false, false]);
+
+		move_focus(&mut tree, true);
+		move_focus(&mut tree, true);
+		assert_eq!(focused(&mut tree), [false, false, true]);
+
+		// wraps around in both directions
+		move_focus(&mut tree, true);
+		assert_eq!(focused(&mut tree), [true, false, false]);
+		move_focus(&mut tree, false);
+		assert_eq!(focused(&mut tree), [false, false, true]);
+	}
+}
@@ -0,0 +1,517 @@
+// Droplet generation frequency from a sequence of frames
+use iced::{
+	canvas::{event, Cursor, Event, Frame, Geometry, Path, Stroke},
+	mouse,
+	pure::widget::canvas::Program,
+	Color, Point, Rectangle, Size,
+};
+use image::{imageops::FilterType, DynamicImage, GenericImageView, GrayImage};
+use std::{
+	error::Error,
+	fs,
+	path::{Path as FilePath, PathBuf},
+};
+
+use crate::{
+	imaging::{otsu, Polarity},
+	views::SceneMessage,
+};
+
+const EXTENSIONS: [&str; 5] = ["png", "tif", "tiff", "jpg", "jpeg"];
+
+// Line droplets cross on their way down the channel, in image pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CrossingLine {
+	pub from: (f32, f32),
+	pub to: (f32, f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Timing {
+	FrameRate(f32), // frames per second
+	// the last number in each file name, in milliseconds
+	FileNames,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrequencyEstimate {
+	pub frequency: f32,         // Hz
+	pub confidence: (f32, f32), // 95% interval, Hz
+	pub crossings: Vec<f32>,    // s
+	pub frames: usize,
+	// fewer than two frames per droplet, so droplets may be missed
+	pub undersampled: bool,
+}
+
+// Image files of a folder, sorted by name
+pub fn frame_paths(dir: &FilePath) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+	let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+		.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+		.filter(|path| {
+			path.extension()
+				.and_then(|ext| ext.to_str())
+				.map(|ext| EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+				.unwrap_or(false)
+		})
+		.collect();
+
+	paths.sort();
+
+	Ok(paths)
+}
+
+/*
+ * Seconds from the last run of digits in a file name given in ms. Camera
+ * counters and epoch times are too large for f32 to keep milliseconds.
+ */
+pub fn timestamp(path: &FilePath) -> Option<f64> {
+	let stem = path.file_stem()?.to_str()?;
+
+	let digits: String = stem
+		.chars()
+		.rev()
+		.skip_while(|c| !c.is_ascii_digit())
+		.take_while(|c| c.is_ascii_digit() || *c == '.')
+		.collect::<Vec<_>>()
+		.into_iter()
+		.rev()
+		.collect();
+
+	digits
+		.trim_matches('.')
+		.parse::<f64>()
+		.ok()
+		.map(|ms| ms / 1000.0)
+}
+
+/*
+ * Frames with their times in seconds from the first, in order of time. At a
+ * frame rate they are taken in order of name, timestamps order them
+ * themselves as names like f_200 and f_1000 do not sort by number.
+ */
+pub fn frame_times(
+	paths: &[PathBuf],
+	timing: Timing,
+) -> Result<Vec<(f32, PathBuf)>, Box<dyn Error>> {
+	match timing {
+		Timing::FrameRate(fps) if fps.is_finite() && fps > 0.0 => Ok(paths
+			.iter()
+			.enumerate()
+			.map(|(i, path)| (i as f32 / fps, path.clone()))
+			.collect()),
+		Timing::FrameRate(_) => Err("Frame rate must be a positive number".into()),
+		Timing::FileNames => {
+			let mut frames = paths
+				.iter()
+				.map(|path| match timestamp(path) {
+					Some(time) => Ok((time, path.clone())),
+					None => Err(format!("No timestamp in file name {}", path.display())),
+				})
+				.collect::<Result<Vec<_>, _>>()?;
+
+			frames.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+			if let Some(pair) = frames.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+				return Err(format!(
+					"{} and {} have the same timestamp",
+					pair[0].1.display(),
+					pair[1].1.display()
+				)
+				.into());
+			}
+
+			let start = frames.first().map_or(0.0, |frame| frame.0);
+
+			Ok(frames
+				.into_iter()
+				.map(|(time, path)| ((time - start) as f32, path))
+				.collect())
+		}
+	}
+}
+
+// Grey values sampled evenly along the line
+pub fn line_profile(gray: &GrayImage, line: CrossingLine) -> Vec<u8> {
+	let (dx, dy) = (line.to.0 - line.from.0, line.to.1 - line.from.1);
+	let samples = (dx.hypot(dy).ceil() as usize).max(2);
+
+	(0..samples)
+		.filter_map(|i| {
+			let t = i as f32 / (samples - 1) as f32;
+			let (x, y) = (line.from.0 + t * dx, line.from.1 + t * dy);
+
+			(x >= 0.0 && y >= 0.0 && (x as u32) < gray.width() && (y as u32) < gray.height())
+				.then(|| gray.get_pixel(x as u32, y as u32).0[0])
+		})
+		.collect()
+}
+
+/*
+ * The threshold and polarity are taken from the first frame. A droplet is on
+ * the line while enough of the line is covered, with hysteresis between half
+ * and a quarter of the largest coverage seen so noise at the edges does not
+ * count twice. Each droplet arriving is one crossing, and the frequency
+ * follows from the mean interval between crossings with a 95% interval from
+ * its standard error.
+ */
+pub fn estimate(
+	frames: impl IntoIterator<Item = Result<(f32, GrayImage), Box<dyn Error>>>,
+	line: CrossingLine,
+	polarity: Polarity,
+) -> Result<FrequencyEstimate, Box<dyn Error>> {
+	let mut foreground: Option<(u8, bool)> = None;
+	let mut coverage = Vec::new();
+
+	for frame in frames {
+		let (time, gray) = frame?;
+
+		if let Some(&(previous, _)) = coverage.last() {
+			if time <= previous {
+				return Err(
+					format!("Frame times must increase, {time} s follows {previous} s").into(),
+				);
+			}
+		}
+
+		let (threshold, dark) = *foreground.get_or_insert_with(|| {
+			let threshold = otsu(&gray);
+			let dark = gray.pixels().filter(|p| p.0[0] <= threshold).count();
+
+			let dark_droplets = match polarity {
+				Polarity::Dark => true,
+				Polarity::Bright => false,
+				Polarity::Auto => 2 * dark <= gray.pixels().len(),
+			};
+			(threshold, dark_droplets)
+		});
+
+		let profile = line_profile(&gray, line);
+		if profile.is_empty() {
+			return Err("The crossing line lies outside the frames".into());
+		}
+
+		let covered = profile
+			.iter()
+			.filter(|&&v| (v <= threshold) == dark)
+			.count();
+		coverage.push((time, covered as f32 / profile.len() as f32));
+	}
+
+	let frames = coverage.len();
+	let peak = coverage.iter().map(|(_, c)| *c).fold(0.0, f32::max);
+	if peak <= 0.0 {
+		return Err("No droplet crossed the line".into());
+	}
+
+	let (high, low) = (0.5 * peak, 0.25 * peak);
+	let mut present = coverage[0].1 >= high;
+	let mut crossings = Vec::new();
+
+	for &(time, covered) in &coverage[1..] {
+		if !present && covered >= high {
+			present = true;
+			crossings.push(time);
+		} else if present && covered <= low {
+			present = false;
+		}
+	}
+
+	if crossings.len() < 3 {
+		return Err(format!(
+			"{} droplets crossed the line, at least 3 are needed",
+			crossings.len()
+		)
+		.into());
+	}
+
+	let intervals: Vec<f32> = crossings.windows(2).map(|w| w[1] - w[0]).collect();
+	let n = intervals.len() as f32;
+	let mean = intervals.iter().sum::<f32>() / n;
+	let sd =
+		(intervals.iter().map(|i| (i - mean).powi(2)).sum::<f32>() / (n - 1.0).max(1.0)).sqrt();
+	let margin = 1.96 * sd / n.sqrt();
+
+	let frame_interval = (coverage[frames - 1].0 - coverage[0].0) / (frames - 1) as f32;
+
+	Ok(FrequencyEstimate {
+		frequency: 1.0 / mean,
+		confidence: (
+			1.0 / (mean + margin),
+			if mean > margin {
+				1.0 / (mean - margin)
+			} else {
+				f32::INFINITY
+			},
+		),
+		crossings,
+		frames,
+		undersampled: mean < 2.0 * frame_interval,
+	})
+}
+
+// Loads and estimates a whole folder, one frame at a time
+pub fn estimate_folder(
+	paths: &[PathBuf],
+	timing: Timing,
+	line: CrossingLine,
+	polarity: Polarity,
+) -> Result<FrequencyEstimate, Box<dyn Error>> {
+	let frames =
+		frame_times(paths, timing)?
+			.into_iter()
+			.map(|(time, path)| -> Result<_, Box<dyn Error>> {
+				Ok((time, image::open(path)?.to_luma8()))
+			});
+
+	estimate(frames, line, polarity)
+}
+
+// -------------------------------------------------- LINE PICKER
+// Downscaled grey copy of a frame to draw the crossing line on
+#[derive(Debug, Clone, PartialEq)]
+pub struct Thumbnail {
+	pub image: GrayImage,
+	// frame pixels per thumbnail pixel
+	pub scale: f32,
+}
+
+impl Thumbnail {
+	pub fn new(image: &DynamicImage) -> Self {
+		const WIDTH: u32 = 120;
+
+		let scale = (image.width() as f32 / WIDTH as f32).max(1.0);
+		let height = ((image.height() as f32 / scale).round() as u32).max(1);
+		let thumbnail = image.resize_exact(
+			(image.width() as f32 / scale).round() as u32,
+			height,
+			FilterType::Triangle,
+		);
+
+		Thumbnail {
+			image: thumbnail.to_luma8(),
+			scale,
+		}
+	}
+}
+
+pub struct LinePicker<'a> {
+	pub thumbnail: &'a Thumbnail,
+	pub line: Option<CrossingLine>,
+}
+
+#[derive(Debug, Default)]
+pub struct LinePickerState {
+	start: Option<Point>,
+}
+
+impl<'a> LinePicker<'a> {
+	// Canvas pixels per thumbnail pixel
+	fn cell(&self, size: Size) -> f32 {
+		let (width, height) = self.thumbnail.image.dimensions();
+		(size.width / width as f32).min(size.height / height as f32)
+	}
+
+	fn to_frame(&self, size: Size, point: Point) -> (f32, f32) {
+		let factor = self.thumbnail.scale / self.cell(size);
+		(point.x * factor, point.y * factor)
+	}
+
+	fn to_canvas(&self, size: Size, (x, y): (f32, f32)) -> Point {
+		let factor = self.cell(size) / self.thumbnail.scale;
+		Point::new(x * factor, y * factor)
+	}
+}
+
+impl<'a> Program<SceneMessage> for LinePicker<'a> {
+	type State = LinePickerState;
+
+	fn update(
+		&self,
+		state: &mut Self::State,
+		event: Event,
+		bounds: Rectangle,
+		cursor: Cursor,
+	) -> (event::Status, Option<SceneMessage>) {
+		match event {
+			Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+				match cursor.position_in(&bounds) {
+					Some(position) => {
+						state.start = Some(position);
+						(event::Status::Captured, None)
+					}
+					None => (event::Status::Ignored, None),
+				}
+			}
+			Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+				let (start, end) =
+					match (state.start.take(), cursor.position_from(bounds.position())) {
+						(Some(start), Some(end)) => (start, end),
+						_ => return (event::Status::Ignored, None),
+					};
+
+				let line = CrossingLine {
+					from: self.to_frame(bounds.size(), start),
+					to: self.to_frame(bounds.size(), end),
+				};
+
+				(
+					event::Status::Captured,
+					Some(SceneMessage::CrossingLineDrawn(line)),
+				)
+			}
+			_ => (event::Status::Ignored, None),
+		}
+	}
+
+	fn draw(&self, state: &Self::State, bounds: Rectangle, cursor: Cursor) -> Vec<Geometry> {
+		let mut frame = Frame::new(bounds.size());
+		let cell = self.cell(bounds.size());
+
+		// runs of similar grey merged into one rectangle per row
+		for (y, row) in self.thumbnail.image.rows().enumerate() {
+			let mut run: Option<(usize, u8)> = None;
+
+			for (x, pixel) in row.chain(std::iter::once(&image::Luma([0]))).enumerate() {
+				let level = pixel.0[0] / 16 * 16;
+				let last = x == self.thumbnail.image.width() as usize;
+
+				match run {
+					Some((start, value)) if value != level || last => {
+						frame.fill_rectangle(
+							Point::new(start as f32 * cell, y as f32 * cell),
+							Size::new((x - start) as f32 * cell + 0.5, cell + 0.5),
+							Color::from_rgb8(value, value, value),
+						);
+						run = Some((x, level));
+					}
+					None => run = Some((x, level)),
+					_ => {}
+				}
+			}
+		}
+
+		let drawn = match (state.start, cursor.position_in(&bounds)) {
+			(Some(start), Some(end)) => Some((start, end)),
+			_ => self.line.map(|line| {
+				(
+					self.to_canvas(bounds.size(), line.from),
+					self.to_canvas(bounds.size(), line.to),
+				)
+			}),
+		};
+
+		if let Some((from, to)) = drawn {
+			frame.stroke(
+				&Path::line(from, to),
+				Stroke {
+					width: 2.0,
+					color: Color::from_rgb8(255, 40, 40),
+					..Stroke::default()
+				},
+			);
+		}
+
+		vec![frame.into_geometry()]
+	}
+
+	fn mouse_interaction(
+		&self,
+		_state: &Self::State,
+		bounds: Rectangle,
+		cursor: Cursor,
+	) -> mouse::Interaction {
+		if cursor.is_over(&bounds) {
+			mouse::Interaction::Crosshair
+		} else {
+			mouse::Interaction::default()
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use image::Luma;
+
+	// Dark 20 px droplets moving right at 4 px per frame, one every 25 frames
+	fn frames(count: usize) -> Vec<Result<(f32, GrayImage), Box<dyn Error>>> {
+		(0..count)
+			.map(|i| {
+				let image = GrayImage::from_fn(200, 40, |x, y| {
+					let inside = (0..10).any(|k| {
+						let center = 4.0 * (i as f32 - 25.0 * k as f32);
+						((x as f32 - center) / 10.0).powi(2) + ((y as f32 - 20.0) / 8.0).powi(2)
+							<= 1.0
+					});
+					Luma([if inside { 30 } else { 220 }])
+				});
+
+				Ok((i as f32 / 1000.0, image))
+			})
+			.collect()
+	}
+
+	#[test]
+	fn estimates_frequency() {
+		let line = CrossingLine {
+			from: (100.0, 0.0),
+			to: (100.0, 39.0),
+		};
+
+		// 1000 fps and a droplet every 25 frames is 40 Hz
+		let estimate = estimate(frames(200), line, Polarity::Auto).unwrap();
+
+		assert!(
+			(estimate.frequency - 40.0).abs() < 0.5,
+			"{}",
+			estimate.frequency
+		);
+		assert!(estimate.confidence.0 <= estimate.frequency);
+		assert!(estimate.confidence.1 >= estimate.frequency);
+		assert!(!estimate.undersampled);
+		assert_eq!(estimate.frames, 200);
+	}
+
+	#[test]
+	fn timestamps_from_file_names() {
+		assert_eq!(timestamp(FilePath::new("frame_0012_1500.png")), Some(1.5));
+		assert_eq!(timestamp(FilePath::new("frame.png")), None);
+
+		// epoch milliseconds, unpadded so they do not sort by name
+		let start: u64 = 1_760_000_000_000;
+		let mut paths: Vec<PathBuf> = (0..200)
+			.map(|i| PathBuf::from(format!("f_{}.png", start + i)))
+			.chain([PathBuf::from("f_999.png")])
+			.collect();
+		paths.sort();
+
+		let frames = frame_times(&paths[..paths.len() - 1], Timing::FileNames).unwrap();
+		assert_eq!(frames[0].1, PathBuf::from(format!("f_{start}.png")));
+		assert!(frames.windows(2).all(|pair| {
+			let interval = pair[1].0 - pair[0].0;
+			(interval - 0.001).abs() < 1e-6
+		}));
+
+		// the images of the frequency test at these times, still 40 Hz
+		let images = frames
+			.iter()
+			.zip(self::frames(200))
+			.map(|((time, _), frame)| Ok((*time, frame?.1)));
+		let line = CrossingLine {
+			from: (100.0, 0.0),
+			to: (100.0, 39.0),
+		};
+		let from_names = estimate(images, line, Polarity::Auto).unwrap();
+		assert!((from_names.frequency - 40.0).abs() < 0.5);
+
+		// f_999 sorts among the others by name but comes first by time
+		let frames = frame_times(&paths, Timing::FileNames).unwrap();
+		assert_eq!(frames[0].1, PathBuf::from("f_999.png"));
+
+		let duplicate = [PathBuf::from("a_200.png"), PathBuf::from("b_200.png")];
+		assert!(frame_times(&duplicate, Timing::FileNames).is_err());
+
+		let backwards = [(0.002, GrayImage::new(1, 1)), (0.001, GrayImage::new(1, 1))];
+		assert!(estimate(backwards.into_iter().map(Ok), line, Polarity::Auto).is_err());
+	}
+}
@@ -0,0 +1,175 @@
+// Every prediction made, kept between sessions for lab notebooks
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+	error::Error,
+	fmt::Write,
+	fs,
+	path::{Path, PathBuf},
+	time::SystemTime,
+};
+
+use crate::storage::{data_dir, load_json, save_json};
+
+const HEADER: [&str; 11] = [
+	"timestamp",
+	"device",
+	"model",
+	"model_hash",
+	"dim_a [µm]",
+	"dim_b [µm]",
+	"freq [Hz]",
+	"flow1 [µL/min]",
+	"flow2 [µL/min]",
+	"baseline_flow1 [µL/min]",
+	"baseline_flow2 [µL/min]",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+	pub timestamp: String, // RFC 3339, UTC
+	pub device: Option<String>,
+	pub model: String,
+	// ONNX file the prediction was made with, None for the scaling law
+	pub model_path: Option<PathBuf>,
+	pub model_hash: Option<String>, // SHA-256 of the model file
+	pub inputs: [f32; 3],           // dim_a, dim_b, freq
+	pub flows: [f32; 2],            // µL/min, continuous and dispersed
+	pub baseline: Option<[f32; 2]>, // scaling law next to the model
+}
+
+impl Record {
+	// First characters of the hash, enough to tell models apart in a table
+	pub fn short_hash(&self) -> &str {
+		match &self.model_hash {
+			Some(hash) => &hash[..hash.len().min(8)],
+			None => "",
+		}
+	}
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct History {
+	pub records: Vec<Record>,
+}
+
+impl History {
+	pub fn path() -> PathBuf {
+		data_dir().join("history.json")
+	}
+
+	pub fn load() -> Result<Self, Box<dyn Error>> {
+		load_json(&Self::path())
+	}
+
+	pub fn save(&self) -> Result<(), Box<dyn Error>> {
+		save_json(&Self::path(), self)
+	}
+
+	pub fn push(&mut self, record: Record) {
+		self.records.push(record);
+	}
+
+	pub fn to_csv(&self) -> String {
+		let mut csv = HEADER.join(",");
+
+		for record in &self.records {
+			let number = |value: Option<f32>| value.map(|v| v.to_string()).unwrap_or_default();
+			let [dim_a, dim_b, freq] = record.inputs;
+			let [flow1, flow2] = record.flows;
+			let baseline = record.baseline.map_or([None; 2], |flows| flows.map(Some));
+
+			let _ = write!(
+				csv,
+				"\n{},{},{},{},{dim_a},{dim_b},{freq},{flow1},{flow2},{},{}",
+				record.timestamp,
+				quote(record.device.as_deref().unwrap_or("")),
+				quote(&record.model),
+				record.model_hash.as_deref().unwrap_or(""),
+				number(baseline[0]),
+				number(baseline[1]),
+			);
+		}
+
+		csv
+	}
+
+	pub fn save_csv(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+		fs::write(path, self.to_csv())?;
+		Ok(())
+	}
+
+	pub fn save_json(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+		save_json(path, &self.records)
+	}
+}
+
+// Model file names may contain commas
+fn quote(field: &str) -> String {
+	if field.contains([',', '"']) {
+		format!("\"{}\"", field.replace('"', "\"\""))
+	} else {
+		field.to_owned()
+	}
+}
+
+pub fn timestamp() -> String {
+	humantime::format_rfc3339_seconds(SystemTime::now()).to_string()
+}
+
+// Hex SHA-256 of a file's contents
+pub fn file_hash(path: &Path) -> Result<String, Box<dyn Error>> {
+	let digest = Sha256::digest(fs::read(path)?);
+
+	Ok(digest.iter().fold(String::new(), |mut hex, byte| {
+		let _ = write!(hex, "{byte:02x}");
+		hex
+	}))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn exports_records() {
+		let path = std::env::temp_dir().join("idrop-history-test.onnx");
+		fs::write(&path, "abc").unwrap();
+
+		let hash = file_hash(&path).unwrap();
+		assert_eq!(
+			hash,
+			"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+		);
+
+		let mut history = History::default();
+		history.push(Record {
+			timestamp: timestamp(),
+			device: Some(String::from("100")),
+			model: String::from("tuned, v2.onnx"),
+			model_path: Some(path),
+			model_hash: Some(hash),
+			inputs: [120.0, 80.0, 200.0],
+			flows: [10.0, 5.0],
+			baseline: None,
+		});
+		history.push(Record {
+			model: String::from("Scaling law"),
+			model_path: None,
+			model_hash: None,
+			baseline: Some([9.0, 4.5]),
+			..history.records[0].clone()
+		});
+
+		assert_eq!(history.records[0].short_hash(), "ba7816bf");
+
+		let csv = history.to_csv();
+		let lines: Vec<&str> = csv.lines().collect();
+		assert_eq!(lines.len(), 3);
+		assert!(lines[1].contains("\"tuned, v2.onnx\""));
+		assert!(lines[2].ends_with(",9,4.5"));
+
+		let json = serde_json::to_string(&history).unwrap();
+		assert_eq!(serde_json::from_str::<History>(&json).unwrap(), history);
+	}
+}
@@ -0,0 +1,318 @@
+// Measuring droplets on microscopy frames
+use image::{DynamicImage, GrayImage, Rgba, RgbaImage};
+use std::{collections::VecDeque, error::Error, path::Path};
+
+// Which side of the threshold the droplets are on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Polarity {
+	// whichever class covers less of the frame
+	#[default]
+	Auto,
+	Dark,
+	Bright,
+}
+
+impl Polarity {
+	pub const ALL: [Self; 3] = [Polarity::Auto, Polarity::Dark, Polarity::Bright];
+}
+
+impl std::fmt::Display for Polarity {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				Polarity::Auto => "Automatic",
+				Polarity::Dark => "Dark droplets",
+				Polarity::Bright => "Bright droplets",
+			}
+		)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+	pub dim_a: f32,         // µm, along the channel
+	pub dim_b: f32,         // µm, across the channel
+	pub center: (f32, f32), // px
+	pub angle: f32,         // rad, of the major axis from the x axis
+	pub area: usize,        // px
+	pub contour: Vec<(u32, u32)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+	pub threshold: u8,
+	pub droplets: Vec<Measurement>,
+}
+
+impl Analysis {
+	// Mean (dim_a, dim_b) over all droplets
+	pub fn mean(&self) -> Option<(f32, f32)> {
+		if self.droplets.is_empty() {
+			return None;
+		}
+
+		let n = self.droplets.len() as f32;
+		let (a, b) = self
+			.droplets
+			.iter()
+			.fold((0.0, 0.0), |(a, b), d| (a + d.dim_a, b + d.dim_b));
+
+		Some((a / n, b / n))
+	}
+
+	// Coefficient of variation (σ / mean) of dim_a and dim_b
+	pub fn cv(&self) -> Option<(f32, f32)> {
+		let (mean_a, mean_b) = self.mean()?;
+		let n = self.droplets.len() as f32;
+
+		let (var_a, var_b) = self.droplets.iter().fold((0.0, 0.0), |(a, b), d| {
+			(
+				a + (d.dim_a - mean_a).powi(2),
+				b + (d.dim_b - mean_b).powi(2),
+			)
+		});
+
+		Some(((var_a / n).sqrt() / mean_a, (var_b / n).sqrt() / mean_b))
+	}
+
+	// Frame with the detected contours and centres drawn on top
+	pub fn overlay(&self, image: &DynamicImage) -> RgbaImage {
+		let mut overlay = image.to_rgba8();
+		let color = Rgba([255, 40, 40, 255]);
+
+		for droplet in &self.droplets {
+			for &(x, y) in &droplet.contour {
+				overlay.put_pixel(x, y, color);
+			}
+
+			let (cx, cy) = (droplet.center.0 as i64, droplet.center.1 as i64);
+			for d in -3..=3 {
+				for (x, y) in [(cx + d, cy), (cx, cy + d)] {
+					if x >= 0
+						&& y >= 0 && (x as u32) < overlay.width()
+						&& (y as u32) < overlay.height()
+					{
+						overlay.put_pixel(x as u32, y as u32, color);
+					}
+				}
+			}
+		}
+
+		overlay
+	}
+}
+
+pub fn load(path: &Path) -> Result<DynamicImage, Box<dyn Error>> {
+	Ok(image::open(path)?)
+}
+
+// Threshold maximising the between-class variance of the histogram
+pub fn otsu(gray: &GrayImage) -> u8 {
+	let mut histogram = [0usize; 256];
+	for pixel in gray.pixels() {
+		histogram[pixel.0[0] as usize] += 1;
+	}
+
+	let total = gray.pixels().len() as f64;
+	let sum: f64 = histogram
+		.iter()
+		.enumerate()
+		.map(|(i, &count)| i as f64 * count as f64)
+		.sum();
+
+	let (mut weight_low, mut sum_low) = (0.0, 0.0);
+	let (mut best, mut best_variance) = (0, 0.0);
+
+	for (t, &count) in histogram.iter().enumerate() {
+		weight_low += count as f64;
+		sum_low += t as f64 * count as f64;
+
+		let weight_high = total - weight_low;
+		if weight_low == 0.0 || weight_high == 0.0 {
+			continue;
+		}
+
+		let (mean_low, mean_high) = (sum_low / weight_low, (sum - sum_low) / weight_high);
+		let variance = weight_low * weight_high * (mean_low - mean_high).powi(2);
+
+		if variance > best_variance {
+			best = t;
+			best_variance = variance;
+		}
+	}
+
+	best as u8
+}
+
+/*
+ * Otsu threshold, 4-connected components, and an ellipse with the same
+ * second moments for each component. Components touching the border are cut
+ * off by the frame and smaller ones than min_area are noise, both are
+ * skipped. The channel is assumed to run horizontally in the frame, so the
+ * axis closer to horizontal is the droplet's length.
+ */
+pub fn analyse(
+	image: &DynamicImage,
+	um_per_px: f32,
+	polarity: Polarity,
+	min_area: usize,
+) -> Result<Analysis, Box<dyn Error>> {
+	if !(um_per_px.is_finite() && um_per_px > 0.0) {
+		return Err("Calibration must be a positive number of µm per pixel".into());
+	}
+
+	let gray = image.to_luma8();
+	let (width, height) = gray.dimensions();
+	let threshold = otsu(&gray);
+
+	let dark = gray.pixels().filter(|p| p.0[0] <= threshold).count();
+	let dark_droplets = match polarity {
+		Polarity::Dark => true,
+		Polarity::Bright => false,
+		Polarity::Auto => 2 * dark <= gray.pixels().len(),
+	};
+
+	let foreground: Vec<bool> = gray
+		.pixels()
+		.map(|p| (p.0[0] <= threshold) == dark_droplets)
+		.collect();
+
+	let index = |x: u32, y: u32| (y * width + x) as usize;
+	let mut visited = vec![false; foreground.len()];
+	let mut droplets = Vec::new();
+
+	for start in 0..foreground.len() {
+		if !foreground[start] || visited[start] {
+			continue;
+		}
+
+		// -------------------- FLOOD FILL
+		let mut pixels = Vec::new();
+		let mut queue = VecDeque::from([start]);
+		visited[start] = true;
+
+		while let Some(i) = queue.pop_front() {
+			let (x, y) = ((i as u32) % width, (i as u32) / width);
+			pixels.push((x, y));
+
+			let neighbours = [
+				(x > 0).then(|| (x - 1, y)),
+				(x + 1 < width).then(|| (x + 1, y)),
+				(y > 0).then(|| (x, y - 1)),
+				(y + 1 < height).then(|| (x, y + 1)),
+			];
+
+			for (nx, ny) in neighbours.into_iter().flatten() {
+				let j = index(nx, ny);
+				if foreground[j] && !visited[j] {
+					visited[j] = true;
+					queue.push_back(j);
+				}
+			}
+		}
+
+		let touches_border = pixels
+			.iter()
+			.any(|&(x, y)| x == 0 || y == 0 || x + 1 == width || y + 1 == height);
+
+		if touches_border || pixels.len() < min_area {
+			continue;
+		}
+
+		// -------------------- ELLIPSE
+		let n = pixels.len() as f32;
+		let (cx, cy) = pixels.iter().fold((0.0, 0.0), |(sx, sy), &(x, y)| {
+			(sx + x as f32, sy + y as f32)
+		});
+		let (cx, cy) = (cx / n, cy / n);
+
+		let (mut xx, mut yy, mut xy) = (0.0, 0.0, 0.0);
+		for &(x, y) in &pixels {
+			let (dx, dy) = (x as f32 - cx, y as f32 - cy);
+			xx += dx * dx;
+			yy += dy * dy;
+			xy += dx * dy;
+		}
+		let (xx, yy, xy) = (xx / n, yy / n, xy / n);
+
+		// eigenvalues of the covariance matrix
+		let spread = (((xx - yy) / 2.0).powi(2) + xy * xy).sqrt();
+		let (major, minor) = ((xx + yy) / 2.0 + spread, (xx + yy) / 2.0 - spread);
+		let angle = 0.5 * (2.0 * xy).atan2(xx - yy);
+
+		// a uniform ellipse with semi-axis a has variance a²/4 along it
+		let (major, minor) = (4.0 * major.sqrt(), 4.0 * minor.max(0.0).sqrt());
+		let (along, across) = if angle.cos().abs() >= std::f32::consts::FRAC_1_SQRT_2 {
+			(major, minor)
+		} else {
+			(minor, major)
+		};
+
+		let contour = pixels
+			.iter()
+			.copied()
+			.filter(|&(x, y)| {
+				[(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+					.iter()
+					.any(|&(nx, ny)| !foreground[index(nx, ny)])
+			})
+			.collect();
+
+		droplets.push(Measurement {
+			dim_a: along * um_per_px,
+			dim_b: across * um_per_px,
+			center: (cx, cy),
+			angle,
+			area: pixels.len(),
+			contour,
+		});
+	}
+
+	Ok(Analysis {
+		threshold,
+		droplets,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use image::Luma;
+
+	// centre and semi-axes in px
+	type Ellipse = ((f32, f32), (f32, f32));
+
+	// Dark ellipses on a bright background
+	fn frame(ellipses: &[Ellipse]) -> DynamicImage {
+		let image = GrayImage::from_fn(200, 100, |x, y| {
+			let inside = ellipses.iter().any(|&((cx, cy), (a, b))| {
+				((x as f32 - cx) / a).powi(2) + ((y as f32 - cy) / b).powi(2) <= 1.0
+			});
+			Luma([if inside { 40 } else { 200 }])
+		});
+
+		DynamicImage::ImageLuma8(image)
+	}
+
+	#[test]
+	fn measures_ellipses() {
+		// two 40 x 20 px droplets and one cut off by the border
+		let image = frame(&[
+			((50.0, 50.0), (20.0, 10.0)),
+			((120.0, 50.0), (20.0, 10.0)),
+			((195.0, 50.0), (20.0, 10.0)),
+		]);
+
+		let analysis = analyse(&image, 2.0, Polarity::Auto, 20).unwrap();
+		assert_eq!(analysis.droplets.len(), 2);
+
+		let (dim_a, dim_b) = analysis.mean().unwrap();
+		assert!((dim_a - 80.0).abs() < 3.0, "{dim_a}");
+		assert!((dim_b - 40.0).abs() < 3.0, "{dim_b}");
+
+		let (cv_a, cv_b) = analysis.cv().unwrap();
+		assert!(cv_a < 0.01 && cv_b < 0.01);
+	}
+}
@@ -0,0 +1,238 @@
+// What iDrop does, logged to a rotating file, to stderr and to the log scene
+use env_logger::filter::{Builder, Filter};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::{
+	collections::VecDeque,
+	error::Error,
+	fs::{self, File, OpenOptions},
+	io::Write,
+	path::{Path, PathBuf},
+	sync::Mutex,
+};
+
+use crate::{history::timestamp, storage::user_dir};
+
+// The file is rotated past this size, keeping this many rotated files
+const MAX_SIZE: u64 = 1024 * 1024;
+const KEEP: usize = 3;
+// Entries kept for the log scene
+const RECENT: usize = 1000;
+
+static ENTRIES: Mutex<VecDeque<Entry>> = Mutex::new(VecDeque::new());
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+	pub time: String, // RFC 3339, UTC
+	pub level: Level,
+	// module the entry comes from, or "python" for the training script's output
+	pub target: String,
+	pub message: String,
+}
+
+impl Entry {
+	// One JSON object per line, so the file can be read by other tools
+	fn to_json(&self) -> String {
+		serde_json::json!({
+			"time": self.time,
+			"level": self.level.as_str(),
+			"target": self.target,
+			"message": self.message,
+		})
+		.to_string()
+	}
+}
+
+impl std::fmt::Display for Entry {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{} {:<5} {}: {}",
+			self.time, self.level, self.target, self.message
+		)
+	}
+}
+
+pub fn log_path() -> PathBuf {
+	user_dir().join("logs").join("idrop.log")
+}
+
+// Latest entries, oldest first
+pub fn recent() -> Vec<Entry> {
+	ENTRIES
+		.lock()
+		.map(|entries| entries.iter().cloned().collect())
+		.unwrap_or_default()
+}
+
+/*
+ * Logs iDrop at debug, the training script's output at info and other
+ * crates at warn. RUST_LOG adds to that, e.g. RUST_LOG=tract_core=debug.
+ */
+pub fn init() -> Result<(), Box<dyn Error>> {
+	let mut filter = Builder::new();
+	filter
+		.filter_level(LevelFilter::Warn)
+		.filter_module("drop_gui", LevelFilter::Debug)
+		.filter_module("app", LevelFilter::Debug)
+		.filter_module("python", LevelFilter::Info);
+
+	if let Ok(spec) = std::env::var("RUST_LOG") {
+		filter.parse(&spec);
+	}
+
+	let filter = filter.build();
+	let max_level = filter.filter();
+
+	log::set_boxed_logger(Box::new(Logger {
+		filter,
+		file: Mutex::new(LogFile::open(log_path(), MAX_SIZE)?),
+	}))?;
+	log::set_max_level(max_level);
+
+	Ok(())
+}
+
+struct Logger {
+	filter: Filter,
+	file: Mutex<LogFile>,
+}
+
+impl Log for Logger {
+	fn enabled(&self, metadata: &Metadata) -> bool {
+		self.filter.enabled(metadata)
+	}
+
+	fn log(&self, record: &Record) {
+		if !self.filter.matches(record) {
+			return;
+		}
+
+		let entry = Entry {
+			time: timestamp(),
+			level: record.level(),
+			target: record.target().to_owned(),
+			message: record.args().to_string(),
+		};
+
+		eprintln!("{entry}");
+
+		// a log that cannot be written has nowhere to report it
+		if let Ok(mut file) = self.file.lock() {
+			let _ = file.write(&entry);
+		}
+
+		if let Ok(mut entries) = ENTRIES.lock() {
+			if entries.len() == RECENT {
+				entries.pop_front();
+			}
+			entries.push_back(entry);
+		}
+	}
+
+	fn flush(&self) {
+		if let Ok(mut file) = self.file.lock() {
+			let _ = file.file.flush();
+		}
+	}
+}
+
+// idrop.log, rotated to idrop.log.1 up to idrop.log.KEEP
+struct LogFile {
+	path: PathBuf,
+	file: File,
+	size: u64,
+	max_size: u64,
+}
+
+impl LogFile {
+	fn open(path: PathBuf, max_size: u64) -> Result<Self, Box<dyn Error>> {
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		let file = OpenOptions::new().create(true).append(true).open(&path)?;
+		let size = file.metadata()?.len();
+
+		Ok(LogFile {
+			path,
+			file,
+			size,
+			max_size,
+		})
+	}
+
+	fn write(&mut self, entry: &Entry) -> Result<(), Box<dyn Error>> {
+		let line = entry.to_json() + "\n";
+
+		if self.size > 0 && self.size + line.len() as u64 > self.max_size {
+			self.rotate()?;
+		}
+
+		self.file.write_all(line.as_bytes())?;
+		self.size += line.len() as u64;
+
+		Ok(())
+	}
+
+	fn rotate(&mut self) -> Result<(), Box<dyn Error>> {
+		for i in (1..KEEP).rev() {
+			let older = rotated(&self.path, i);
+			if older.exists() {
+				fs::rename(older, rotated(&self.path, i + 1))?;
+			}
+		}
+
+		fs::rename(&self.path, rotated(&self.path, 1))?;
+		*self = LogFile::open(self.path.clone(), self.max_size)?;
+
+		Ok(())
+	}
+}
+
+fn rotated(path: &Path, i: usize) -> PathBuf {
+	let mut name = path.as_os_str().to_owned();
+	name.push(format!(".{i}"));
+
+	PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rotates_log_files() {
+		let dir = std::env::temp_dir().join("idrop-log-test");
+		let _ = fs::remove_dir_all(&dir);
+		let path = dir.join("idrop.log");
+
+		let entry = Entry {
+			time: String::from("2026-10-19T12:00:00Z"),
+			level: Level::Info,
+			target: String::from("python"),
+			message: String::from("Epoch 1/100"),
+		};
+		let line = entry.to_json().len() as u64 + 1;
+
+		// two entries fit, so every other entry rotates the file
+		let mut file = LogFile::open(path.clone(), 2 * line).unwrap();
+		for _ in 0..9 {
+			file.write(&entry).unwrap();
+		}
+
+		assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+		assert!(rotated(&path, KEEP).exists());
+		assert!(!rotated(&path, KEEP + 1).exists());
+
+		let json: serde_json::Value = serde_json::from_str(
+			fs::read_to_string(rotated(&path, 1))
+				.unwrap()
+				.lines()
+				.next()
+				.unwrap(),
+		)
+		.unwrap();
+		assert_eq!(json["level"], "INFO");
+		assert_eq!(json["message"], "Epoch 1/100");
+	}
+}
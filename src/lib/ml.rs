@@ -1,22 +1,302 @@
+use log::Level;
 use pyo3::{
-	types::{PyModule, PyString},
+	exceptions::{PyOSError, PyValueError},
+	pyclass, pymethods,
+	types::{PyList, PyModule, PyString},
 	Py, PyAny, PyResult, Python,
 };
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::BTreeMap,
+	error::Error,
+	path::{Path, PathBuf},
+	process::Command,
+};
+
+use crate::{
+	config::Config,
+	fluids::FluidPair,
+	storage::{load_json, save_json},
+	units::Units,
+	views::prediction_ui::Device,
+};
 
-pub fn create_model(path: &str, model_name: &str) -> PyResult<()> {
+/*
+ * Python is embedded, so another interpreter cannot run the script. Putting
+ * that interpreter's module paths first lets training use the packages, such
+ * as TensorFlow, installed for it.
+ */
+pub fn create_model(
+	path: &str,
+	model_name: &str,
+	seed: u64,
+	python: Option<&Path>,
+) -> PyResult<Training> {
 	let python_code = include_str!("../create_model.py");
 
-	let from_py: PyResult<_> = Python::with_gil(|py| -> PyResult<Py<PyAny>> {
-		let script = PyModule::from_code(py, python_code, "", "")?;
-		let path = PyString::new(py, path);
-		let model_name = PyString::new(py, model_name);
-		let new_model: Py<PyAny> = script.getattr("new_model")?.into();
-		new_model.call1(py, (path, model_name))
+	let module_paths = match python {
+		Some(python) => interpreter_paths(python).map_err(|e| PyOSError::new_err(e.to_string()))?,
+		None => Vec::new(),
+	};
+
+	log::info!("Training {model_name} on {path} with seed {seed}");
+	log::debug!("Module paths added for training: {module_paths:?}");
+
+	let from_py: PyResult<String> = Python::with_gil(|py| {
+		let sys = py.import("sys")?;
+		let sys_path: &PyList = sys.getattr("path")?.downcast()?;
+		// the interpreter stays up between trainings, so only missing paths are added
+		let mut i = 0;
+		for module_path in &module_paths {
+			if !sys_path.contains(module_path)? {
+				sys_path.insert(i, module_path)?;
+				i += 1;
+			}
+		}
+
+		let (stdout, stderr) = (sys.getattr("stdout")?, sys.getattr("stderr")?);
+		let logs = [
+			Py::new(py, PythonLog::new(Level::Info))?,
+			Py::new(py, PythonLog::new(Level::Warn))?,
+		];
+		sys.setattr("stdout", &logs[0])?;
+		sys.setattr("stderr", &logs[1])?;
+
+		let result = PyModule::from_code(py, python_code, "", "").and_then(|script| {
+			let path = PyString::new(py, path);
+			let model_name = PyString::new(py, model_name);
+			let new_model: Py<PyAny> = script.getattr("new_model")?.into();
+			new_model.call1(py, (path, model_name, seed))?.extract(py)
+		});
+
+		for log in &logs {
+			log.borrow_mut(py).flush();
+		}
+		sys.setattr("stdout", stdout)?;
+		sys.setattr("stderr", stderr)?;
+
+		result
 	});
 
-	if let Err(e) = from_py {
-		return Err(e);
-	};
+	if let Err(e) = &from_py {
+		log::error!("Training {model_name} failed: {e}");
+	}
+
+	let training: Training =
+		serde_json::from_str(&from_py?).map_err(|e| PyValueError::new_err(e.to_string()))?;
+	log::info!(
+		"Trained {model_name}, loss {} and validation loss {}",
+		training.metrics.loss,
+		training.metrics.val_loss
+	);
+
+	Ok(training)
+}
+
+/*
+ * Stands in for sys.stdout or sys.stderr while training, so what Keras and
+ * TensorFlow print ends up in the log. Output written by TensorFlow's native
+ * code bypasses Python and still goes to the terminal.
+ */
+#[pyclass]
+struct PythonLog {
+	level: Level,
+	// written text not yet ended by a newline
+	line: String,
+}
+
+impl PythonLog {
+	fn new(level: Level) -> Self {
+		PythonLog {
+			level,
+			line: String::new(),
+		}
+	}
+
+	fn log(&self, line: &str) {
+		// progress bars redraw after a carriage return, only the last state is kept
+		let shown = line.rsplit('\r').next().unwrap_or_default().trim();
+
+		if !shown.is_empty() {
+			log::log!(target: "python", self.level, "{shown}");
+		}
+	}
+}
+
+#[pymethods]
+impl PythonLog {
+	fn write(&mut self, text: &str) -> usize {
+		self.line.push_str(text);
+
+		while let Some(end) = self.line.find('\n') {
+			let line: String = self.line.drain(..=end).collect();
+			self.log(&line);
+		}
+
+		text.chars().count()
+	}
+
+	fn flush(&mut self) {
+		let line = std::mem::take(&mut self.line);
+		self.log(&line);
+	}
+
+	fn isatty(&self) -> bool {
+		false
+	}
+}
+
+// What the training script reports back
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Training {
+	pub metrics: Metrics,
+	// package to version, e.g. "tensorflow": "2.11.0"
+	pub versions: BTreeMap<String, String>,
+}
+
+// Mean absolute errors of the last epoch, µL/min
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Metrics {
+	pub loss: f32,
+	pub val_loss: f32,
+}
+
+/*
+ * Everything a model's training depended on, so a result can be traced back
+ * and the model trained again with identical settings.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fingerprint {
+	pub trained: String, // RFC 3339, UTC
+	pub seed: u64,
+	// commit of iDrop, None outside a git checkout
+	pub idrop: Option<String>,
+	pub versions: BTreeMap<String, String>,
+	pub data: PathBuf,
+	pub data_hash: String, // SHA-256
+	pub config: Config,
+	pub metrics: Metrics,
+}
+
+impl Fingerprint {
+	// Parts of the environment that differ, which could explain different metrics
+	pub fn differences(&self, other: &Fingerprint) -> Vec<String> {
+		let mut differences = Vec::new();
+
+		if self.idrop != other.idrop {
+			differences.push(format!(
+				"iDrop {} → {}",
+				self.idrop.as_deref().unwrap_or("unknown"),
+				other.idrop.as_deref().unwrap_or("unknown")
+			));
+		}
+
+		let packages: std::collections::BTreeSet<&String> =
+			self.versions.keys().chain(other.versions.keys()).collect();
+
+		for package in packages {
+			let (before, after) = (self.versions.get(package), other.versions.get(package));
+			if before != after {
+				differences.push(format!(
+					"{package} {} → {}",
+					before.map_or("missing", String::as_str),
+					after.map_or("missing", String::as_str)
+				));
+			}
+		}
+
+		if self.config != other.config {
+			differences.push(String::from("Settings changed"));
+		}
+
+		differences
+	}
+}
+
+// Full hash of the commit iDrop was built from, recorded by build.rs, with -dirty if it had changes
+pub fn git_commit() -> Option<String> {
+	option_env!("IDROP_COMMIT").map(String::from)
+}
+
+// sys.path of an interpreter, which also checks that it runs
+pub fn interpreter_paths(python: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+	let output = Command::new(python)
+		.args(["-c", "import sys, json; print(json.dumps(sys.path))"])
+		.output()?;
+
+	if !output.status.success() {
+		log::warn!(
+			"{} did not run: {}",
+			python.display(),
+			String::from_utf8_lossy(&output.stderr).trim()
+		);
+		return Err(format!("{} did not run", python.display()).into());
+	}
+
+	let paths: Vec<String> = serde_json::from_slice(&output.stdout)?;
+
+	Ok(paths.into_iter().filter(|path| !path.is_empty()).collect())
+}
+
+// Metadata stored next to a model, `model.onnx` -> `model.json`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelInfo {
+	pub fluid_pair: Option<FluidPair>,
+	// of the training data, models without them were trained in the base units
+	#[serde(default)]
+	pub units: Units,
+	// None for models trained before fingerprints were recorded
+	#[serde(default)]
+	pub fingerprint: Option<Fingerprint>,
+	// device the training data was measured on
+	#[serde(default)]
+	pub device: Option<Device>,
+}
+
+impl ModelInfo {
+	pub fn sidecar(model: &Path) -> PathBuf {
+		model.with_extension("json")
+	}
+
+	pub fn load(model: &Path) -> Result<Self, Box<dyn Error>> {
+		load_json(&Self::sidecar(model))
+	}
+
+	pub fn save(&self, model: &Path) -> Result<(), Box<dyn Error>> {
+		save_json(&Self::sidecar(model), self)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn lists_environment_differences() {
+		let original = Fingerprint {
+			trained: String::from("2026-10-19T12:00:00Z"),
+			seed: 7,
+			idrop: Some(String::from("abc")),
+			versions: BTreeMap::from([
+				(String::from("numpy"), String::from("1.24.0")),
+				(String::from("tensorflow"), String::from("2.11.0")),
+			]),
+			data: PathBuf::from("data.csv"),
+			data_hash: String::from("00"),
+			config: Config::default(),
+			metrics: Metrics::default(),
+		};
+		assert!(original.differences(&original).is_empty());
+
+		let mut reproduced = original.clone();
+		reproduced.versions.remove("numpy");
+		reproduced
+			.versions
+			.insert(String::from("tensorflow"), String::from("2.12.0"));
 
-	Ok(())
+		assert_eq!(
+			original.differences(&reproduced),
+			["numpy 1.24.0 → missing", "tensorflow 2.11.0 → 2.12.0"]
+		);
+	}
 }
@@ -1,40 +1,155 @@
+pub mod animation;
 pub mod app; // reexports
 pub mod colors;
+pub mod config;
+pub mod dataset;
+pub mod experiments;
+pub mod export;
+pub mod fluids;
+pub mod focus;
+pub mod frequency;
+pub mod history;
+pub mod imaging;
+pub mod logging;
 pub mod ml;
+pub mod optimize;
+pub mod physics;
+pub mod plot;
+pub mod predictor;
+pub mod project;
+pub mod sensitivity;
+pub mod shortcuts;
+pub mod storage;
 pub mod styling;
+pub mod sweep;
+pub mod units;
 pub mod views;
 
-use iced::{alignment, Color, Point, Rectangle, Vector};
+use iced::{alignment, mouse, Point, Rectangle, Size, Vector};
 use iced::{
-	canvas::{Cursor, Frame, Geometry, Path, Stroke},
+	canvas::{event, Cursor, Event, Frame, Geometry, Path, Stroke},
 	pure::widget::canvas::{self, Program},
 };
+use physics::Channel;
+use styling::Theme;
 use views::SceneMessage;
 
 #[derive(Debug)]
 pub struct Droplet {
 	pub radii: (f32, f32),
+	/*
+	 * Valid (min, max) of length and height in µm. With a range the droplet
+	 * is drawn to scale and its handles can be dragged to set the dimensions.
+	 */
+	pub range: Option<[(f32, f32); 2]>,
+	// Channel the droplet is drawn in, to scale
+	pub channel: Option<Channel>,
+	pub theme: Theme,
 }
 
-impl Program<SceneMessage> for Droplet {
-	type State = ();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Confinement {
+	Free,
+	// longer than the channel is wide, so squeezed by the walls
+	Confined,
+	// as wide as the channel, blocking it
+	Plug,
+}
 
-	fn draw(&self, _state: &Self::State, bounds: Rectangle, _cursor: Cursor) -> Vec<Geometry> {
-		let mut frame = Frame::new(bounds.size());
-		let center = frame.center();
-		let stroke_width = 1.0;
-		let padding = 10.0;
-		let drop_width = (frame.width() / 2.0) - padding - stroke_width;
-		let drop_height = (frame.height() / 2.0) - padding - stroke_width;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Handle {
+	Length,
+	Height,
+}
+
+#[derive(Debug, Default)]
+pub struct DropletState {
+	dragging: Option<Handle>,
+}
+
+const PADDING: f32 = 10.0;
+const HANDLE_SIZE: f32 = 8.0;
+// room kept below the channel for the scale bar
+const SCALE_BAR_SPACE: f32 = 20.0;
+
+// Rounds a rough step to 1, 2 or 5 times a power of ten
+fn nice_step(rough: f32) -> f32 {
+	let magnitude = 10_f32.powf(rough.log10().floor());
+
+	match rough / magnitude {
+		r if r < 1.5 => magnitude,
+		r if r < 3.5 => 2.0 * magnitude,
+		r if r < 7.5 => 5.0 * magnitude,
+		_ => 10.0 * magnitude,
+	}
+}
+
+impl Droplet {
+	// µm per step the handles snap to
+	fn snap(&self) -> f32 {
+		match self.range {
+			Some([(a_min, a_max), (b_min, b_max)]) => {
+				nice_step(((a_max - a_min).max(b_max - b_min) / 50.0).max(0.1))
+			}
+			None => 1.0,
+		}
+	}
+
+	/*
+	 * Pixels per µm when drawn to scale. The frame fits the droplet, the
+	 * largest droplet of the valid range and a stretch of channel with a
+	 * margin beyond its walls.
+	 */
+	fn scale(&self, size: Size) -> Option<f32> {
+		if self.range.is_none() && self.channel.is_none() {
+			return None;
+		}
 
-		let drop_fill = Color::from_rgb8(63, 183, 250);
-		let drop_outline = Color::from_rgb8(100, 100, 100);
+		let (dim_b, dim_a) = self.radii;
+		let (mut horizontal, mut vertical) = (dim_a, dim_b);
+
+		if let Some([(_, a_max), (_, b_max)]) = self.range {
+			horizontal = horizontal.max(a_max);
+			vertical = vertical.max(b_max);
+		}
+
+		if let Some(channel) = self.channel {
+			horizontal = horizontal.max(2.0 * channel.width);
+			vertical = vertical.max(1.25 * channel.width);
+		}
+
+		let scale = ((size.width - 2.0 * PADDING) / horizontal)
+			.min((size.height - 2.0 * (PADDING + SCALE_BAR_SPACE)) / vertical);
+
+		(scale.is_finite() && scale > 0.0).then_some(scale)
+	}
+
+	fn confinement(&self) -> Confinement {
+		let (dim_b, dim_a) = self.radii;
+
+		match self.channel {
+			Some(channel) if dim_b >= channel.width => Confinement::Plug,
+			Some(channel) if dim_a >= channel.width => Confinement::Confined,
+			_ => Confinement::Free,
+		}
+	}
+
+	// Horizontal and vertical semi-axes of the drawn ellipse in pixels
+	fn semi_axes(&self, size: Size) -> (f32, f32) {
+		let (dim_b, dim_a) = self.radii;
+
+		if let Some(scale) = self.scale(size) {
+			return (dim_a * scale / 2.0, dim_b * scale / 2.0);
+		}
+
+		let drop_width = (size.width / 2.0) - PADDING - 1.0;
+		let drop_height = (size.height / 2.0) - PADDING - 1.0;
 
 		let (mut x, mut y) = self.radii;
 
 		let aspect = x / y;
 
-		if x.is_normal() & x.is_normal() {
+		if x.is_normal() && y.is_normal() {
 			if aspect >= 1.0 {
 				x = drop_width;
 				y = drop_width / aspect;
@@ -44,6 +159,123 @@ impl Program<SceneMessage> for Droplet {
 			}
 		}
 
+		// the ellipse is rotated by a quarter turn when drawn
+		(y, x)
+	}
+
+	fn handles(&self, size: Size) -> [(Handle, Point); 2] {
+		let center = Point::new(size.width / 2.0, size.height / 2.0);
+		let (x, y) = self.semi_axes(size);
+
+		[
+			(Handle::Length, Point::new(center.x + x, center.y)),
+			(Handle::Height, Point::new(center.x, center.y - y)),
+		]
+	}
+
+	fn handle_at(&self, size: Size, position: Point) -> Option<Handle> {
+		self.range?;
+
+		self.handles(size)
+			.into_iter()
+			.find(|(_, point)| point.distance(position) <= HANDLE_SIZE)
+			.map(|(handle, _)| handle)
+	}
+
+	// Dimension in µm for a handle dragged to position, snapped and clamped
+	fn dimension(&self, handle: Handle, size: Size, position: Point) -> Option<f32> {
+		let scale = self.scale(size)?;
+		let [length, height] = self.range?;
+		let center = Point::new(size.width / 2.0, size.height / 2.0);
+
+		let (offset, (min, max)) = match handle {
+			Handle::Length => ((position.x - center.x).abs(), length),
+			Handle::Height => ((position.y - center.y).abs(), height),
+		};
+
+		let snap = self.snap();
+		let value = (2.0 * offset / scale / snap).round() * snap;
+
+		Some(value.clamp(min, max))
+	}
+
+	fn format(&self, value: f32) -> String {
+		let decimals = (-self.snap().log10()).ceil().max(0.0) as usize;
+		format!("{value:.decimals$}")
+	}
+}
+
+impl Program<SceneMessage> for Droplet {
+	type State = DropletState;
+
+	fn update(
+		&self,
+		state: &mut Self::State,
+		event: Event,
+		bounds: Rectangle,
+		cursor: Cursor,
+	) -> (event::Status, Option<SceneMessage>) {
+		let position = match cursor.position_from(bounds.position()) {
+			Some(position) => position,
+			None => return (event::Status::Ignored, None),
+		};
+
+		match event {
+			Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
+				if cursor.is_over(&bounds) =>
+			{
+				state.dragging = self.handle_at(bounds.size(), position);
+
+				match state.dragging {
+					Some(_) => (event::Status::Captured, None),
+					None => (event::Status::Ignored, None),
+				}
+			}
+			Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+				let handle = match state.dragging {
+					Some(handle) => handle,
+					None => return (event::Status::Ignored, None),
+				};
+
+				let message = self
+					.dimension(handle, bounds.size(), position)
+					.map(|value| {
+						// in µm, the prediction scene converts them to the units it shows
+						let (dim_b, dim_a) = self.radii;
+
+						match handle {
+							Handle::Length => SceneMessage::UseDimensions(value, dim_b),
+							Handle::Height => SceneMessage::UseDimensions(dim_a, value),
+						}
+					});
+
+				(event::Status::Captured, message)
+			}
+			Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+				if state.dragging.is_some() =>
+			{
+				state.dragging = None;
+				(event::Status::Captured, None)
+			}
+			_ => (event::Status::Ignored, None),
+		}
+	}
+
+	fn draw(&self, state: &Self::State, bounds: Rectangle, cursor: Cursor) -> Vec<Geometry> {
+		let mut frame = Frame::new(bounds.size());
+		let center = frame.center();
+		let stroke_width = 1.0;
+
+		let palette = self.theme.extended_palette();
+		let drop_fill = palette.primary.weak.color;
+		let drop_outline = palette.background.strong.color;
+		let warning = palette.danger.weak.color;
+		let danger = palette.danger.strong.color;
+		let foreground = palette.background.base.text;
+
+		let (x, y) = self.semi_axes(frame.size());
+		let confinement = self.confinement();
+
 		let droplet_frame = Path::new(|path| {
 			path.move_to(Point::ORIGIN);
 			path.line_to(Point {
@@ -59,7 +291,7 @@ impl Program<SceneMessage> for Droplet {
 		let background = Path::new(|path| {
 			path.ellipse(canvas::path::arc::Elliptical {
 				center,
-				radii: Vector { x, y },
+				radii: Vector { x: y, y: x },
 				start_angle: 0.0,
 				end_angle: 2.0 * std::f32::consts::PI,
 				rotation: std::f32::consts::FRAC_PI_2,
@@ -76,13 +308,93 @@ impl Program<SceneMessage> for Droplet {
 			horizontal_alignment: alignment::Horizontal::Left,
 			vertical_alignment: alignment::Vertical::Top,
 			size: 15.0,
+			color: foreground,
 			..canvas::Text::default()
 		};
 
+		// backdrop, a shade off the theme's background, under everything else
+		frame.fill_rectangle(Point::ORIGIN, frame.size(), palette.background.weak.color);
+
+		// -------------------- CHANNEL
+		if let (Some(channel), Some(scale)) = (self.channel, self.scale(frame.size())) {
+			let half_width = channel.width * scale / 2.0;
+
+			for wall in [center.y - half_width, center.y + half_width] {
+				frame.stroke(
+					&Path::line(Point::new(0.0, wall), Point::new(frame.width(), wall)),
+					Stroke {
+						width: 2.0,
+						..stroke
+					},
+				);
+			}
+
+			// scale bar of roughly a quarter of the frame
+			let length = nice_step(frame.width() / 4.0 / scale);
+			let (start, end) = (
+				Point::new(PADDING, frame.height() - PADDING),
+				Point::new(PADDING + length * scale, frame.height() - PADDING),
+			);
+
+			frame.stroke(
+				&Path::new(|path| {
+					path.move_to(Point::new(start.x, start.y - 4.0));
+					path.line_to(start);
+					path.line_to(end);
+					path.line_to(Point::new(end.x, end.y - 4.0));
+				}),
+				Stroke {
+					width: 2.0,
+					color: foreground,
+					..Stroke::default()
+				},
+			);
+			frame.fill_text(canvas::Text {
+				content: format!("{length} µm"),
+				position: Point::new(start.x, start.y - 6.0),
+				color: foreground,
+				size: 13.0,
+				vertical_alignment: alignment::Vertical::Bottom,
+				..canvas::Text::default()
+			});
+		}
+
+		// -------------------- DROPLET
+		let outline = match confinement {
+			Confinement::Free => stroke,
+			Confinement::Confined => Stroke {
+				width: 2.0,
+				color: warning,
+				..stroke
+			},
+			Confinement::Plug => Stroke {
+				width: 2.0,
+				color: danger,
+				..stroke
+			},
+		};
+
 		frame.fill(&background, drop_fill);
-		frame.stroke(&background, stroke);
+		frame.stroke(&background, outline);
 		frame.stroke(&droplet_frame, stroke);
 
+		// confinement cue (top right)
+		let cue = match confinement {
+			Confinement::Free => None,
+			Confinement::Confined => Some(("Confined", warning)),
+			Confinement::Plug => Some(("Plug", danger)),
+		};
+
+		if let Some((content, color)) = cue {
+			frame.fill_text(canvas::Text {
+				content: content.to_owned(),
+				position: Point::new(frame.width() - 2.0, 0.0),
+				color,
+				horizontal_alignment: alignment::Horizontal::Right,
+				..text
+			});
+		}
+
 		// dim a (top left)
 		frame.fill_text(canvas::Text {
 			content: format!("{:2}", self.radii.0.to_string()),
@@ -102,19 +414,125 @@ impl Program<SceneMessage> for Droplet {
 			..text
 		});
 
-		frame.fill_rectangle(
-			Point::ORIGIN,
-			frame.size(),
-			Color::from_rgba8(0, 0, 0, 0.35),
-		);
+		// -------------------- HANDLES
+		if self.range.is_some() {
+			let hovered = cursor
+				.position_in(&bounds)
+				.and_then(|position| self.handle_at(frame.size(), position));
+
+			for (handle, point) in self.handles(frame.size()) {
+				let active = state.dragging == Some(handle) || hovered == Some(handle);
+				let size = if active {
+					HANDLE_SIZE * 1.5
+				} else {
+					HANDLE_SIZE
+				};
+
+				frame.fill_rectangle(
+					Point::new(point.x - size / 2.0, point.y - size / 2.0),
+					Size::new(size, size),
+					if active {
+						palette.primary.strong.color
+					} else {
+						palette.background.base.color
+					},
+				);
+				frame.stroke(
+					&Path::rectangle(
+						Point::new(point.x - size / 2.0, point.y - size / 2.0),
+						Size::new(size, size),
+					),
+					stroke,
+				);
+			}
+
+			// values next to the cursor while hovering or dragging
+			if let Some(position) = cursor.position_in(&bounds) {
+				let (dim_b, dim_a) = self.radii;
+
+				frame.fill_text(canvas::Text {
+					content: format!("{} × {} µm", self.format(dim_a), self.format(dim_b)),
+					position: Point::new(position.x + 12.0, position.y + 12.0),
+					..text
+				});
+			}
+		}
 
 		vec![frame.into_geometry()]
 	}
+
+	fn mouse_interaction(
+		&self,
+		state: &Self::State,
+		bounds: Rectangle,
+		cursor: Cursor,
+	) -> mouse::Interaction {
+		if state.dragging.is_some() {
+			return mouse::Interaction::Grabbing;
+		}
+
+		match cursor.position_in(&bounds) {
+			Some(position) if self.handle_at(bounds.size(), position).is_some() => {
+				mouse::Interaction::Grab
+			}
+			_ => mouse::Interaction::default(),
+		}
+	}
 }
 
 #[cfg(test)]
 mod tests {
 	use super::ml::create_model;
+	use super::*;
+
+	#[test]
+	fn droplet_handles_snap_and_clamp() {
+		let droplet = Droplet {
+			radii: (50.0, 100.0),
+			range: Some([(20.0, 200.0), (20.0, 200.0)]),
+			channel: None,
+			theme: Theme::Light,
+		};
+		let size = Size::new(220.0, 260.0);
+
+		// 1 px per µm, handles snap to 5 µm
+		assert_eq!(droplet.snap(), 5.0);
+		assert_eq!(
+			droplet.handle_at(size, Point::new(161.0, 130.0)),
+			Some(Handle::Length)
+		);
+		assert_eq!(
+			droplet.dimension(Handle::Length, size, Point::new(171.0, 130.0)),
+			Some(120.0)
+		);
+		assert_eq!(
+			droplet.dimension(Handle::Height, size, Point::new(110.0, 125.0)),
+			Some(20.0)
+		);
+	}
+
+	#[test]
+	fn droplet_confinement() {
+		let channel = Channel {
+			width: 100.0,
+			height: 100.0,
+		};
+		let droplet = |length: f32, height: f32| Droplet {
+			radii: (height, length),
+			range: None,
+			channel: Some(channel),
+			theme: Theme::Light,
+		};
+
+		assert_eq!(droplet(80.0, 80.0).confinement(), Confinement::Free);
+		assert_eq!(droplet(150.0, 80.0).confinement(), Confinement::Confined);
+		assert_eq!(droplet(150.0, 100.0).confinement(), Confinement::Plug);
+
+		// 50 and 300 µm droplets are no longer drawn the same size
+		let size = Size::new(220.0, 220.0);
+		assert!(droplet(300.0, 50.0).semi_axes(size).0 > droplet(50.0, 50.0).semi_axes(size).0);
+	}
+
 	#[test]
 	fn model_creation() {
 		use std::path::PathBuf;
@@ -131,6 +549,8 @@ mod tests {
 			model_save_path
 				.to_str()
 				.expect("could not unwrap model_save_path"),
+			0,
+			None,
 		) {
 			eprintln!("{msg}");
 			panic!();
@@ -0,0 +1,354 @@
+// Searching for pump settings that produce a requested droplet
+use std::error::Error;
+
+use crate::predictor::Predictor;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Objective {
+	#[default]
+	MaxFrequency,
+	MinTotalFlow,
+}
+
+impl Objective {
+	pub const ALL: [Self; 2] = [Objective::MaxFrequency, Objective::MinTotalFlow];
+}
+
+impl std::fmt::Display for Objective {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				Objective::MaxFrequency => "Highest frequency",
+				Objective::MinTotalFlow => "Lowest total flow",
+			}
+		)
+	}
+}
+
+// What the user asks for, every goal is optional
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Goals {
+	pub length: Option<f32>,         // µm
+	pub height: Option<f32>,         // µm
+	pub min_freq: Option<f32>,       // Hz
+	pub max_total_flow: Option<f32>, // µL/min
+	pub flow1: (Option<f32>, Option<f32>),
+	pub flow2: (Option<f32>, Option<f32>),
+	pub objective: Objective,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candidate {
+	pub inputs: [f32; 3], // dim_a, dim_b, freq
+	pub flows: [f32; 2],  // flow1, flow2
+	pub cost: f32,
+	pub feasible: bool,
+}
+
+const TARGET_WEIGHT: f32 = 10.0;
+const CONSTRAINT_WEIGHT: f32 = 100.0;
+const OBJECTIVE_WEIGHT: f32 = 0.1;
+// relative deviation from a target length/height still counted as a hit
+const TARGET_TOLERANCE: f32 = 0.02;
+
+impl Goals {
+	// Squared relative violations of the constraints, zero when all hold
+	fn violation(&self, [_, _, freq]: [f32; 3], [flow1, flow2]: [f32; 2]) -> f32 {
+		let below = |value: f32, bound: Option<f32>| match bound {
+			Some(bound) if value < bound => ((bound - value) / bound.abs().max(1.0)).powi(2),
+			_ => 0.0,
+		};
+		let above = |value: f32, bound: Option<f32>| match bound {
+			Some(bound) if value > bound => ((value - bound) / bound.abs().max(1.0)).powi(2),
+			_ => 0.0,
+		};
+
+		below(freq, self.min_freq)
+			+ above(flow1 + flow2, self.max_total_flow)
+			+ below(flow1, self.flow1.0)
+			+ above(flow1, self.flow1.1)
+			+ below(flow2, self.flow2.0)
+			+ above(flow2, self.flow2.1)
+	}
+
+	fn deviation(&self, [dim_a, dim_b, _]: [f32; 3]) -> f32 {
+		let relative = |value: f32, target: Option<f32>| match target {
+			Some(target) => ((value - target) / target).abs(),
+			None => 0.0,
+		};
+
+		relative(dim_a, self.length).max(relative(dim_b, self.height))
+	}
+
+	fn candidate(&self, inputs: [f32; 3], flows: [f32; 2], bounds: &[(f32, f32); 3]) -> Candidate {
+		if !flows.iter().all(|flow| flow.is_finite()) {
+			return Candidate {
+				inputs,
+				flows,
+				cost: f32::INFINITY,
+				feasible: false,
+			};
+		}
+
+		let deviation = self.deviation(inputs);
+		let violation = self.violation(inputs, flows);
+
+		let objective = match self.objective {
+			Objective::MaxFrequency => {
+				let (lo, hi) = bounds[2];
+				1.0 - (inputs[2] - lo) / (hi - lo).max(f32::EPSILON)
+			}
+			Objective::MinTotalFlow => {
+				let scale = self.max_total_flow.unwrap_or(100.0).max(1.0);
+				(flows[0] + flows[1]) / scale
+			}
+		};
+
+		Candidate {
+			inputs,
+			flows,
+			cost: TARGET_WEIGHT * deviation.powi(2)
+				+ CONSTRAINT_WEIGHT * violation
+				+ OBJECTIVE_WEIGHT * objective,
+			feasible: violation <= 1e-6 && deviation <= TARGET_TOLERANCE,
+		}
+	}
+}
+
+/*
+ * Multi-start Nelder-Mead over the model's input domain. The domain is first
+ * scanned on a coarse grid in one batched inference, the best grid points
+ * seed local searches, and the refined points are ranked by cost. Inputs
+ * with a target start the searches there, so they mostly move along the
+ * free ones.
+ */
+pub fn optimize(
+	predictor: &dyn Predictor,
+	goals: &Goals,
+	bounds: [(f32, f32); 3],
+	count: usize,
+) -> Result<Vec<Candidate>, Box<dyn Error>> {
+	const GRID: usize = 8;
+	const STARTS: usize = 6;
+
+	let to_inputs = |u: &[f32]| -> [f32; 3] {
+		let mut inputs = [0.0; 3];
+		for (i, input) in inputs.iter_mut().enumerate() {
+			let (lo, hi) = bounds[i];
+			*input = lo + u[i].clamp(0.0, 1.0) * (hi - lo);
+		}
+		inputs
+	};
+
+	// -------------------- COARSE GRID
+	let step = |i: usize| i as f32 / (GRID - 1) as f32;
+	let grid: Vec<[f32; 3]> = (0..GRID.pow(3))
+		.map(|i| [step(i % GRID), step(i / GRID % GRID), step(i / GRID / GRID)])
+		.collect();
+
+	let inputs: Vec<[f32; 3]> = grid.iter().map(|u| to_inputs(u)).collect();
+	let flows = predictor.predict_batch(&inputs)?;
+
+	let mut ranked_grid: Vec<(f32, [f32; 3])> = grid
+		.iter()
+		.zip(inputs.iter().zip(&flows))
+		.map(|(u, (inputs, flows))| (goals.candidate(*inputs, *flows, &bounds).cost, *u))
+		.collect();
+	ranked_grid.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+	// the best grid points, moved onto the targets and without repeats
+	let targets = [goals.length, goals.height, None];
+	let mut seeds: Vec<[f32; 3]> = Vec::new();
+	for (_, u) in ranked_grid {
+		let mut seed = u;
+		for (i, target) in targets.iter().enumerate() {
+			if let Some(target) = target {
+				let (lo, hi) = bounds[i];
+				seed[i] = ((target - lo) / (hi - lo).max(f32::EPSILON)).clamp(0.0, 1.0);
+			}
+		}
+
+		if !seeds.contains(&seed) {
+			seeds.push(seed);
+		}
+		if seeds.len() == STARTS {
+			break;
+		}
+	}
+
+	// -------------------- LOCAL SEARCH
+	let mut candidates = Vec::new();
+
+	for seed in &seeds {
+		let mut error = None;
+		let mut cost = |u: &[f32]| {
+			let inputs = to_inputs(u);
+			match predictor.predict(inputs) {
+				Ok(flows) => goals.candidate(inputs, flows, &bounds).cost,
+				Err(e) => {
+					error.get_or_insert(e);
+					f32::INFINITY
+				}
+			}
+		};
+
+		let (best, _) = nelder_mead(&mut cost, seed, 0.1, 200);
+
+		if let Some(e) = error {
+			return Err(e);
+		}
+
+		let inputs = to_inputs(&best);
+		candidates.push(goals.candidate(inputs, predictor.predict(inputs)?, &bounds));
+	}
+
+	candidates.sort_by(|a, b| a.cost.total_cmp(&b.cost));
+
+	// drop local searches that converged onto the same point
+	let mut ranked: Vec<Candidate> = Vec::new();
+	for candidate in candidates {
+		let distinct = ranked.iter().all(|other| {
+			candidate
+				.inputs
+				.iter()
+				.zip(other.inputs)
+				.zip(bounds)
+				.any(|((a, b), (lo, hi))| (a - b).abs() > 0.01 * (hi - lo))
+		});
+
+		if distinct && candidate.cost.is_finite() {
+			ranked.push(candidate);
+		}
+	}
+
+	ranked.truncate(count);
+
+	Ok(ranked)
+}
+
+// Derivative-free simplex minimisation, returns the best point and its cost
+pub fn nelder_mead(
+	f: &mut impl FnMut(&[f32]) -> f32,
+	start: &[f32],
+	step: f32,
+	iterations: usize,
+) -> (Vec<f32>, f32) {
+	let n = start.len();
+
+	let mut simplex: Vec<(Vec<f32>, f32)> = (0..=n)
+		.map(|i| {
+			let mut point = start.to_vec();
+			if i > 0 {
+				point[i - 1] += step;
+			}
+			let cost = f(&point);
+			(point, cost)
+		})
+		.collect();
+
+	let blend = |a: &[f32], b: &[f32], t: f32| -> Vec<f32> {
+		a.iter().zip(b).map(|(a, b)| a + t * (b - a)).collect()
+	};
+
+	for _ in 0..iterations {
+		simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+		let spread = simplex[n].1 - simplex[0].1;
+		if spread.abs() < 1e-7 {
+			break;
+		}
+
+		let centroid: Vec<f32> = (0..n)
+			.map(|d| simplex[..n].iter().map(|(p, _)| p[d]).sum::<f32>() / n as f32)
+			.collect();
+
+		let worst = simplex[n].clone();
+
+		let reflected = blend(&centroid, &worst.0, -1.0);
+		let reflected_cost = f(&reflected);
+
+		if reflected_cost < simplex[0].1 {
+			let expanded = blend(&centroid, &worst.0, -2.0);
+			let expanded_cost = f(&expanded);
+
+			simplex[n] = if expanded_cost < reflected_cost {
+				(expanded, expanded_cost)
+			} else {
+				(reflected, reflected_cost)
+			};
+		} else if reflected_cost < simplex[n - 1].1 {
+			simplex[n] = (reflected, reflected_cost);
+		} else {
+			let contracted = blend(&centroid, &worst.0, 0.5);
+			let contracted_cost = f(&contracted);
+
+			if contracted_cost < worst.1 {
+				simplex[n] = (contracted, contracted_cost);
+			} else {
+				// shrink everything towards the best point
+				let best = simplex[0].0.clone();
+				for vertex in simplex.iter_mut().skip(1) {
+					vertex.0 = blend(&best, &vertex.0, 0.5);
+					vertex.1 = f(&vertex.0);
+				}
+			}
+		}
+	}
+
+	simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+	simplex.swap_remove(0)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	struct Linear;
+
+	impl Predictor for Linear {
+		fn name(&self) -> String {
+			String::from("linear")
+		}
+
+		fn predict_batch(&self, inputs: &[[f32; 3]]) -> Result<Vec<[f32; 2]>, Box<dyn Error>> {
+			Ok(inputs
+				.iter()
+				.map(|[dim_a, dim_b, freq]| [dim_a * 0.1 + freq * 0.1, dim_b * 0.1])
+				.collect())
+		}
+	}
+
+	#[test]
+	fn nelder_mead_finds_minimum() {
+		let (best, cost) = nelder_mead(
+			&mut |p: &[f32]| (p[0] - 1.0).powi(2) + (p[1] + 2.0).powi(2),
+			&[0.0, 0.0],
+			0.5,
+			500,
+		);
+
+		assert!(cost < 1e-5);
+		assert!((best[0] - 1.0).abs() < 1e-2 && (best[1] + 2.0).abs() < 1e-2);
+	}
+
+	#[test]
+	fn respects_goals() {
+		let goals = Goals {
+			length: Some(120.0),
+			min_freq: Some(200.0),
+			max_total_flow: Some(150.0),
+			..Goals::default()
+		};
+
+		let bounds = [(50.0, 300.0), (50.0, 300.0), (10.0, 1000.0)];
+		let candidates = optimize(&Linear, &goals, bounds, 3).unwrap();
+		let best = candidates[0];
+
+		assert!(best.feasible);
+		assert!((best.inputs[0] - 120.0).abs() < 0.02 * 120.0);
+		assert!(best.inputs[2] >= 200.0 * 0.99);
+		assert!(best.flows[0] + best.flows[1] <= 150.0 * 1.01);
+	}
+}
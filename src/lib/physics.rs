@@ -0,0 +1,299 @@
+// Dimensionless groups describing a flow-focusing / T-junction droplet generator.
+//
+// All inputs are taken in the units used across the UI and converted to SI
+// internally:
+// - lengths in µm
+// - flow rates in µL/min
+// - viscosities in mPa·s
+// - densities in kg/m³
+// - interfacial tension in mN/m
+use serde::{Deserialize, Serialize};
+
+const MICRO: f32 = 1e-6;
+const MILLI: f32 = 1e-3;
+// 1 µL/min expressed in m³/s
+const UL_PER_MIN: f32 = 1e-9 / 60.0;
+// 1 µL expressed in µm³
+const UM3_PER_UL: f32 = 1e9;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FluidProperties {
+	pub viscosity: f32, // mPa·s
+	pub density: f32,   // kg/m³
+}
+
+// Rectangular cross-section of the channel the droplets are generated in.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Channel {
+	pub width: f32,  // µm
+	pub height: f32, // µm
+}
+
+impl Channel {
+	pub fn area(&self) -> f32 {
+		self.width * MICRO * self.height * MICRO
+	}
+
+	pub fn hydraulic_diameter(&self) -> f32 {
+		2.0 * self.width * self.height / (self.width + self.height) * MICRO
+	}
+
+	// Mean velocity in m/s of a flow in µL/min through the channel
+	pub fn mean_velocity(&self, flow: f32) -> f32 {
+		flow * UL_PER_MIN / self.area()
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlowConditions {
+	pub continuous: FluidProperties,
+	pub dispersed: FluidProperties,
+	pub interfacial_tension: f32, // mN/m
+	pub channel: Channel,
+	pub flow_continuous: f32, // µL/min
+	pub flow_dispersed: f32,  // µL/min
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DimensionlessNumbers {
+	// viscous vs. interfacial stresses of the continuous phase
+	pub capillary: f32,
+	// inertial vs. viscous forces of the continuous phase
+	pub reynolds: f32,
+	// inertial vs. interfacial forces of the dispersed phase
+	pub weber: f32,
+	// Qd / Qc
+	pub flow_ratio: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Regime {
+	Squeezing,
+	Dripping,
+	Jetting,
+}
+
+impl FlowConditions {
+	// None unless both flows, the tension and the viscosity are positive
+	pub fn dimensionless(&self) -> Option<DimensionlessNumbers> {
+		let positive = [
+			self.flow_continuous,
+			self.flow_dispersed,
+			self.interfacial_tension,
+			self.continuous.viscosity,
+		];
+		if !positive
+			.iter()
+			.all(|value| value.is_finite() && *value > 0.0)
+		{
+			return None;
+		}
+
+		let area = self.channel.area();
+		let length = self.channel.hydraulic_diameter();
+		let tension = self.interfacial_tension * MILLI;
+
+		let u_c = self.flow_continuous * UL_PER_MIN / area;
+		let u_d = self.flow_dispersed * UL_PER_MIN / area;
+		let mu_c = self.continuous.viscosity * MILLI;
+
+		Some(DimensionlessNumbers {
+			capillary: mu_c * u_c / tension,
+			reynolds: self.continuous.density * u_c * length / mu_c,
+			weber: self.dispersed.density * u_d * u_d * length / tension,
+			flow_ratio: self.flow_dispersed / self.flow_continuous,
+		})
+	}
+}
+
+impl DimensionlessNumbers {
+	/*
+	 * Regime boundaries follow the usual rules of thumb for confined
+	 * geometries: breakup is pressure driven (squeezing) below Ca ≈ 0.01,
+	 * and a jet forms once viscous and inertial forces together outweigh
+	 * interfacial tension (Ca + We ≳ 1, Utada et al. 2007).
+	 */
+	pub fn regime(&self) -> Regime {
+		if self.capillary + self.weber >= 1.0 {
+			Regime::Jetting
+		} else if self.capillary < 0.01 {
+			Regime::Squeezing
+		} else {
+			Regime::Dripping
+		}
+	}
+}
+
+// Geometry assumed when turning the measured droplet dimensions into a volume
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum VolumeModel {
+	// free droplet, depth limited by the channel height
+	#[default]
+	Ellipsoid,
+	// droplet squashed between the top and bottom walls
+	Plug,
+	// unconfined droplet, diameter averaged from both dimensions
+	Sphere,
+}
+
+impl VolumeModel {
+	pub const ALL: [Self; 3] = [
+		VolumeModel::Ellipsoid,
+		VolumeModel::Plug,
+		VolumeModel::Sphere,
+	];
+
+	// Droplet volume in µm³ from its length (dim_a) and height (dim_b) in µm
+	pub fn volume(&self, dim_a: f32, dim_b: f32, channel: Channel) -> f32 {
+		use std::f32::consts::PI;
+
+		match self {
+			VolumeModel::Ellipsoid => PI / 6.0 * dim_a * dim_b * dim_b.min(channel.height),
+			VolumeModel::Plug => {
+				// footprint of a stadium: rectangle with semicircular caps
+				let (long, short) = (dim_a.max(dim_b), dim_a.min(dim_b));
+				let footprint = short * (long - short) + PI / 4.0 * short * short;
+
+				footprint * dim_b.min(channel.height)
+			}
+			VolumeModel::Sphere => {
+				let diameter = (dim_a + dim_b) / 2.0;
+
+				PI / 6.0 * diameter.powi(3)
+			}
+		}
+	}
+
+	// Dispersed flow rate in µL/min implied by producing one droplet per period
+	pub fn implied_flow(&self, dim_a: f32, dim_b: f32, freq: f32, channel: Channel) -> f32 {
+		self.volume(dim_a, dim_b, channel) / UM3_PER_UL * freq * 60.0
+	}
+}
+
+/*
+ * Relative mismatch between the implied and the measured/predicted dispersed
+ * flow, None when there is no positive flow to compare against.
+ */
+pub fn conservation_error(implied: f32, flow: f32) -> Option<f32> {
+	if !flow.is_finite() || flow <= 0.0 || !implied.is_finite() {
+		return None;
+	}
+
+	Some(((implied - flow) / flow).abs())
+}
+
+impl std::fmt::Display for VolumeModel {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				VolumeModel::Ellipsoid => "Ellipsoid",
+				VolumeModel::Plug => "Pancake/plug",
+				VolumeModel::Sphere => "Sphere",
+			}
+		)
+	}
+}
+
+impl std::fmt::Display for Regime {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				Regime::Squeezing => "Squeezing",
+				Regime::Dripping => "Dripping",
+				Regime::Jetting => "Jetting",
+			}
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn conditions(flow_continuous: f32, interfacial_tension: f32) -> FlowConditions {
+		let water = FluidProperties {
+			viscosity: 1.0,
+			density: 1000.0,
+		};
+
+		FlowConditions {
+			continuous: water,
+			dispersed: water,
+			interfacial_tension,
+			channel: Channel {
+				width: 100.0,
+				height: 100.0,
+			},
+			flow_continuous,
+			flow_dispersed: 10.0,
+		}
+	}
+
+	#[test]
+	fn dimensionless_numbers() {
+		// 60 µL/min through 100x100 µm is a mean velocity of 0.1 m/s
+		let numbers = conditions(60.0, 10.0).dimensionless().unwrap();
+
+		assert!((numbers.capillary - 0.01).abs() < 1e-6);
+		assert!((numbers.reynolds - 10.0).abs() < 1e-3);
+		assert!((numbers.flow_ratio - 1.0 / 6.0).abs() < 1e-6);
+
+		// a predicted flow or a tension of zero or less has no regime
+		assert_eq!(conditions(0.0, 10.0).dimensionless(), None);
+		assert_eq!(conditions(-5.0, 10.0).dimensionless(), None);
+		assert_eq!(conditions(60.0, 0.0).dimensionless(), None);
+	}
+
+	#[test]
+	fn droplet_volume() {
+		let channel = Channel {
+			width: 100.0,
+			height: 50.0,
+		};
+
+		let sphere = VolumeModel::Sphere.volume(60.0, 60.0, channel);
+		assert!((sphere - std::f32::consts::PI / 6.0 * 216_000.0).abs() < 1.0);
+
+		// a round pancake is a disc as thick as the channel
+		let disc = VolumeModel::Plug.volume(80.0, 80.0, channel);
+		assert!((disc - std::f32::consts::PI * 1600.0 * 50.0).abs() < 1.0);
+
+		// π/4 nL discs at 100 Hz carry 6·π/4 µL/min
+		let size = 100.0_f32;
+		let flow = VolumeModel::Plug.implied_flow(
+			size,
+			size,
+			100.0,
+			Channel {
+				width: size,
+				height: size,
+			},
+		);
+		assert!((flow - 6.0 * std::f32::consts::PI / 4.0).abs() < 1e-3);
+
+		assert_eq!(conservation_error(3.0, 2.0), Some(0.5));
+		assert_eq!(conservation_error(flow, 0.0), None);
+		assert_eq!(conservation_error(flow, -1.0), None);
+		assert_eq!(conservation_error(flow, f32::NAN), None);
+	}
+
+	#[test]
+	fn regimes() {
+		assert_eq!(
+			conditions(6.0, 10.0).dimensionless().unwrap().regime(),
+			Regime::Squeezing
+		);
+		assert_eq!(
+			conditions(600.0, 10.0).dimensionless().unwrap().regime(),
+			Regime::Dripping
+		);
+		assert_eq!(
+			conditions(60_000.0, 10.0).dimensionless().unwrap().regime(),
+			Regime::Jetting
+		);
+	}
+}
@@ -0,0 +1,295 @@
+// Canvas programs for sweep results
+use iced::{
+	alignment,
+	canvas::{Cache, Cursor, Frame, Geometry, Path, Stroke},
+	pure::widget::canvas::{self, Program},
+	Color, Point, Rectangle, Size,
+};
+
+use crate::{
+	sweep::SweepResult,
+	units::{format_value, Units},
+	views::SceneMessage,
+};
+
+const MARGIN: f32 = 40.0;
+
+// Perceptually ordered blue → green → yellow ramp for t in [0, 1]
+pub fn colormap(t: f32) -> [u8; 3] {
+	const STOPS: [[f32; 3]; 5] = [
+		[68.0, 1.0, 84.0],
+		[59.0, 82.0, 139.0],
+		[33.0, 145.0, 140.0],
+		[94.0, 201.0, 98.0],
+		[253.0, 231.0, 37.0],
+	];
+
+	let t = if t.is_finite() {
+		t.clamp(0.0, 1.0)
+	} else {
+		0.0
+	};
+	let scaled = t * (STOPS.len() - 1) as f32;
+	let i = (scaled as usize).min(STOPS.len() - 2);
+	let f = scaled - i as f32;
+
+	let mut color = [0; 3];
+	for (c, channel) in color.iter_mut().enumerate() {
+		*channel = (STOPS[i][c] + f * (STOPS[i + 1][c] - STOPS[i][c])) as u8;
+	}
+	color
+}
+
+fn label(frame: &mut Frame, content: String, position: Point, horizontal: alignment::Horizontal) {
+	frame.fill_text(canvas::Text {
+		content,
+		position,
+		size: 14.0,
+		horizontal_alignment: horizontal,
+		vertical_alignment: alignment::Vertical::Top,
+		..canvas::Text::default()
+	});
+}
+
+// Labels the x axis below the plot area, in the given units, and the y axis range on its left
+fn axes(
+	frame: &mut Frame,
+	result: &SweepResult,
+	units: &Units,
+	y_range: (String, String),
+	y_name: String,
+) {
+	let x = result.sweep.x;
+	let (width, height) = (frame.width(), frame.height());
+
+	label(
+		frame,
+		format_value(x.variable.from_base(units, x.from)),
+		Point::new(MARGIN, height - MARGIN + 4.0),
+		alignment::Horizontal::Left,
+	);
+	label(
+		frame,
+		format!("{} ({})", x.variable, x.variable.unit(units)),
+		Point::new(MARGIN + (width - 2.0 * MARGIN) / 2.0, height - MARGIN + 4.0),
+		alignment::Horizontal::Center,
+	);
+	label(
+		frame,
+		format_value(x.variable.from_base(units, x.to)),
+		Point::new(width - MARGIN, height - MARGIN + 4.0),
+		alignment::Horizontal::Right,
+	);
+
+	label(
+		frame,
+		y_range.1,
+		Point::new(2.0, MARGIN),
+		alignment::Horizontal::Left,
+	);
+	label(
+		frame,
+		y_range.0,
+		Point::new(2.0, height - MARGIN - 16.0),
+		alignment::Horizontal::Left,
+	);
+	label(
+		frame,
+		y_name,
+		Point::new(MARGIN, 4.0),
+		alignment::Horizontal::Left,
+	);
+}
+
+// -------------------------------------------------- HEATMAP
+/*
+ * A 2-D sweep has up to 250 000 cells, so they are drawn once into the
+ * cache, which the prediction scene clears when the result, the coloured
+ * output or the units change.
+ */
+pub struct Heatmap<'a> {
+	pub result: &'a SweepResult,
+	pub output: usize,
+	pub output_name: String,
+	pub units: Units,
+	pub cache: &'a Cache,
+}
+
+impl<'a> Program<SceneMessage> for Heatmap<'a> {
+	type State = ();
+
+	fn draw(&self, _state: &Self::State, bounds: Rectangle, _cursor: Cursor) -> Vec<Geometry> {
+		let result = self.result;
+
+		let y = match result.sweep.y {
+			Some(axis) => axis,
+			None => return Vec::new(),
+		};
+
+		let heatmap = self.cache.draw(bounds.size(), |frame| {
+			let (columns, rows) = (result.columns(), result.rows());
+			let (lo, hi) = result.range(self.output);
+			let cell = Size::new(
+				(frame.width() - 2.0 * MARGIN) / columns as f32,
+				(frame.height() - 2.0 * MARGIN) / rows as f32,
+			);
+
+			for (i, flows) in result.flows.iter().enumerate() {
+				let (column, row) = (i % columns, i / columns);
+				let [r, g, b] = colormap((flows[self.output] - lo) / (hi - lo).max(f32::EPSILON));
+
+				frame.fill_rectangle(
+					Point::new(
+						MARGIN + column as f32 * cell.width,
+						MARGIN + (rows - 1 - row) as f32 * cell.height,
+					),
+					// overlap by a pixel so no seams show between cells
+					Size::new(cell.width + 1.0, cell.height + 1.0),
+					Color::from_rgb8(r, g, b),
+				);
+			}
+
+			let unit = if self.output == 0 {
+				self.units.flow1
+			} else {
+				self.units.flow2
+			};
+			let to_unit = |value: f32| format_value(unit.from_base(value));
+
+			axes(
+				frame,
+				result,
+				&self.units,
+				(
+					format_value(y.variable.from_base(&self.units, y.from)),
+					format_value(y.variable.from_base(&self.units, y.to)),
+				),
+				format!(
+					"{} ({}), colour: {} {} – {} {unit}",
+					y.variable,
+					y.variable.unit(&self.units),
+					self.output_name,
+					to_unit(lo),
+					to_unit(hi)
+				),
+			);
+		});
+
+		vec![heatmap]
+	}
+}
+
+// -------------------------------------------------- LINE PLOT
+pub struct LinePlot<'a> {
+	pub result: &'a SweepResult,
+	pub output_names: (String, String),
+	pub units: Units,
+}
+
+impl<'a> Program<SceneMessage> for LinePlot<'a> {
+	type State = ();
+
+	fn draw(&self, _state: &Self::State, bounds: Rectangle, _cursor: Cursor) -> Vec<Geometry> {
+		let mut frame = Frame::new(bounds.size());
+		let result = self.result;
+		let units = &self.units;
+
+		// each output in its own unit
+		let flows: Vec<[f32; 2]> = result
+			.flows
+			.iter()
+			.map(|flows| units.flows_from_base(*flows))
+			.collect();
+
+		let (lo, hi) = flows
+			.iter()
+			.flatten()
+			.filter(|value| value.is_finite())
+			.fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &v| {
+				(lo.min(v), hi.max(v))
+			});
+
+		let (width, height) = (frame.width() - 2.0 * MARGIN, frame.height() - 2.0 * MARGIN);
+		let points = flows.len().max(2) - 1;
+		let to_point = |i: usize, value: f32| {
+			Point::new(
+				MARGIN + i as f32 / points as f32 * width,
+				MARGIN + (1.0 - (value - lo) / (hi - lo).max(f32::EPSILON)) * height,
+			)
+		};
+
+		let frame_path = Path::rectangle(Point::new(MARGIN, MARGIN), Size::new(width, height));
+		frame.stroke(
+			&frame_path,
+			Stroke {
+				width: 1.0,
+				color: Color::from_rgb8(150, 150, 150),
+				..Stroke::default()
+			},
+		);
+
+		for (output, color, name) in [
+			(0, Color::from_rgb8(0, 90, 200), &self.output_names.0),
+			(1, Color::from_rgb8(220, 80, 30), &self.output_names.1),
+		] {
+			let line = Path::new(|path| {
+				let mut drawing = false;
+				for (i, flows) in flows.iter().enumerate() {
+					let value = flows[output];
+					if !value.is_finite() {
+						drawing = false;
+						continue;
+					}
+
+					if drawing {
+						path.line_to(to_point(i, value));
+					} else {
+						path.move_to(to_point(i, value));
+						drawing = true;
+					}
+				}
+			});
+
+			frame.stroke(
+				&line,
+				Stroke {
+					width: 2.0,
+					color,
+					..Stroke::default()
+				},
+			);
+
+			// named at its last point, so the lines can be told apart without colour
+			let last = flows
+				.iter()
+				.enumerate()
+				.rev()
+				.find(|(_, flows)| flows[output].is_finite());
+
+			if let Some((i, flows)) = last {
+				frame.fill_text(canvas::Text {
+					content: name.clone(),
+					position: to_point(i, flows[output]),
+					color,
+					size: 14.0,
+					horizontal_alignment: alignment::Horizontal::Right,
+					vertical_alignment: alignment::Vertical::Bottom,
+					..canvas::Text::default()
+				});
+			}
+		}
+
+		axes(
+			&mut frame,
+			result,
+			units,
+			(format_value(lo), format_value(hi)),
+			format!(
+				"blue: {} ({}), orange: {} ({})",
+				self.output_names.0, units.flow1, self.output_names.1, units.flow2
+			),
+		);
+
+		vec![frame.into_geometry()]
+	}
+}
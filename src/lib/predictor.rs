@@ -0,0 +1,227 @@
+// Models mapping droplet (dim_a, dim_b, freq) to pump settings (flow1, flow2)
+use std::{
+	cell::RefCell,
+	collections::HashMap,
+	error::Error,
+	path::{Path, PathBuf},
+};
+use tract_onnx::prelude::*;
+
+use crate::{
+	dataset::Sample,
+	ml::ModelInfo,
+	physics::{Channel, VolumeModel},
+	units::Units,
+};
+
+pub trait Predictor {
+	fn name(&self) -> String;
+
+	// Rows of (dim_a, dim_b, freq) to rows of (flow1, flow2)
+	fn predict_batch(&self, inputs: &[[f32; 3]]) -> Result<Vec<[f32; 2]>, Box<dyn Error>>;
+
+	fn predict(&self, input: [f32; 3]) -> Result<[f32; 2], Box<dyn Error>> {
+		Ok(self.predict_batch(&[input])?[0])
+	}
+}
+
+// -------------------------------------------------- NEURAL NETWORK
+pub struct OnnxPredictor {
+	path: PathBuf,
+	model: InferenceModel,
+	// the model's own, inputs and outputs are converted from and to the base units
+	units: Units,
+	// optimised plans by batch size, iterative searches reuse the same one
+	plans: RefCell<HashMap<usize, TypedRunnableModel<TypedModel>>>,
+}
+
+impl OnnxPredictor {
+	pub fn load(path: &Path) -> TractResult<Self> {
+		log::info!("Loading model {}", path.display());
+
+		Ok(OnnxPredictor {
+			path: path.to_path_buf(),
+			model: tract_onnx::onnx().model_for_path(path)?,
+			units: ModelInfo::load(path).unwrap_or_default().units,
+			plans: RefCell::new(HashMap::new()),
+		})
+	}
+
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+}
+
+impl Predictor for OnnxPredictor {
+	fn name(&self) -> String {
+		self.path
+			.file_name()
+			.and_then(|name| name.to_str())
+			.unwrap_or("ONNX model")
+			.to_owned()
+	}
+
+	// The model is optimised for the batch size, so a whole grid runs in one pass
+	fn predict_batch(&self, inputs: &[[f32; 3]]) -> Result<Vec<[f32; 2]>, Box<dyn Error>> {
+		if inputs.is_empty() {
+			return Ok(Vec::new());
+		}
+
+		let mut plans = self.plans.borrow_mut();
+
+		let model = match plans.entry(inputs.len()) {
+			std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+			std::collections::hash_map::Entry::Vacant(entry) => entry.insert(
+				self.model
+					.clone()
+					.with_input_fact(0, f32::fact([inputs.len(), 3]).into())?
+					.into_optimized()?
+					.into_runnable()?,
+			),
+		};
+
+		let vals: Vec<f32> = inputs
+			.iter()
+			.flat_map(|input| self.units.inputs_from_base(*input))
+			.collect();
+		let input = tract_ndarray::Array2::from_shape_vec((inputs.len(), 3), vals)?;
+
+		let result = model.run(tvec!(input.into()))?;
+		let out = result[0].to_array_view::<f32>()?;
+
+		Ok(out
+			.as_slice()
+			.ok_or("model output is not contiguous")?
+			.chunks(2)
+			.map(|flows| self.units.flows_to_base([flows[0], flows[1]]))
+			.collect())
+	}
+}
+
+// -------------------------------------------------- SCALING LAW
+/*
+ * Garstecki's squeezing law relates droplet length to the flow-rate ratio,
+ * L/w = β + α·Qd/Qc, with β = 1 in the original paper. The absolute dispersed
+ * flow follows from mass conservation, Qd = k·V·f, where k corrects for the
+ * volume model not matching the real droplet shape. All three coefficients
+ * are fitted by least squares to a device's training data.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScalingLaw {
+	pub alpha: f32,
+	pub beta: f32,
+	pub k: f32,
+	channel: Channel,
+	volume_model: VolumeModel,
+}
+
+impl ScalingLaw {
+	pub fn fit(
+		samples: &[Sample],
+		channel: Channel,
+		volume_model: VolumeModel,
+	) -> Result<Self, Box<dyn Error>> {
+		if samples.len() < 2 {
+			return Err("at least two samples are needed to fit the scaling law".into());
+		}
+
+		let n = samples.len() as f32;
+		let ratio = |s: &Sample| s.flow2 / s.flow1;
+		let length = |s: &Sample| s.dim_a / channel.width;
+
+		let mean_x = samples.iter().map(ratio).sum::<f32>() / n;
+		let mean_y = samples.iter().map(length).sum::<f32>() / n;
+
+		let (cov, var) = samples.iter().fold((0.0, 0.0), |(cov, var), s| {
+			let dx = ratio(s) - mean_x;
+			(cov + dx * (length(s) - mean_y), var + dx * dx)
+		});
+
+		if var <= f32::EPSILON {
+			return Err("training data needs more than one flow-rate ratio".into());
+		}
+
+		let alpha = cov / var;
+		let beta = mean_y - alpha * mean_x;
+
+		let (qi, ii) = samples.iter().fold((0.0, 0.0), |(qi, ii), s| {
+			let implied = volume_model.implied_flow(s.dim_a, s.dim_b, s.freq, channel);
+			(qi + s.flow2 * implied, ii + implied * implied)
+		});
+
+		Ok(ScalingLaw {
+			alpha,
+			beta,
+			k: qi / ii,
+			channel,
+			volume_model,
+		})
+	}
+}
+
+impl Predictor for ScalingLaw {
+	fn name(&self) -> String {
+		String::from("Scaling law")
+	}
+
+	// Operating points the law cannot reach (Qd/Qc ≤ 0) come out as NaN
+	fn predict_batch(&self, inputs: &[[f32; 3]]) -> Result<Vec<[f32; 2]>, Box<dyn Error>> {
+		Ok(inputs
+			.iter()
+			.map(|&[dim_a, dim_b, freq]| {
+				let ratio = (dim_a / self.channel.width - self.beta) / self.alpha;
+				let dispersed = self.k
+					* self
+						.volume_model
+						.implied_flow(dim_a, dim_b, freq, self.channel);
+
+				if ratio > 0.0 && ratio.is_finite() {
+					[dispersed / ratio, dispersed]
+				} else {
+					[f32::NAN, f32::NAN]
+				}
+			})
+			.collect())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn scaling_law_recovers_coefficients() {
+		let channel = Channel {
+			width: 100.0,
+			height: 100.0,
+		};
+		let volume_model = VolumeModel::Plug;
+		let (alpha, beta, k) = (1.5, 0.8, 2.0);
+
+		let samples: Vec<Sample> = [(120.0, 100.0), (200.0, 200.0), (300.0, 50.0)]
+			.iter()
+			.map(|&(dim_a, freq)| {
+				let dim_b = 80.0;
+				let ratio = (dim_a / channel.width - beta) / alpha;
+				let flow2 = k * volume_model.implied_flow(dim_a, dim_b, freq, channel);
+
+				Sample {
+					dim_a,
+					dim_b,
+					flow1: flow2 / ratio,
+					flow2,
+					freq,
+				}
+			})
+			.collect();
+
+		let law = ScalingLaw::fit(&samples, channel, volume_model).unwrap();
+		assert!((law.alpha - alpha).abs() < 1e-3);
+		assert!((law.beta - beta).abs() < 1e-3);
+		assert!((law.k - k).abs() < 1e-3);
+
+		let [flow1, flow2] = law.predict(samples[1].inputs()).unwrap();
+		assert!((flow1 - samples[1].flow1).abs() < 1e-2 * samples[1].flow1);
+		assert!((flow2 - samples[1].flow2).abs() < 1e-2 * samples[1].flow2);
+	}
+}
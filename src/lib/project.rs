@@ -0,0 +1,417 @@
+// Projects bundle the devices, data, models and history of a study in one directory
+use serde::{Deserialize, Serialize};
+use std::{
+	error::Error,
+	fs,
+	path::{Path, PathBuf},
+};
+
+use crate::{
+	history::{file_hash, timestamp},
+	ml::ModelInfo,
+	physics::Channel,
+	storage::{load_json, save_json, user_dir},
+	views::prediction_ui::Device,
+};
+
+pub const MANIFEST: &str = "idrop.json";
+pub const DATA_DIR: &str = "data";
+pub const MODELS_DIR: &str = "models";
+
+// Layout version of the manifest, projects saved by newer versions are refused
+pub const FORMAT: u32 = 1;
+
+// Files of the data directory that belong to a study
+const STATE: [&str; 4] = [
+	"devices.json",
+	"history.json",
+	"experiments.json",
+	"fluids.json",
+];
+
+const RECENT: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+	pub format: u32,
+	pub name: String,
+	pub saved: String, // RFC 3339, UTC
+	pub devices: Vec<DeviceEntry>,
+	// files of STATE the project has
+	pub files: Vec<String>,
+	// trained or loaded by the user, projects saved before had none
+	#[serde(default)]
+	pub models: Vec<ModelEntry>,
+}
+
+// Paths are relative to the project directory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceEntry {
+	pub name: String,
+	pub channel: Channel,
+	pub dataset: Option<PathBuf>,
+	pub model: Option<PathBuf>,
+	pub model_info: Option<PathBuf>,
+	pub model_hash: Option<String>, // SHA-256
+}
+
+impl DeviceEntry {
+	fn files(&self) -> impl Iterator<Item = &PathBuf> {
+		[&self.dataset, &self.model, &self.model_info]
+			.into_iter()
+			.flatten()
+	}
+}
+
+// Model the user trained or loaded, with its metadata and metrics if it has them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelEntry {
+	pub model: PathBuf,
+	pub model_info: Option<PathBuf>,
+	pub model_hash: String, // SHA-256
+}
+
+impl ModelEntry {
+	fn files(&self) -> impl Iterator<Item = &PathBuf> {
+		[Some(&self.model), self.model_info.as_ref()]
+			.into_iter()
+			.flatten()
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Project {
+	pub dir: PathBuf,
+	pub manifest: Manifest,
+}
+
+impl Project {
+	pub fn open(dir: &Path) -> Result<Self, Box<dyn Error>> {
+		let path = dir.join(MANIFEST);
+		if !path.exists() {
+			return Err(format!("{} is not an iDrop project", dir.display()).into());
+		}
+
+		let manifest: Manifest = serde_json::from_str(&fs::read_to_string(path)?)?;
+		if manifest.format > FORMAT {
+			return Err("This project was saved by a newer version of iDrop".into());
+		}
+
+		let missing: Vec<String> = manifest
+			.files
+			.iter()
+			.map(PathBuf::from)
+			.chain(manifest.devices.iter().flat_map(|d| d.files().cloned()))
+			.chain(manifest.models.iter().flat_map(|m| m.files().cloned()))
+			.filter(|file| !dir.join(file).exists())
+			.map(|file| file.display().to_string())
+			.collect();
+
+		if !missing.is_empty() {
+			return Err(format!("The project is missing {}", missing.join(", ")).into());
+		}
+
+		Ok(Project {
+			dir: dir.to_owned(),
+			manifest,
+		})
+	}
+
+	/*
+	 * Copies the current state, that is the files of the data directory
+	 * source, every device's training data and model, and the user's models,
+	 * into dir and writes the manifest. Saving the open project into its own
+	 * directory only refreshes the manifest.
+	 */
+	pub fn save(dir: &Path, source: &Path, models: &[PathBuf]) -> Result<Self, Box<dyn Error>> {
+		fs::create_dir_all(dir)?;
+
+		let mut files = Vec::new();
+
+		for file in STATE {
+			if let Some(file) = bundle(&source.join(file), Path::new(file), dir)? {
+				files.push(file.display().to_string());
+			}
+		}
+
+		let devices = Device::ALL
+			.into_iter()
+			.map(|device| device_entry(device, dir))
+			.collect::<Result<Vec<_>, _>>()?;
+
+		let mut entries: Vec<ModelEntry> = Vec::new();
+		for model in models {
+			let taken: Vec<&PathBuf> = devices
+				.iter()
+				.flat_map(|device| device.model.as_ref())
+				.chain(entries.iter().map(|entry| &entry.model))
+				.collect();
+
+			entries.push(model_entry(model, &taken, dir)?);
+		}
+
+		let manifest = Manifest {
+			format: FORMAT,
+			name: dir
+				.file_name()
+				.map(|name| name.to_string_lossy().into_owned())
+				.unwrap_or_default(),
+			saved: timestamp(),
+			devices,
+			files,
+			models: entries,
+		};
+
+		save_json(&dir.join(MANIFEST), &manifest)?;
+
+		Ok(Project {
+			dir: dir.to_owned(),
+			manifest,
+		})
+	}
+}
+
+fn device_entry(device: Device, dir: &Path) -> Result<DeviceEntry, Box<dyn Error>> {
+	let model_path = device.model_path();
+	let model_file = Path::new(MODELS_DIR).join(model_path.file_name().unwrap_or_default());
+
+	let dataset = bundle(
+		&device.data_path(),
+		&Path::new(DATA_DIR).join(format!("{device}.csv")),
+		dir,
+	)?;
+	let model = bundle(&model_path, &model_file, dir)?;
+	let model_info = bundle(
+		&ModelInfo::sidecar(&model_path),
+		&ModelInfo::sidecar(&model_file),
+		dir,
+	)?;
+
+	Ok(DeviceEntry {
+		name: device.to_string(),
+		channel: device.channel(),
+		model_hash: model
+			.as_ref()
+			.map(|model| file_hash(&dir.join(model)))
+			.transpose()?,
+		dataset,
+		model,
+		model_info,
+	})
+}
+
+/*
+ * Copies a model and its sidecar into the models directory, under a name
+ * no other model of the project has. A model already in the project keeps
+ * its name.
+ */
+fn model_entry(model: &Path, taken: &[&PathBuf], dir: &Path) -> Result<ModelEntry, Box<dyn Error>> {
+	if !model.exists() {
+		return Err(format!("Model {} is gone", model.display()).into());
+	}
+
+	let stem = model.file_stem().unwrap_or_default().to_string_lossy();
+	let in_project = |file: &PathBuf| {
+		matches!(
+			(model.canonicalize(), dir.join(file).canonicalize()),
+			(Ok(from), Ok(to)) if from == to
+		)
+	};
+
+	let model_file = (1..)
+		.map(|i| match i {
+			1 => Path::new(MODELS_DIR).join(format!("{stem}.onnx")),
+			_ => Path::new(MODELS_DIR).join(format!("{stem}-{i}.onnx")),
+		})
+		.find(|file| in_project(file) || !taken.contains(&file))
+		.unwrap_or_default();
+
+	// a model without metadata must not pick up that of a model saved under its name before
+	let sidecar = dir.join(ModelInfo::sidecar(&model_file));
+	if !ModelInfo::sidecar(model).exists() && sidecar.exists() {
+		fs::remove_file(sidecar)?;
+	}
+
+	let model_path = bundle(model, &model_file, dir)?.ok_or("Model could not be copied")?;
+	let model_info = bundle(
+		&ModelInfo::sidecar(model),
+		&ModelInfo::sidecar(&model_file),
+		dir,
+	)?;
+
+	Ok(ModelEntry {
+		model_hash: file_hash(&dir.join(&model_path))?,
+		model: model_path,
+		model_info,
+	})
+}
+
+// Copies a file into the project, returns its relative path if the project has it
+fn bundle(from: &Path, relative: &Path, dir: &Path) -> Result<Option<PathBuf>, Box<dyn Error>> {
+	let to = dir.join(relative);
+
+	// fs::copy truncates the destination, copying a file onto itself would empty it
+	let same = matches!(
+		(from.canonicalize(), to.canonicalize()),
+		(Ok(from), Ok(to)) if from == to
+	);
+
+	if from.exists() && !same {
+		if let Some(parent) = to.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		fs::copy(from, &to)?;
+	}
+
+	Ok(to.exists().then(|| relative.to_owned()))
+}
+
+// Project directory as listed in the recent projects
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecentProject(pub PathBuf);
+
+impl std::fmt::Display for RecentProject {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.0.file_name() {
+			Some(name) => write!(f, "{}", name.to_string_lossy()),
+			None => write!(f, "{}", self.0.display()),
+		}
+	}
+}
+
+// Most recently opened or saved first, kept in the user's data directory
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecentProjects(pub Vec<RecentProject>);
+
+impl RecentProjects {
+	pub fn path() -> PathBuf {
+		user_dir().join("recent.json")
+	}
+
+	pub fn load() -> Result<Self, Box<dyn Error>> {
+		load_json(&Self::path())
+	}
+
+	pub fn save(&self) -> Result<(), Box<dyn Error>> {
+		save_json(&Self::path(), self)
+	}
+
+	pub fn add(&mut self, dir: &Path) {
+		self.0.retain(|recent| recent.0 != dir);
+		self.0.insert(0, RecentProject(dir.to_owned()));
+		self.0.truncate(RECENT);
+	}
+
+	// Projects moved or deleted since they were opened
+	pub fn prune(&mut self) {
+		self.0.retain(|recent| recent.0.join(MANIFEST).exists());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ml::{Fingerprint, Metrics};
+
+	// Empty directory of its own for each test and test run
+	fn test_dir(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("idrop-{name}-{}", std::process::id()));
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).unwrap();
+		dir
+	}
+
+	#[test]
+	fn saves_and_opens() {
+		let temp = test_dir("project-test");
+		let source = temp.join("state");
+		let dir = temp.join("study");
+		fs::create_dir_all(&source).unwrap();
+		fs::write(source.join("fluids.json"), "[]").unwrap();
+
+		let saved = Project::save(&dir, &source, &[]).unwrap();
+		assert_eq!(saved.manifest.name, "study");
+		assert_eq!(saved.manifest.devices.len(), Device::ALL.len());
+
+		// only the state files the data directory has are copied in
+		assert_eq!(saved.manifest.files, ["fluids.json"]);
+		assert!(dir.join("fluids.json").exists());
+
+		// the bundled training data is copied in
+		let dataset = saved.manifest.devices[0].dataset.clone().unwrap();
+		assert!(dir.join(&dataset).exists());
+
+		assert_eq!(Project::open(&dir).unwrap(), saved);
+
+		fs::remove_file(dir.join(&dataset)).unwrap();
+		assert!(Project::open(&dir).is_err());
+
+		let mut recent = RecentProjects::default();
+		recent.add(&dir);
+		recent.add(Path::new("/elsewhere"));
+		recent.add(&dir);
+		assert_eq!(recent.0.len(), 2);
+		assert_eq!(recent.0[0].to_string(), "study");
+
+		recent.prune();
+		assert_eq!(recent.0, [RecentProject(dir)]);
+
+		fs::remove_dir_all(temp).unwrap();
+	}
+
+	#[test]
+	fn bundles_user_models() {
+		let temp = test_dir("project-models-test");
+		let source = temp.join("state");
+		let dir = temp.join("study");
+
+		// two models of the same name, the first with its metrics
+		let models = [
+			temp.join("trained-a").join("tuned.onnx"),
+			temp.join("trained-b").join("tuned.onnx"),
+		];
+		for model in &models {
+			fs::create_dir_all(model.parent().unwrap()).unwrap();
+			fs::write(model, "onnx").unwrap();
+		}
+
+		let metrics = Metrics {
+			loss: 1.5,
+			val_loss: 2.0,
+		};
+		ModelInfo {
+			fingerprint: Some(Fingerprint {
+				trained: timestamp(),
+				seed: 0,
+				idrop: None,
+				versions: Default::default(),
+				data: PathBuf::from("data.csv"),
+				data_hash: String::new(),
+				config: Default::default(),
+				metrics,
+			}),
+			..ModelInfo::default()
+		}
+		.save(&models[0])
+		.unwrap();
+
+		let saved = Project::save(&dir, &source, &models).unwrap();
+		let entries = &saved.manifest.models;
+		assert_eq!(entries[0].model, Path::new("models/tuned.onnx"));
+		assert_eq!(entries[1].model, Path::new("models/tuned-2.onnx"));
+		assert_eq!(entries[1].model_info, None);
+
+		let opened = Project::open(&dir).unwrap();
+		assert_eq!(opened, saved);
+
+		let info = ModelInfo::load(&dir.join(&entries[0].model)).unwrap();
+		assert_eq!(info.fingerprint.unwrap().metrics, metrics);
+
+		// saving again keeps the names of the models already in the project
+		let bundled: Vec<PathBuf> = entries.iter().map(|entry| dir.join(&entry.model)).collect();
+		let resaved = Project::save(&dir, &source, &bundled).unwrap();
+		assert_eq!(&resaved.manifest.models, entries);
+
+		fs::remove_dir_all(temp).unwrap();
+	}
+}
@@ -0,0 +1,108 @@
+// Local sensitivity of a prediction to its inputs
+use std::error::Error;
+
+use crate::predictor::Predictor;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sensitivity {
+	pub point: [f32; 3], // dim_a, dim_b, freq
+	pub flows: [f32; 2], // flow1, flow2
+	// jacobian[j][i] = ∂flow_i/∂input_j
+	pub jacobian: [[f32; 2]; 3],
+}
+
+impl Sensitivity {
+	/*
+	 * Central finite differences with a step relative to each input. The
+	 * query point and the six perturbed points go through the model as one
+	 * batch.
+	 */
+	pub fn analyse(
+		predictor: &dyn Predictor,
+		point: [f32; 3],
+		relative_step: f32,
+	) -> Result<Self, Box<dyn Error>> {
+		let steps = point.map(|x| (x.abs() * relative_step).max(1e-3));
+
+		let mut inputs = vec![point];
+		for (j, step) in steps.iter().enumerate() {
+			let (mut plus, mut minus) = (point, point);
+			plus[j] += step;
+			minus[j] -= step;
+			inputs.push(plus);
+			inputs.push(minus);
+		}
+
+		let outputs = predictor.predict_batch(&inputs)?;
+
+		let mut jacobian = [[0.0; 2]; 3];
+		for (j, row) in jacobian.iter_mut().enumerate() {
+			let (plus, minus) = (outputs[1 + 2 * j], outputs[2 + 2 * j]);
+			for (i, derivative) in row.iter_mut().enumerate() {
+				*derivative = (plus[i] - minus[i]) / (2.0 * steps[j]);
+			}
+		}
+
+		Ok(Sensitivity {
+			point,
+			flows: outputs[0],
+			jacobian,
+		})
+	}
+
+	// % change of an output for a 1% change of an input
+	pub fn elasticity(&self, input: usize, output: usize) -> f32 {
+		self.jacobian[input][output] * self.point[input] / self.flows[output]
+	}
+
+	/*
+	 * First-order propagation of independent input uncertainties,
+	 * σ_i² = Σ_j (∂flow_i/∂input_j · σ_j)².
+	 */
+	pub fn propagate(&self, uncertainty: [f32; 3]) -> [f32; 2] {
+		let mut variance = [0.0; 2];
+		for (row, sigma) in self.jacobian.iter().zip(uncertainty) {
+			for (v, derivative) in variance.iter_mut().zip(row) {
+				*v += (derivative * sigma).powi(2);
+			}
+		}
+		variance.map(f32::sqrt)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	struct Linear;
+
+	impl Predictor for Linear {
+		fn name(&self) -> String {
+			String::from("linear")
+		}
+
+		fn predict_batch(&self, inputs: &[[f32; 3]]) -> Result<Vec<[f32; 2]>, Box<dyn Error>> {
+			Ok(inputs
+				.iter()
+				.map(|[a, b, f]| [2.0 * a + f, 3.0 * b])
+				.collect())
+		}
+	}
+
+	#[test]
+	fn jacobian_and_uncertainty() {
+		let sensitivity = Sensitivity::analyse(&Linear, [10.0, 20.0, 30.0], 0.01).unwrap();
+
+		assert_eq!(sensitivity.flows, [50.0, 60.0]);
+		assert!((sensitivity.jacobian[0][0] - 2.0).abs() < 1e-2);
+		assert!((sensitivity.jacobian[1][1] - 3.0).abs() < 1e-2);
+		assert!(sensitivity.jacobian[2][1].abs() < 1e-2);
+
+		// flow2 is proportional to dim_b, so elasticity 1
+		assert!((sensitivity.elasticity(1, 1) - 1.0).abs() < 1e-2);
+
+		let [sigma1, sigma2] = sensitivity.propagate([3.0, 1.0, 8.0]);
+		assert!((sigma1 - 10.0).abs() < 0.1);
+		assert!((sigma2 - 3.0).abs() < 0.1);
+	}
+}
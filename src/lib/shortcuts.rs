@@ -0,0 +1,133 @@
+// Keyboard shortcuts, so every action can be reached without the mouse
+use iced_native::{
+	event,
+	keyboard::{self, KeyCode, Modifiers},
+	Event,
+};
+
+use crate::views::Message;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shortcut {
+	Back,
+	Next,
+	// the main button of the current scene
+	Run,
+	Predict,
+	Train,
+	SelectModel,
+	SelectTrainingData,
+	OpenProject,
+	SaveProject,
+	SaveProjectAs,
+}
+
+impl Shortcut {
+	pub const ALL: [Self; 10] = [
+		Shortcut::Back,
+		Shortcut::Next,
+		Shortcut::Run,
+		Shortcut::Predict,
+		Shortcut::Train,
+		Shortcut::SelectModel,
+		Shortcut::SelectTrainingData,
+		Shortcut::OpenProject,
+		Shortcut::SaveProject,
+		Shortcut::SaveProjectAs,
+	];
+
+	// Ctrl is Cmd on macOS
+	pub fn keys(self) -> &'static str {
+		match self {
+			Shortcut::Back => "Alt+Left",
+			Shortcut::Next => "Alt+Right",
+			Shortcut::Run => "Ctrl+Enter",
+			Shortcut::Predict => "Ctrl+P",
+			Shortcut::Train => "Ctrl+T",
+			Shortcut::SelectModel => "Ctrl+M",
+			Shortcut::SelectTrainingData => "Ctrl+D",
+			Shortcut::OpenProject => "Ctrl+O",
+			Shortcut::SaveProject => "Ctrl+S",
+			Shortcut::SaveProjectAs => "Ctrl+Shift+S",
+		}
+	}
+
+	fn from_keys(key_code: KeyCode, modifiers: Modifiers) -> Option<Self> {
+		let shortcut = match (key_code, modifiers.command(), modifiers.shift()) {
+			(KeyCode::Left, false, false) if modifiers.alt() => Shortcut::Back,
+			(KeyCode::Right, false, false) if modifiers.alt() => Shortcut::Next,
+			(KeyCode::Enter | KeyCode::NumpadEnter, true, false) => Shortcut::Run,
+			(KeyCode::P, true, false) => Shortcut::Predict,
+			(KeyCode::T, true, false) => Shortcut::Train,
+			(KeyCode::M, true, false) => Shortcut::SelectModel,
+			(KeyCode::D, true, false) => Shortcut::SelectTrainingData,
+			(KeyCode::O, true, false) => Shortcut::OpenProject,
+			(KeyCode::S, true, false) => Shortcut::SaveProject,
+			(KeyCode::S, true, true) => Shortcut::SaveProjectAs,
+			_ => return None,
+		};
+
+		Some(shortcut)
+	}
+}
+
+impl std::fmt::Display for Shortcut {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				Shortcut::Back => "Previous scene",
+				Shortcut::Next => "Next scene",
+				Shortcut::Run => "Run the scene's main action",
+				Shortcut::Predict => "Predict flow",
+				Shortcut::Train => "Train model",
+				Shortcut::SelectModel => "Select a prediction model",
+				Shortcut::SelectTrainingData => "Select training data",
+				Shortcut::OpenProject => "Open project",
+				Shortcut::SaveProject => "Save project",
+				Shortcut::SaveProjectAs => "Save project as",
+			}
+		)
+	}
+}
+
+// Subscription callback, hence a plain function
+pub fn listen(event: Event, _status: event::Status) -> Option<Message> {
+	match event {
+		Event::Keyboard(keyboard::Event::KeyPressed {
+			key_code,
+			modifiers,
+		}) => Shortcut::from_keys(key_code, modifiers).map(Message::Shortcut),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn maps_keys() {
+		let ctrl = if cfg!(target_os = "macos") {
+			Modifiers::LOGO
+		} else {
+			Modifiers::CTRL
+		};
+
+		assert_eq!(
+			Shortcut::from_keys(KeyCode::Left, Modifiers::ALT),
+			Some(Shortcut::Back)
+		);
+		assert_eq!(Shortcut::from_keys(KeyCode::Left, Modifiers::empty()), None);
+		assert_eq!(
+			Shortcut::from_keys(KeyCode::S, ctrl),
+			Some(Shortcut::SaveProject)
+		);
+		assert_eq!(
+			Shortcut::from_keys(KeyCode::S, ctrl | Modifiers::SHIFT),
+			Some(Shortcut::SaveProjectAs)
+		);
+		assert_eq!(Shortcut::from_keys(KeyCode::P, Modifiers::empty()), None);
+	}
+}
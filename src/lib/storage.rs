@@ -0,0 +1,55 @@
+// Reading and writing the files iDrop keeps between sessions
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+	error::Error,
+	fs,
+	path::{Path, PathBuf},
+	sync::RwLock,
+};
+
+// Open project, whose directory replaces the user's data directory
+static PROJECT: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+// Per-user data directory, e.g. ~/.local/share/iDrop on Linux
+pub fn user_dir() -> PathBuf {
+	dirs_next::data_dir()
+		.unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("assets"))
+		.join("iDrop")
+}
+
+// Where devices, history, experiments and fluids are kept
+pub fn data_dir() -> PathBuf {
+	project_dir().unwrap_or_else(user_dir)
+}
+
+pub fn project_dir() -> Option<PathBuf> {
+	PROJECT.read().ok()?.clone()
+}
+
+pub fn set_project_dir(dir: Option<PathBuf>) {
+	if let Ok(mut project) = PROJECT.write() {
+		*project = dir;
+	}
+}
+
+// Deserializes a JSON file, falling back to the default value if it does not exist yet
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T, Box<dyn Error>> {
+	if !path.exists() {
+		return Ok(T::default());
+	}
+
+	let contents = fs::read_to_string(path)?;
+
+	Ok(serde_json::from_str(&contents)?)
+}
+
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<(), Box<dyn Error>> {
+	if let Some(parent) = path.parent() {
+		fs::create_dir_all(parent)?;
+	}
+
+	fs::write(path, serde_json::to_string_pretty(value)?)?;
+	log::debug!("Saved {}", path.display());
+
+	Ok(())
+}
@@ -1,15 +1,16 @@
 use iced::{
 	alignment,
 	pure::{
-		button, container, pick_list, text_input, toggler,
+		button, container, pick_list, row, text_input, toggler,
 		widget::{Button, Container, Image, PickList, Text, TextInput, Toggler},
 	},
 	Color, ContentFit, Font, Length,
 };
 
-use super::colors::{Extended, Palette, EXTENDED_DARK, EXTENDED_LIGHT};
-use iced_style::{button, menu, pick_list, text_input, toggler};
-use std::borrow::Cow;
+use super::colors::{Extended, Palette, EXTENDED_DARK, EXTENDED_HIGH_CONTRAST, EXTENDED_LIGHT};
+use iced_style::{button, container as container_style, menu, pick_list, text_input, toggler};
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, process::Command};
 
 // Loading custom font
 pub(crate) const BOLD: Font = Font::External {
@@ -17,11 +18,21 @@ pub(crate) const BOLD: Font = Font::External {
 	bytes: include_bytes!("../../assets/fonts/Poppins/Poppins-Bold.ttf"),
 };
 
+// Icon font, glyphs are Font Awesome code points
+pub(crate) const ICONS: Font = Font::External {
+	name: "Font Awesome 6 Free Solid",
+	bytes: include_bytes!("../../assets/fonts/fa-solid-900.otf"),
+};
+
 // Theme profiles
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum Theme {
+	#[default]
 	Light,
 	Dark,
+	HighContrast,
+	// user-defined, see `CustomPalette`
+	Custom(Palette),
 }
 
 impl Theme {
@@ -29,26 +40,153 @@ impl Theme {
 		match self {
 			Self::Light => Palette::LIGHT,
 			Self::Dark => Palette::DARK,
+			Self::HighContrast => Palette::high_contrast(),
+			Self::Custom(palette) => palette,
 		}
 	}
 
-	pub fn extended_palette(&self) -> &Extended {
+	// Custom palettes are extended on every call, the built-in ones only once
+	pub fn extended_palette(&self) -> Extended {
 		match self {
-			Self::Light => &EXTENDED_LIGHT,
-			Self::Dark => &EXTENDED_DARK,
+			Self::Light => *EXTENDED_LIGHT,
+			Self::Dark => *EXTENDED_DARK,
+			Self::HighContrast => *EXTENDED_HIGH_CONTRAST,
+			Self::Custom(palette) => Extended::generate(*palette),
 		}
 	}
 }
 
-impl Default for Theme {
+/*
+ * Theme chosen by the user. System follows the desktop's dark mode, Custom
+ * names one of the palettes in the config file.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ThemePreference {
+	#[default]
+	Light,
+	Dark,
+	HighContrast,
+	System,
+	Custom(String),
+}
+
+// Zoom of the whole interface, in percent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct UiScale(pub u32);
+
+impl UiScale {
+	pub const ALL: [Self; 6] = [
+		UiScale(75),
+		UiScale(100),
+		UiScale(125),
+		UiScale(150),
+		UiScale(175),
+		UiScale(200),
+	];
+
+	pub fn factor(self) -> f64 {
+		self.0 as f64 / 100.0
+	}
+}
+
+impl Default for UiScale {
 	fn default() -> Self {
-		Self::Light
+		UiScale(100)
+	}
+}
+
+impl std::fmt::Display for UiScale {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}%", self.0)
+	}
+}
+
+// Default size of text, in pixels before scaling
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TextSize(pub u16);
+
+impl TextSize {
+	pub const ALL: [Self; 6] = [
+		TextSize(16),
+		TextSize(18),
+		TextSize(20),
+		TextSize(24),
+		TextSize(28),
+		TextSize(32),
+	];
+}
+
+impl Default for TextSize {
+	fn default() -> Self {
+		TextSize(24)
+	}
+}
+
+impl std::fmt::Display for TextSize {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{} px", self.0)
+	}
+}
+
+/*
+ * Reads the desktop's dark mode setting with the platform's own tools, as
+ * there is no portable API for it. None where the setting cannot be read.
+ */
+pub fn system_theme() -> Option<Theme> {
+	let read = |program: &str, args: &[&str]| {
+		Command::new(program)
+			.args(args)
+			.output()
+			.ok()
+			.filter(|output| output.status.success())
+			.map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+	};
+
+	let dark = if cfg!(target_os = "macos") {
+		// the key only exists in dark mode
+		read("defaults", &["read", "-g", "AppleInterfaceStyle"])
+			.is_some_and(|style| style.trim() == "Dark")
+	} else if cfg!(target_os = "windows") {
+		read(
+			"reg",
+			&[
+				"query",
+				r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize",
+				"/v",
+				"AppsUseLightTheme",
+			],
+		)?
+		.contains("0x0")
+	} else {
+		read(
+			"gsettings",
+			&["get", "org.gnome.desktop.interface", "color-scheme"],
+		)?
+		.contains("dark")
+	};
+
+	Some(if dark { Theme::Dark } else { Theme::Light })
+}
+
+// Window background, and the text color every widget inherits
+impl container_style::StyleSheet for Theme {
+	fn style(&self) -> container_style::Style {
+		let palette = self.extended_palette();
+
+		container_style::Style {
+			text_color: Some(palette.background.base.text),
+			background: Some(palette.background.base.color.into()),
+			..Default::default()
+		}
 	}
 }
 
 // Button styles
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Brn {
+	#[default]
 	Primary,
 	Secondary,
 	Positive,
@@ -56,12 +194,6 @@ pub enum Brn {
 	Text,
 }
 
-impl Default for Brn {
-	fn default() -> Self {
-		Self::Primary
-	}
-}
-
 impl button::StyleSheet for Theme {
 	fn active(&self) -> button::Style {
 		let palette = self.extended_palette();
@@ -228,28 +360,68 @@ impl text_input::StyleSheet for Theme {
 
 // custom functions for button, toggler and text_input
 
-pub fn btn<'a, T>(label: &'a str, msg: T) -> Button<'a, T> {
+pub fn btn<'a, T>(theme: Theme, label: &'a str, msg: T) -> Button<'a, T> {
 	button(Text::new(label).horizontal_alignment(alignment::Horizontal::Center))
 		.on_press(msg)
-		.style(Theme::Light)
+		.style(theme)
+}
+
+pub fn icon(glyph: char) -> Text {
+	Text::new(glyph.to_string())
+		.font(ICONS)
+		.width(Length::Units(30))
+		.horizontal_alignment(alignment::Horizontal::Center)
+}
+
+// Sidebar entry, the current one is shown in bold instead of as a button
+pub fn nav_btn<'a, T: Clone + 'a>(
+	theme: Theme,
+	glyph: char,
+	label: &'a str,
+	current: bool,
+	msg: T,
+) -> Container<'a, T> {
+	let content = row()
+		.spacing(10)
+		.align_items(iced::Alignment::Center)
+		.push(icon(glyph))
+		.push(if current {
+			Text::new(label).font(BOLD)
+		} else {
+			Text::new(label)
+		});
+
+	if current {
+		container(content).padding(5)
+	} else {
+		container(
+			button(content)
+				.on_press(msg)
+				.style(theme)
+				.width(Length::Fill),
+		)
+	}
+	.width(Length::Fill)
 }
 
 pub fn tglr<'a, T>(
+	theme: Theme,
 	label: &'a str,
 	is_checked: bool,
 	msg: impl Fn(bool) -> T + 'a,
 ) -> Toggler<'a, T> {
 	toggler(label.to_owned(), is_checked, msg)
-		.style(Theme::Light)
+		.style(theme)
 		.spacing(5)
 }
 
 pub fn tinput<'a, M: Clone>(
+	theme: Theme,
 	place_holder: &str,
 	value: &str,
 	on_change: impl Fn(String) -> M + 'a,
 ) -> TextInput<'a, M> {
-	text_input(place_holder, value, on_change).style(Theme::Light)
+	text_input(place_holder, value, on_change).style(theme)
 }
 
 // function to place logo in the UI
@@ -270,6 +442,7 @@ pub fn logo<'a, M: Clone + 'a>(height: u16, content_fit: ContentFit) -> Containe
 }
 
 pub fn drop_down<'a, M, T, U>(
+	theme: Theme,
 	opt: impl Into<Cow<'a, [T]>>,
 	selected: Option<T>,
 	on_selected: U,
@@ -279,5 +452,5 @@ where
 	[T]: ToOwned<Owned = Vec<T>>,
 	U: Fn(T) -> M + 'a,
 {
-	pick_list(opt, selected, on_selected).style(Theme::Light)
+	pick_list(opt, selected, on_selected).style(theme)
 }
@@ -0,0 +1,369 @@
+// Evaluating a model over a grid of one or two of its inputs
+use image::{Rgb, RgbImage};
+use std::{error::Error, fmt::Write, fs, path::Path};
+
+use crate::{dataset::header, plot::colormap, predictor::Predictor, units::Units};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variable {
+	DimA,
+	DimB,
+	Freq,
+}
+
+impl Variable {
+	pub const ALL: [Self; 3] = [Variable::DimA, Variable::DimB, Variable::Freq];
+
+	// position in the model's input row
+	pub fn index(&self) -> usize {
+		match self {
+			Variable::DimA => 0,
+			Variable::DimB => 1,
+			Variable::Freq => 2,
+		}
+	}
+
+	pub fn unit(&self, units: &Units) -> String {
+		match self {
+			Variable::DimA => units.dim_a.to_string(),
+			Variable::DimB => units.dim_b.to_string(),
+			Variable::Freq => units.freq.to_string(),
+		}
+	}
+
+	pub fn to_base(&self, units: &Units, value: f32) -> f32 {
+		match self {
+			Variable::DimA => units.dim_a.to_base(value),
+			Variable::DimB => units.dim_b.to_base(value),
+			Variable::Freq => units.freq.to_base(value),
+		}
+	}
+
+	pub fn from_base(&self, units: &Units, value: f32) -> f32 {
+		match self {
+			Variable::DimA => units.dim_a.from_base(value),
+			Variable::DimB => units.dim_b.from_base(value),
+			Variable::Freq => units.freq.from_base(value),
+		}
+	}
+}
+
+impl std::fmt::Display for Variable {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				Variable::DimA => "Length",
+				Variable::DimB => "Height",
+				Variable::Freq => "Frequency",
+			}
+		)
+	}
+}
+
+// Steps per axis, which keeps a 2D grid to a batch the model runs in seconds
+pub const MAX_STEPS: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Axis {
+	pub variable: Variable,
+	pub from: f32,
+	pub to: f32,
+	pub steps: usize,
+}
+
+impl Axis {
+	// at most MAX_STEPS values, whatever steps is set to
+	pub fn values(&self) -> Vec<f32> {
+		let steps = self.steps.min(MAX_STEPS);
+		if steps < 2 {
+			return vec![self.from];
+		}
+
+		(0..steps)
+			.map(|i| self.from + (self.to - self.from) * i as f32 / (steps - 1) as f32)
+			.collect()
+	}
+
+	pub fn check(&self) -> Result<(), Box<dyn Error>> {
+		if !(2..=MAX_STEPS).contains(&self.steps) {
+			return Err(format!("Sweep {} in 2 to {MAX_STEPS} steps", self.variable).into());
+		}
+		if !self.from.is_finite() || !self.to.is_finite() || self.from == self.to {
+			return Err(format!("Sweep {} over a range, not a single value", self.variable).into());
+		}
+
+		Ok(())
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sweep {
+	pub x: Axis,
+	pub y: Option<Axis>,
+	// values of the inputs that are not swept
+	pub fixed: [f32; 3],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepResult {
+	pub sweep: Sweep,
+	// row-major, x varies fastest
+	pub inputs: Vec<[f32; 3]>,
+	pub flows: Vec<[f32; 2]>,
+}
+
+impl Sweep {
+	pub fn inputs(&self) -> Vec<[f32; 3]> {
+		let ys = match self.y {
+			Some(axis) => axis.values(),
+			None => vec![0.0],
+		};
+
+		ys.iter()
+			.flat_map(|&y| {
+				self.x.values().into_iter().map(move |x| {
+					let mut input = self.fixed;
+					input[self.x.variable.index()] = x;
+					if let Some(axis) = self.y {
+						input[axis.variable.index()] = y;
+					}
+					input
+				})
+			})
+			.collect()
+	}
+
+	// The whole grid goes through the model as a single batch
+	pub fn run(&self, predictor: &dyn Predictor) -> Result<SweepResult, Box<dyn Error>> {
+		self.x.check()?;
+		if let Some(y) = self.y {
+			y.check()?;
+			if y.variable == self.x.variable {
+				return Err("Sweep two different variables".into());
+			}
+		}
+
+		let inputs = self.inputs();
+		let flows = predictor.predict_batch(&inputs)?;
+
+		Ok(SweepResult {
+			sweep: *self,
+			inputs,
+			flows,
+		})
+	}
+}
+
+impl SweepResult {
+	pub fn columns(&self) -> usize {
+		self.sweep.x.values().len()
+	}
+
+	pub fn rows(&self) -> usize {
+		self.sweep.y.map(|axis| axis.values().len()).unwrap_or(1)
+	}
+
+	// (min, max) of one output over the finite values of the grid
+	pub fn range(&self, output: usize) -> (f32, f32) {
+		self.flows
+			.iter()
+			.map(|flows| flows[output])
+			.filter(|value| value.is_finite())
+			.fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), v| {
+				(lo.min(v), hi.max(v))
+			})
+	}
+
+	// Same columns as the training data, in the given units, which the header records
+	pub fn to_csv(&self, units: &Units) -> String {
+		let mut csv = header(units);
+
+		for (inputs, flows) in self.inputs.iter().zip(&self.flows) {
+			let [dim_a, dim_b, freq] = units.inputs_from_base(*inputs);
+			let [flow1, flow2] = units.flows_from_base(*flows);
+			let _ = write!(csv, "\n{dim_a},{dim_b},{flow1},{flow2},{freq}");
+		}
+
+		csv
+	}
+
+	pub fn save_csv(&self, path: &Path, units: &Units) -> Result<(), Box<dyn Error>> {
+		fs::write(path, self.to_csv(units))?;
+		Ok(())
+	}
+
+	/*
+	 * 2-D sweeps are saved as a heatmap of the chosen output, 1-D sweeps as a
+	 * line plot of both outputs.
+	 */
+	pub fn render(&self, output: usize) -> RgbImage {
+		const CELL: u32 = 8;
+		const SIZE: (u32, u32) = (640, 400);
+
+		let (columns, rows) = (self.columns() as u32, self.rows() as u32);
+
+		if self.sweep.y.is_some() {
+			let (lo, hi) = self.range(output);
+			let mut image = RgbImage::new(columns * CELL, rows * CELL);
+
+			for (i, flows) in self.flows.iter().enumerate() {
+				let (column, row) = (i as u32 % columns, i as u32 / columns);
+				let color = colormap((flows[output] - lo) / (hi - lo).max(f32::EPSILON));
+
+				// y grows upwards in the plot
+				for dy in 0..CELL {
+					for dx in 0..CELL {
+						image.put_pixel(
+							column * CELL + dx,
+							(rows - 1 - row) * CELL + dy,
+							Rgb(color),
+						);
+					}
+				}
+			}
+
+			return image;
+		}
+
+		let mut image = RgbImage::from_pixel(SIZE.0, SIZE.1, Rgb([255, 255, 255]));
+		let (lo, hi) = {
+			let (a, b) = (self.range(0), self.range(1));
+			(a.0.min(b.0), a.1.max(b.1))
+		};
+
+		let to_pixel = |i: usize, value: f32| {
+			let x = i as f32 / (columns.max(2) - 1) as f32 * (SIZE.0 - 1) as f32;
+			let y = (1.0 - (value - lo) / (hi - lo).max(f32::EPSILON)) * (SIZE.1 - 1) as f32;
+			(x as i32, y as i32)
+		};
+
+		for (output, color) in [(0, [0, 90, 200]), (1, [220, 80, 30])] {
+			for (i, pair) in self.flows.windows(2).enumerate() {
+				if pair[0][output].is_finite() && pair[1][output].is_finite() {
+					draw_line(
+						&mut image,
+						to_pixel(i, pair[0][output]),
+						to_pixel(i + 1, pair[1][output]),
+						Rgb(color),
+					);
+				}
+			}
+		}
+
+		image
+	}
+
+	pub fn save_png(&self, path: &Path, output: usize) -> Result<(), Box<dyn Error>> {
+		self.render(output).save(path)?;
+		Ok(())
+	}
+}
+
+// Bresenham's line algorithm
+fn draw_line(image: &mut RgbImage, from: (i32, i32), to: (i32, i32), color: Rgb<u8>) {
+	let (mut x, mut y) = from;
+	let (dx, dy) = ((to.0 - x).abs(), -(to.1 - y).abs());
+	let (sx, sy) = ((to.0 - x).signum(), (to.1 - y).signum());
+	let mut error = dx + dy;
+
+	loop {
+		if x >= 0 && y >= 0 && (x as u32) < image.width() && (y as u32) < image.height() {
+			image.put_pixel(x as u32, y as u32, color);
+		}
+
+		if (x, y) == to {
+			break;
+		}
+
+		let e2 = 2 * error;
+		if e2 >= dy {
+			error += dy;
+			x += sx;
+		}
+		if e2 <= dx {
+			error += dx;
+			y += sy;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::units::{FlowUnit, LengthUnit};
+
+	struct Sum;
+
+	impl Predictor for Sum {
+		fn name(&self) -> String {
+			String::from("sum")
+		}
+
+		fn predict_batch(&self, inputs: &[[f32; 3]]) -> Result<Vec<[f32; 2]>, Box<dyn Error>> {
+			Ok(inputs.iter().map(|[a, b, f]| [a + b, *f]).collect())
+		}
+	}
+
+	#[test]
+	fn grid_runs_as_one_batch() {
+		let sweep = Sweep {
+			x: Axis {
+				variable: Variable::DimA,
+				from: 0.0,
+				to: 10.0,
+				steps: 3,
+			},
+			y: Some(Axis {
+				variable: Variable::Freq,
+				from: 100.0,
+				to: 200.0,
+				steps: 2,
+			}),
+			fixed: [0.0, 1.0, 0.0],
+		};
+
+		let result = sweep.run(&Sum).unwrap();
+
+		assert_eq!((result.columns(), result.rows()), (3, 2));
+		assert_eq!(result.inputs[4], [5.0, 1.0, 200.0]);
+		assert_eq!(result.flows[4], [6.0, 200.0]);
+		assert_eq!(result.range(0), (1.0, 11.0));
+		assert_eq!(result.to_csv(&Units::default()).lines().count(), 7);
+
+		let units = Units::new(LengthUnit::Millimetres, FlowUnit::MicrolitresPerHour);
+		let csv = result.to_csv(&units);
+		// 5 µm, 1 µm, 6 and 200 µL/min, 200 Hz
+		assert_eq!(csv.lines().nth(5).unwrap(), "0.005,0.001,360,12000,200");
+		assert_eq!(result.render(0).dimensions(), (24, 16));
+	}
+
+	#[test]
+	fn rejects_oversized_and_empty_ranges() {
+		let axis = Axis {
+			variable: Variable::DimA,
+			from: 0.0,
+			to: 10.0,
+			steps: 100_000,
+		};
+		let sweep = Sweep {
+			x: axis,
+			y: None,
+			fixed: [0.0; 3],
+		};
+
+		assert_eq!(sweep.inputs().len(), MAX_STEPS);
+		assert!(sweep.run(&Sum).is_err());
+
+		let single = Sweep {
+			x: Axis {
+				to: 0.0,
+				steps: 3,
+				..axis
+			},
+			..sweep
+		};
+		assert!(single.run(&Sum).is_err());
+	}
+}
@@ -0,0 +1,277 @@
+// Units values are entered and shown in, models and data always use µm, Hz and µL/min
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum FlowUnit {
+	#[default]
+	MicrolitresPerMinute,
+	MicrolitresPerHour,
+	NanolitresPerSecond,
+}
+
+impl FlowUnit {
+	pub const ALL: [Self; 3] = [
+		FlowUnit::MicrolitresPerMinute,
+		FlowUnit::MicrolitresPerHour,
+		FlowUnit::NanolitresPerSecond,
+	];
+
+	// Value in this unit of 1 µL/min
+	fn per_base(self) -> f32 {
+		match self {
+			FlowUnit::MicrolitresPerMinute => 1.0,
+			FlowUnit::MicrolitresPerHour => 60.0,
+			FlowUnit::NanolitresPerSecond => 1000.0 / 60.0,
+		}
+	}
+
+	pub fn from_base(self, value: f32) -> f32 {
+		value * self.per_base()
+	}
+
+	pub fn to_base(self, value: f32) -> f32 {
+		value / self.per_base()
+	}
+}
+
+impl std::fmt::Display for FlowUnit {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				FlowUnit::MicrolitresPerMinute => "µL/min",
+				FlowUnit::MicrolitresPerHour => "µL/h",
+				FlowUnit::NanolitresPerSecond => "nL/s",
+			}
+		)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LengthUnit {
+	#[default]
+	Micrometres,
+	Millimetres,
+}
+
+impl LengthUnit {
+	pub const ALL: [Self; 2] = [LengthUnit::Micrometres, LengthUnit::Millimetres];
+
+	// µm in one of this unit
+	fn base_per_unit(self) -> f32 {
+		match self {
+			LengthUnit::Micrometres => 1.0,
+			LengthUnit::Millimetres => 1000.0,
+		}
+	}
+
+	pub fn from_base(self, value: f32) -> f32 {
+		value / self.base_per_unit()
+	}
+
+	pub fn to_base(self, value: f32) -> f32 {
+		value * self.base_per_unit()
+	}
+}
+
+impl std::fmt::Display for LengthUnit {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				LengthUnit::Micrometres => "µm",
+				LengthUnit::Millimetres => "mm",
+			}
+		)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum FrequencyUnit {
+	#[default]
+	Hertz,
+	Kilohertz,
+}
+
+impl FrequencyUnit {
+	pub const ALL: [Self; 2] = [FrequencyUnit::Hertz, FrequencyUnit::Kilohertz];
+
+	// Hz in one of this unit
+	fn base_per_unit(self) -> f32 {
+		match self {
+			FrequencyUnit::Hertz => 1.0,
+			FrequencyUnit::Kilohertz => 1000.0,
+		}
+	}
+
+	pub fn from_base(self, value: f32) -> f32 {
+		value / self.base_per_unit()
+	}
+
+	pub fn to_base(self, value: f32) -> f32 {
+		value * self.base_per_unit()
+	}
+}
+
+impl std::fmt::Display for FrequencyUnit {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				FrequencyUnit::Hertz => "Hz",
+				FrequencyUnit::Kilohertz => "kHz",
+			}
+		)
+	}
+}
+
+/*
+ * Unit of each column of a training CSV, of each field of the prediction
+ * scene, and of a model's inputs and outputs. The default is the base units.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Units {
+	pub dim_a: LengthUnit,
+	pub dim_b: LengthUnit,
+	pub flow1: FlowUnit,
+	pub flow2: FlowUnit,
+	pub freq: FrequencyUnit,
+}
+
+// One field's new unit, as picked next to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnitChoice {
+	DimA(LengthUnit),
+	DimB(LengthUnit),
+	Flow1(FlowUnit),
+	Flow2(FlowUnit),
+	Freq(FrequencyUnit),
+}
+
+impl Units {
+	pub fn new(length: LengthUnit, flow: FlowUnit) -> Self {
+		Units {
+			dim_a: length,
+			dim_b: length,
+			flow1: flow,
+			flow2: flow,
+			freq: FrequencyUnit::default(),
+		}
+	}
+
+	pub fn with(self, choice: UnitChoice) -> Self {
+		match choice {
+			UnitChoice::DimA(unit) => Units {
+				dim_a: unit,
+				..self
+			},
+			UnitChoice::DimB(unit) => Units {
+				dim_b: unit,
+				..self
+			},
+			UnitChoice::Flow1(unit) => Units {
+				flow1: unit,
+				..self
+			},
+			UnitChoice::Flow2(unit) => Units {
+				flow2: unit,
+				..self
+			},
+			UnitChoice::Freq(unit) => Units { freq: unit, ..self },
+		}
+	}
+
+	// dim_a, dim_b and freq
+	pub fn inputs_to_base(&self, [dim_a, dim_b, freq]: [f32; 3]) -> [f32; 3] {
+		[
+			self.dim_a.to_base(dim_a),
+			self.dim_b.to_base(dim_b),
+			self.freq.to_base(freq),
+		]
+	}
+
+	pub fn inputs_from_base(&self, [dim_a, dim_b, freq]: [f32; 3]) -> [f32; 3] {
+		[
+			self.dim_a.from_base(dim_a),
+			self.dim_b.from_base(dim_b),
+			self.freq.from_base(freq),
+		]
+	}
+
+	// flow1 and flow2
+	pub fn flows_to_base(&self, [flow1, flow2]: [f32; 2]) -> [f32; 2] {
+		[self.flow1.to_base(flow1), self.flow2.to_base(flow2)]
+	}
+
+	pub fn flows_from_base(&self, [flow1, flow2]: [f32; 2]) -> [f32; 2] {
+		[self.flow1.from_base(flow1), self.flow2.from_base(flow2)]
+	}
+
+	// Symbols in the order of the training data's columns, dim_a,dim_b,flow1,flow2,freq
+	pub fn symbols(&self) -> [String; 5] {
+		[
+			self.dim_a.to_string(),
+			self.dim_b.to_string(),
+			self.flow1.to_string(),
+			self.flow2.to_string(),
+			self.freq.to_string(),
+		]
+	}
+}
+
+// Four significant digits, enough for a converted value without float noise
+pub fn format_value(value: f32) -> String {
+	if value == 0.0 || !value.is_finite() {
+		return value.to_string();
+	}
+
+	let decimals = (3 - value.abs().log10().floor() as i32).max(0) as usize;
+	format!("{value:.decimals$}")
+}
+
+/*
+ * Unit named by a symbol such as "mm" or "µL/h". A plain u is accepted for
+ * µ, which is awkward to type.
+ */
+pub fn parse_symbol<T: Copy + std::fmt::Display>(all: &[T], symbol: &str) -> Option<T> {
+	let symbol = symbol.trim().replace('u', "µ");
+
+	all.iter().copied().find(|unit| unit.to_string() == symbol)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn converts_to_and_from_base_units() {
+		let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+
+		assert!(close(FlowUnit::MicrolitresPerHour.from_base(2.0), 120.0));
+		assert!(close(FlowUnit::MicrolitresPerHour.to_base(120.0), 2.0));
+		assert!(close(FlowUnit::NanolitresPerSecond.from_base(6.0), 100.0));
+		assert_eq!(LengthUnit::Millimetres.to_base(0.25), 250.0);
+		assert_eq!(LengthUnit::Micrometres.from_base(80.0), 80.0);
+
+		let units = Units::new(LengthUnit::Millimetres, FlowUnit::MicrolitresPerHour)
+			.with(UnitChoice::Freq(FrequencyUnit::Kilohertz));
+		assert_eq!(
+			units.inputs_to_base([0.12, 0.08, 0.2]).map(f32::round),
+			[120.0, 80.0, 200.0]
+		);
+		assert!(close(units.flows_from_base([1.0, 0.5])[1], 30.0));
+
+		assert_eq!(
+			parse_symbol(&FlowUnit::ALL, "uL/min"),
+			Some(FlowUnit::MicrolitresPerMinute)
+		);
+		assert_eq!(parse_symbol(&LengthUnit::ALL, "in"), None);
+
+		assert_eq!(format_value(120.00001), "120.0");
+		assert_eq!(format_value(0.12), "0.1200");
+	}
+}
@@ -0,0 +1,427 @@
+use iced::{
+	pure::{
+		column, horizontal_rule, horizontal_space, row, text,
+		widget::{Column, Row, Text},
+	},
+	Alignment, Length,
+};
+use native_dialog::FileDialog;
+use std::{
+	error::Error,
+	path::{Path, PathBuf},
+};
+
+use super::{prediction_ui::Device, SceneMessage};
+use crate::{
+	experiments::{ExperimentLog, Run},
+	imaging::{analyse, load, Polarity},
+	styling::{btn, drop_down, tinput, Theme, BOLD},
+};
+
+// -------------------------------------------------- EXPERIMENT LOG UI
+pub struct ExperimentsUI {
+	log: ExperimentLog,
+	device: Option<Device>,
+	inputs: ExperimentInputs,
+	// images the dimensions of the next run were measured on
+	images: Vec<PathBuf>,
+	status: Option<String>,
+	error: Result<(), Box<dyn Error>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum ExperimentInput {
+	Session(String),
+	Flow1(String),
+	Flow2(String),
+	DimA(String),
+	DimB(String),
+	Freq(String),
+	Calibration(String),
+}
+
+struct ExperimentInputs {
+	session: String,
+	flow1: String,
+	flow2: String,
+	dim_a: String,
+	dim_b: String,
+	freq: String,
+	calibration: String, // µm per pixel
+}
+
+impl Default for ExperimentInputs {
+	fn default() -> Self {
+		ExperimentInputs {
+			session: String::new(),
+			flow1: String::new(),
+			flow2: String::new(),
+			dim_a: String::new(),
+			dim_b: String::new(),
+			freq: String::new(),
+			calibration: String::from("1"),
+		}
+	}
+}
+
+impl Default for ExperimentsUI {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl ExperimentsUI {
+	pub fn new() -> Self {
+		let (log, error) = match ExperimentLog::load() {
+			Ok(log) => (log, Ok(())),
+			Err(e) => (ExperimentLog::default(), Err(e)),
+		};
+
+		ExperimentsUI {
+			log,
+			device: None,
+			inputs: ExperimentInputs::default(),
+			images: Vec::new(),
+			status: None,
+			error,
+		}
+	}
+
+	pub fn update(&mut self, msg: SceneMessage) {
+		self.status = None;
+
+		match msg {
+			SceneMessage::DeviceSelected(device) => self.device = Some(device),
+			SceneMessage::ExperimentInputChanged(input) => {
+				let ExperimentInputs {
+					session,
+					flow1,
+					flow2,
+					dim_a,
+					dim_b,
+					freq,
+					calibration,
+				} = &mut self.inputs;
+
+				let (field, value) = match input {
+					ExperimentInput::Session(value) => {
+						*session = value;
+						return;
+					}
+					ExperimentInput::Flow1(value) => (flow1, value),
+					ExperimentInput::Flow2(value) => (flow2, value),
+					ExperimentInput::DimA(value) => (dim_a, value),
+					ExperimentInput::DimB(value) => (dim_b, value),
+					ExperimentInput::Freq(value) => (freq, value),
+					ExperimentInput::Calibration(value) => (calibration, value),
+				};
+
+				if value.parse::<f32>().is_ok() || value.is_empty() {
+					*field = value;
+				}
+			}
+			SceneMessage::SelectRunImages => self.error = self.measure_images(),
+			SceneMessage::AddRun => self.error = self.add_run(),
+			SceneMessage::RemoveRun(index) => {
+				self.log.remove(index);
+				self.error = self.log.save();
+			}
+			SceneMessage::ImportSession => self.error = self.import_session(),
+			SceneMessage::UseTrainingData(path) => self.error = self.import_file(&path),
+			SceneMessage::ExportDataset => self.error = self.export(),
+			_ => {}
+		}
+	}
+
+	fn device(&self) -> Result<String, Box<dyn Error>> {
+		Ok(self.device.ok_or("Select a device first")?.to_string())
+	}
+
+	fn session(&self) -> Result<String, Box<dyn Error>> {
+		match self.inputs.session.trim() {
+			"" => Err("Name the session first".into()),
+			session => Ok(session.to_owned()),
+		}
+	}
+
+	// Mean length and height of the droplets on a set of images
+	fn measure_images(&mut self) -> Result<(), Box<dyn Error>> {
+		let images = FileDialog::new()
+			.add_filter("Image", &["png", "tif", "tiff", "jpg", "jpeg"])
+			.show_open_multiple_file()?;
+
+		if images.is_empty() {
+			return Ok(());
+		}
+
+		let um_per_px = self
+			.inputs
+			.calibration
+			.trim()
+			.parse::<f32>()
+			.map_err(|_| "Calibration is not a number")?;
+
+		let mut droplets = Vec::new();
+		for path in &images {
+			droplets.extend(analyse(&load(path)?, um_per_px, Polarity::Auto, 20)?.droplets);
+		}
+
+		if droplets.is_empty() {
+			return Err("No droplets found on these images".into());
+		}
+
+		let n = droplets.len() as f32;
+		let dim_a = droplets.iter().map(|d| d.dim_a).sum::<f32>() / n;
+		let dim_b = droplets.iter().map(|d| d.dim_b).sum::<f32>() / n;
+
+		self.inputs.dim_a = format!("{dim_a:.1}");
+		self.inputs.dim_b = format!("{dim_b:.1}");
+		self.status = Some(format!(
+			"Measured {} droplets on {} images",
+			droplets.len(),
+			images.len()
+		));
+		self.images = images;
+
+		Ok(())
+	}
+
+	fn add_run(&mut self) -> Result<(), Box<dyn Error>> {
+		let parse = |label: &str, value: &str| {
+			value
+				.trim()
+				.parse::<f32>()
+				.map_err(|_| format!("{label} is not a number"))
+		};
+
+		let inputs = &self.inputs;
+		let run = Run {
+			session: self.session()?,
+			device: self.device()?,
+			flow1: parse("Flow 1", &inputs.flow1)?,
+			flow2: parse("Flow 2", &inputs.flow2)?,
+			dim_a: parse("Droplet length", &inputs.dim_a)?,
+			dim_b: parse("Droplet height", &inputs.dim_b)?,
+			freq: parse("Frequency", &inputs.freq)?,
+			sources: std::mem::take(&mut self.images),
+		};
+
+		if !self.log.add(run) {
+			return Err("This run is already logged in the session".into());
+		}
+
+		self.log.save()
+	}
+
+	fn import_session(&mut self) -> Result<(), Box<dyn Error>> {
+		// before the dialog, so it is not shown for nothing
+		self.device()?;
+		self.session()?;
+
+		let path = match FileDialog::new()
+			.add_filter("CSV", &["csv"])
+			.show_open_single_file()?
+		{
+			Some(path) => path,
+			None => return Ok(()),
+		};
+
+		self.import_file(&path)
+	}
+
+	fn import_file(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
+		let (device, session) = (self.device()?, self.session()?);
+
+		let added = self.log.import_csv(path, &device, &session)?;
+		self.status = Some(format!("Imported {added} runs"));
+
+		self.log.save()
+	}
+
+	fn export(&mut self) -> Result<(), Box<dyn Error>> {
+		let device = self.device()?;
+
+		let path = match FileDialog::new()
+			.add_filter("CSV", &["csv"])
+			.show_save_single_file()?
+		{
+			Some(path) => path.with_extension("csv"),
+			None => return Ok(()),
+		};
+
+		let runs = self
+			.log
+			.runs
+			.iter()
+			.filter(|run| run.device == device)
+			.count();
+		let rows = self.log.export(&device, &path)?;
+
+		self.status = Some(format!(
+			"Wrote {rows} rows from {runs} runs to {}",
+			path.display()
+		));
+
+		Ok(())
+	}
+
+	pub fn view(&self, theme: Theme) -> Column<'_, SceneMessage> {
+		let field = |label: &str, value: &str, input: fn(String) -> ExperimentInput| {
+			Self::input_row(theme, label, value, move |s| {
+				SceneMessage::ExperimentInputChanged(input(s))
+			})
+		};
+
+		let inputs = &self.inputs;
+
+		let form = column()
+			.spacing(10)
+			.push(
+				row()
+					.push(text("Device"))
+					.push(horizontal_space(Length::Fill))
+					.push(
+						drop_down(
+							theme,
+							&Device::ALL[..],
+							self.device,
+							SceneMessage::DeviceSelected,
+						)
+						.placeholder("Choose a device...")
+						.width(Length::Units(200)),
+					)
+					.align_items(Alignment::Center),
+			)
+			.push(field("Session", &inputs.session, ExperimentInput::Session))
+			.push(field(
+				"Flow 1 (µL/min)",
+				&inputs.flow1,
+				ExperimentInput::Flow1,
+			))
+			.push(field(
+				"Flow 2 (µL/min)",
+				&inputs.flow2,
+				ExperimentInput::Flow2,
+			))
+			.push(field(
+				"Calibration (µm per pixel)",
+				&inputs.calibration,
+				ExperimentInput::Calibration,
+			))
+			.push(
+				row()
+					.push(btn(theme, "Measure images", SceneMessage::SelectRunImages))
+					.push(horizontal_space(Length::Fill))
+					.push(text(format!("{} images", self.images.len())).font(BOLD))
+					.align_items(Alignment::Center),
+			)
+			.push(field(
+				"Droplet length (µm)",
+				&inputs.dim_a,
+				ExperimentInput::DimA,
+			))
+			.push(field(
+				"Droplet height (µm)",
+				&inputs.dim_b,
+				ExperimentInput::DimB,
+			))
+			.push(field("Frequency (Hz)", &inputs.freq, ExperimentInput::Freq))
+			.push(
+				row()
+					.spacing(10)
+					.push(btn(theme, "Add run", SceneMessage::AddRun))
+					.push(btn(theme, "Import CSV", SceneMessage::ImportSession)),
+			);
+
+		let mut view = column()
+			.spacing(20)
+			.push(Text::new(
+				"Log each run with its pump flows, and either measure the droplets on images or type in the dimensions and frequency. Runs of all sessions of a device are merged into one training file.",
+			))
+			.push(form);
+
+		if let Err(e) = &self.error {
+			view = view.push(Text::new(e.to_string()));
+		} else if let Some(status) = &self.status {
+			view = view.push(text(status).size(16));
+		}
+
+		let device = match self.device {
+			Some(device) => device.to_string(),
+			None => return view,
+		};
+
+		// -------------------- RUNS
+		let mut table = column().spacing(5).push(
+			row()
+				.spacing(10)
+				.push(text("Session").font(BOLD).width(Length::Fill))
+				.push(text("Flows").font(BOLD).width(Length::Fill))
+				.push(text("Droplet").font(BOLD).width(Length::Fill))
+				.push(text("Hz").font(BOLD).width(Length::Units(60)))
+				.push(text("").width(Length::Units(100))),
+		);
+
+		for (i, run) in self.log.runs.iter().enumerate() {
+			if run.device != device {
+				continue;
+			}
+
+			table = table.push(
+				row()
+					.spacing(10)
+					.align_items(Alignment::Center)
+					.push(text(&run.session).size(16).width(Length::Fill))
+					.push(
+						text(format!("{} / {}", run.flow1, run.flow2))
+							.size(16)
+							.width(Length::Fill),
+					)
+					.push(
+						text(format!("{:.0} × {:.0}", run.dim_a, run.dim_b))
+							.size(16)
+							.width(Length::Fill),
+					)
+					.push(
+						text(format!("{:.0}", run.freq))
+							.size(16)
+							.width(Length::Units(60)),
+					)
+					.push(
+						btn(theme, "Remove", SceneMessage::RemoveRun(i)).width(Length::Units(100)),
+					),
+			);
+		}
+
+		let entries = self.log.aggregate(&device).len();
+
+		view.push(horizontal_rule(1))
+			.push(text(format!(
+				"{} sessions, {entries} distinct samples",
+				self.log.sessions(&device).len()
+			)))
+			.push(table)
+			.push(btn(
+				theme,
+				"Export training CSV",
+				SceneMessage::ExportDataset,
+			))
+	}
+
+	fn input_row<'a>(
+		theme: Theme,
+		label: &str,
+		value: &str,
+		update: impl Fn(String) -> SceneMessage + 'a,
+	) -> Row<'a, SceneMessage> {
+		row()
+			.push(text(label))
+			.push(horizontal_space(Length::Fill))
+			.push(
+				tinput(theme, "", value, update)
+					.width(Length::Units(200))
+					.padding(10),
+			)
+			.align_items(Alignment::Center)
+	}
+}
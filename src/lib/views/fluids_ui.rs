@@ -0,0 +1,373 @@
+use iced::{
+	pure::{
+		column, horizontal_rule, horizontal_space, row, text,
+		widget::{Column, Row, Text},
+	},
+	Alignment, Length,
+};
+use std::error::Error;
+
+use super::{prediction_ui::Device, SceneMessage};
+use crate::{
+	fluids::{Fluid, FluidLibrary, FluidPair},
+	styling::{btn, drop_down, tinput, Theme, BOLD},
+};
+
+// -------------------------------------------------- FLUID LIBRARY UI
+pub struct FluidsUI {
+	library: FluidLibrary,
+	form: FluidForm,
+	continuous: Option<String>,
+	dispersed: Option<String>,
+	tension: String,
+	device: Option<Device>,
+	error: Result<(), Box<dyn Error>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum FluidInput {
+	Name(String),
+	Viscosity(String),
+	Density(String),
+	Temperature(String),
+	Activation(String),
+	Expansion(String),
+}
+
+// Text fields of the add/edit fluid form
+#[derive(Default)]
+struct FluidForm {
+	name: String,
+	viscosity: String,
+	density: String,
+	temperature: String,
+	activation: String,
+	expansion: String,
+}
+
+impl FluidForm {
+	fn from_fluid(fluid: &Fluid) -> Self {
+		FluidForm {
+			name: fluid.name.clone(),
+			viscosity: fluid.viscosity.to_string(),
+			density: fluid.density.to_string(),
+			temperature: fluid.reference_temperature.to_string(),
+			activation: fluid.viscosity_activation.to_string(),
+			expansion: fluid.thermal_expansion.to_string(),
+		}
+	}
+
+	fn to_fluid(&self) -> Result<Fluid, Box<dyn Error>> {
+		if self.name.trim().is_empty() {
+			return Err("Fluid name is required".into());
+		}
+
+		let parse = |label: &str, value: &str| {
+			value
+				.trim()
+				.parse::<f32>()
+				.map_err(|_| format!("{label} is not a number"))
+		};
+
+		Ok(Fluid {
+			name: self.name.trim().to_string(),
+			viscosity: parse("Viscosity", &self.viscosity)?,
+			density: parse("Density", &self.density)?,
+			reference_temperature: parse("Reference temperature", &self.temperature)?,
+			viscosity_activation: parse("Viscosity activation", &self.activation)?,
+			thermal_expansion: parse("Thermal expansion", &self.expansion)?,
+		})
+	}
+}
+
+impl Default for FluidsUI {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl FluidsUI {
+	pub fn new() -> Self {
+		let (library, error) = match FluidLibrary::load() {
+			Ok(library) => (library, Ok(())),
+			Err(e) => (FluidLibrary::default(), Err(e)),
+		};
+
+		FluidsUI {
+			library,
+			form: FluidForm::default(),
+			continuous: None,
+			dispersed: None,
+			tension: String::new(),
+			device: None,
+			error,
+		}
+	}
+
+	pub fn update(&mut self, msg: SceneMessage) {
+		match msg {
+			SceneMessage::FluidInputChanged(input) => {
+				let FluidForm {
+					name,
+					viscosity,
+					density,
+					temperature,
+					activation,
+					expansion,
+				} = &mut self.form;
+
+				let (field, value) = match input {
+					FluidInput::Name(value) => {
+						*name = value;
+						return;
+					}
+					FluidInput::Viscosity(value) => (viscosity, value),
+					FluidInput::Density(value) => (density, value),
+					FluidInput::Temperature(value) => (temperature, value),
+					FluidInput::Activation(value) => (activation, value),
+					FluidInput::Expansion(value) => (expansion, value),
+				};
+
+				if value.parse::<f32>().is_ok() || value.is_empty() || value == "-" {
+					*field = value;
+				};
+			}
+			SceneMessage::SaveFluid => {
+				self.error = self.form.to_fluid().and_then(|fluid| {
+					self.library.upsert_fluid(fluid);
+					self.library.save()
+				});
+
+				if self.error.is_ok() {
+					self.form = FluidForm::default();
+				}
+			}
+			SceneMessage::EditFluid(name) => {
+				if let Some(fluid) = self.library.fluid(&name) {
+					self.form = FluidForm::from_fluid(fluid);
+				}
+			}
+			SceneMessage::RemoveFluid(name) => {
+				self.library.remove_fluid(&name);
+				self.error = self.library.save();
+			}
+			SceneMessage::ContinuousSelected(name) => self.continuous = Some(name),
+			SceneMessage::DispersedSelected(name) => self.dispersed = Some(name),
+			SceneMessage::TensionChanged(value)
+				if value.parse::<f32>().is_ok() || value.is_empty() =>
+			{
+				self.tension = value;
+			}
+			SceneMessage::SaveTension => {
+				self.error = match (self.pair(), self.tension.parse::<f32>()) {
+					(Some(pair), Ok(tension)) => {
+						self.library.set_tension(pair, tension);
+						self.library.save()
+					}
+					_ => Err("Select both fluids and enter the interfacial tension".into()),
+				};
+			}
+			SceneMessage::DeviceSelected(device) => self.device = Some(device),
+			SceneMessage::AssignFluidPair => {
+				self.error = match (self.device, self.pair()) {
+					(Some(device), Some(pair)) => {
+						self.library.devices.insert(device.to_string(), pair);
+						self.library.save()
+					}
+					_ => Err("Select a device and both fluids".into()),
+				};
+			}
+			_ => {}
+		}
+	}
+
+	pub fn view(&self, theme: Theme) -> Column<'_, SceneMessage> {
+		// -------------------- FLUIDS
+		let mut fluids = column().spacing(5).push(text("Fluids").font(BOLD));
+
+		for fluid in &self.library.fluids {
+			fluids = fluids.push(
+				row()
+					.spacing(10)
+					.align_items(Alignment::Center)
+					.push(text(&fluid.name))
+					.push(horizontal_space(Length::Fill))
+					.push(
+						text(format!(
+							"{} mPa·s, {} kg/m³ at {} °C",
+							fluid.viscosity, fluid.density, fluid.reference_temperature
+						))
+						.size(16),
+					)
+					.push(btn(
+						theme,
+						"Edit",
+						SceneMessage::EditFluid(fluid.name.clone()),
+					))
+					.push(btn(
+						theme,
+						"Remove",
+						SceneMessage::RemoveFluid(fluid.name.clone()),
+					)),
+			);
+		}
+
+		let form = column()
+			.spacing(10)
+			.push(Self::input_row(theme, "Name", &self.form.name, |s| {
+				SceneMessage::FluidInputChanged(FluidInput::Name(s))
+			}))
+			.push(Self::input_row(
+				theme,
+				"Viscosity (mPa·s)",
+				&self.form.viscosity,
+				|s| SceneMessage::FluidInputChanged(FluidInput::Viscosity(s)),
+			))
+			.push(Self::input_row(
+				theme,
+				"Density (kg/m³)",
+				&self.form.density,
+				|s| SceneMessage::FluidInputChanged(FluidInput::Density(s)),
+			))
+			.push(Self::input_row(
+				theme,
+				"At temperature (°C)",
+				&self.form.temperature,
+				|s| SceneMessage::FluidInputChanged(FluidInput::Temperature(s)),
+			))
+			.push(Self::input_row(
+				theme,
+				"Viscosity activation B (K)",
+				&self.form.activation,
+				|s| SceneMessage::FluidInputChanged(FluidInput::Activation(s)),
+			))
+			.push(Self::input_row(
+				theme,
+				"Thermal expansion β (1/K)",
+				&self.form.expansion,
+				|s| SceneMessage::FluidInputChanged(FluidInput::Expansion(s)),
+			))
+			.push(btn(theme, "Save fluid", SceneMessage::SaveFluid));
+
+		// -------------------- PAIRS
+		let names = self.library.names();
+
+		let pair_selection = row()
+			.spacing(10)
+			.push(
+				drop_down(
+					theme,
+					names.clone(),
+					self.continuous.clone(),
+					SceneMessage::ContinuousSelected,
+				)
+				.placeholder("Continuous")
+				.width(Length::Fill),
+			)
+			.push(
+				drop_down(
+					theme,
+					names,
+					self.dispersed.clone(),
+					SceneMessage::DispersedSelected,
+				)
+				.placeholder("Dispersed")
+				.width(Length::Fill),
+			);
+
+		let mut tensions = column()
+			.spacing(5)
+			.push(text("Interfacial tension").font(BOLD));
+
+		for entry in &self.library.tensions {
+			tensions = tensions.push(
+				row()
+					.push(text(entry.pair.to_string()))
+					.push(horizontal_space(Length::Fill))
+					.push(text(format!("{} mN/m", entry.tension))),
+			);
+		}
+
+		tensions = tensions
+			.push(Self::input_row(
+				theme,
+				"Tension (mN/m)",
+				&self.tension,
+				SceneMessage::TensionChanged,
+			))
+			.push(btn(theme, "Save tension", SceneMessage::SaveTension));
+
+		let mut devices = column()
+			.spacing(5)
+			.push(text("Device fluid pairs").font(BOLD));
+
+		for (device, pair) in &self.library.devices {
+			devices = devices.push(
+				row()
+					.push(text(device))
+					.push(horizontal_space(Length::Fill))
+					.push(text(pair.to_string())),
+			);
+		}
+
+		devices = devices.push(
+			row()
+				.spacing(10)
+				.align_items(Alignment::Center)
+				.push(
+					drop_down(
+						theme,
+						&Device::ALL[..],
+						self.device,
+						SceneMessage::DeviceSelected,
+					)
+					.placeholder("Device"),
+				)
+				.push(horizontal_space(Length::Fill))
+				.push(btn(
+					theme,
+					"Assign selected pair",
+					SceneMessage::AssignFluidPair,
+				)),
+		);
+
+		let mut view = column()
+			.spacing(20)
+			.push(fluids)
+			.push(form)
+			.push(horizontal_rule(1))
+			.push(pair_selection)
+			.push(tensions)
+			.push(devices);
+
+		if let Err(e) = &self.error {
+			view = view.push(Text::new(e.to_string()).color(theme.palette().danger));
+		}
+
+		view
+	}
+
+	fn pair(&self) -> Option<FluidPair> {
+		Some(FluidPair {
+			continuous: self.continuous.clone()?,
+			dispersed: self.dispersed.clone()?,
+		})
+	}
+
+	fn input_row<'a>(
+		theme: Theme,
+		label: &str,
+		value: &str,
+		update: impl Fn(String) -> SceneMessage + 'a,
+	) -> Row<'a, SceneMessage> {
+		row()
+			.push(text(label))
+			.push(horizontal_space(Length::Fill))
+			.push(
+				tinput(theme, "", value, update)
+					.width(Length::Units(200))
+					.padding(10),
+			)
+			.align_items(Alignment::Center)
+	}
+}
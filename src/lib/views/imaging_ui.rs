@@ -0,0 +1,419 @@
+use iced::{
+	image::Handle,
+	pure::{
+		column, horizontal_rule, horizontal_space, row, text,
+		widget::{Canvas, Column, Image, Row, Text},
+	},
+	Alignment, Length,
+};
+use image::DynamicImage;
+use native_dialog::FileDialog;
+use std::{error::Error, path::PathBuf};
+
+use super::SceneMessage;
+use crate::{
+	frequency::{
+		estimate_folder, frame_paths, CrossingLine, FrequencyEstimate, LinePicker, Thumbnail,
+		Timing,
+	},
+	imaging::{analyse, load, Analysis, Polarity},
+	styling::{btn, drop_down, tinput, Theme, BOLD},
+};
+
+// -------------------------------------------------- IMAGE MEASUREMENT UI
+pub struct ImagingUI {
+	path: Option<PathBuf>,
+	image: Option<DynamicImage>,
+	inputs: ImagingInputs,
+	polarity: Polarity,
+	analysis: Option<Analysis>,
+	overlay: Option<Handle>,
+	error: Result<(), Box<dyn Error>>,
+	// -------------------- FREQUENCY
+	frames: Vec<PathBuf>,
+	thumbnail: Option<Thumbnail>,
+	line: Option<CrossingLine>,
+	estimate: Option<FrequencyEstimate>,
+	frequency_error: Result<(), Box<dyn Error>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum ImagingInput {
+	Calibration(String),
+	MinArea(String),
+	FrameRate(String),
+}
+
+struct ImagingInputs {
+	calibration: String, // µm per pixel
+	min_area: String,    // px
+	// frames per second, empty to read timestamps from the file names
+	frame_rate: String,
+}
+
+impl Default for ImagingInputs {
+	fn default() -> Self {
+		ImagingInputs {
+			calibration: String::from("1"),
+			min_area: String::from("20"),
+			frame_rate: String::new(),
+		}
+	}
+}
+
+impl Default for ImagingUI {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl ImagingUI {
+	pub fn new() -> Self {
+		ImagingUI {
+			path: None,
+			image: None,
+			inputs: ImagingInputs::default(),
+			polarity: Polarity::default(),
+			analysis: None,
+			overlay: None,
+			error: Ok(()),
+			frames: Vec::new(),
+			thumbnail: None,
+			line: None,
+			estimate: None,
+			frequency_error: Ok(()),
+		}
+	}
+
+	pub fn update(&mut self, msg: SceneMessage) {
+		match msg {
+			SceneMessage::SelectImage => {
+				self.path = FileDialog::new()
+					.add_filter("Image", &["png", "tif", "tiff", "jpg", "jpeg"])
+					.show_open_single_file()
+					.unwrap();
+
+				self.analysis = None;
+				self.overlay = None;
+				self.image = None;
+
+				if let Some(path) = &self.path {
+					match load(path) {
+						Ok(image) => {
+							self.overlay = Some(Self::handle(&image.to_rgba8()));
+							self.image = Some(image);
+							self.error = Ok(());
+						}
+						Err(e) => self.error = Err(e),
+					}
+				}
+			}
+			SceneMessage::ImagingInputChanged(input) => {
+				let (field, value) = match input {
+					ImagingInput::Calibration(value) => (&mut self.inputs.calibration, value),
+					ImagingInput::MinArea(value) => (&mut self.inputs.min_area, value),
+					ImagingInput::FrameRate(value) => (&mut self.inputs.frame_rate, value),
+				};
+
+				if value.parse::<f32>().is_ok() || value.is_empty() {
+					*field = value;
+				}
+			}
+			SceneMessage::PolaritySelected(polarity) => self.polarity = polarity,
+			SceneMessage::GoPressed => self.error = self.measure(),
+			SceneMessage::SelectFrames => {
+				self.estimate = None;
+				self.frequency_error = self.load_frames();
+			}
+			SceneMessage::CrossingLineDrawn(line) => self.line = Some(line),
+			SceneMessage::EstimateFrequency => {
+				self.frequency_error = self.estimate_frequency();
+			}
+			_ => {}
+		}
+	}
+
+	fn measure(&mut self) -> Result<(), Box<dyn Error>> {
+		let image = self.image.as_ref().ok_or("Load an image first")?;
+
+		let um_per_px = self
+			.inputs
+			.calibration
+			.trim()
+			.parse::<f32>()
+			.map_err(|_| "Calibration is not a number")?;
+		let min_area = self.inputs.min_area.trim().parse::<f32>().unwrap_or(0.0) as usize;
+
+		let analysis = analyse(image, um_per_px, self.polarity, min_area)?;
+
+		self.overlay = Some(Self::handle(&analysis.overlay(image)));
+		self.analysis = Some(analysis);
+
+		Ok(())
+	}
+
+	fn load_frames(&mut self) -> Result<(), Box<dyn Error>> {
+		self.frames.clear();
+		self.thumbnail = None;
+		self.line = None;
+
+		let dir = match FileDialog::new().show_open_single_dir()? {
+			Some(dir) => dir,
+			None => return Ok(()),
+		};
+
+		self.frames = frame_paths(&dir)?;
+		let first = self.frames.first().ok_or("No images in this folder")?;
+		self.thumbnail = Some(Thumbnail::new(&load(first)?));
+
+		Ok(())
+	}
+
+	fn estimate_frequency(&mut self) -> Result<(), Box<dyn Error>> {
+		let line = self.line.ok_or("Draw a line across the channel first")?;
+
+		let timing = match self.inputs.frame_rate.trim() {
+			"" => Timing::FileNames,
+			fps => Timing::FrameRate(fps.parse::<f32>()?),
+		};
+
+		self.estimate = Some(estimate_folder(&self.frames, timing, line, self.polarity)?);
+
+		Ok(())
+	}
+
+	// iced takes raw pixels as BGRA
+	fn handle(rgba: &image::RgbaImage) -> Handle {
+		let pixels = rgba
+			.pixels()
+			.flat_map(|p| {
+				let [r, g, b, a] = p.0;
+				[b, g, r, a]
+			})
+			.collect();
+
+		Handle::from_pixels(rgba.width(), rgba.height(), pixels)
+	}
+
+	pub fn view(&self, theme: Theme) -> Column<'_, SceneMessage> {
+		column()
+			.spacing(20)
+			.push(self.measurement_view(theme))
+			.push(horizontal_rule(1))
+			.push(self.frequency_view(theme))
+	}
+
+	// -------------------- FREQUENCY
+	fn frequency_view(&self, theme: Theme) -> Column<'_, SceneMessage> {
+		let mut view = column()
+			.spacing(10)
+			.push(text("Frequency").font(BOLD))
+			.push(Text::new(
+				"Load a folder of frames and draw a line across the channel. Droplets crossing it are counted.",
+			))
+			.push(
+				row()
+					.push(btn(theme, "Load frames", SceneMessage::SelectFrames))
+					.push(horizontal_space(Length::Fill))
+					.push(text(format!("{} frames", self.frames.len())).font(BOLD))
+					.align_items(Alignment::Center),
+			)
+			.push(Self::input_row(theme, "Frame rate (fps)",
+				&self.inputs.frame_rate,
+				|s| SceneMessage::ImagingInputChanged(ImagingInput::FrameRate(s)),
+			));
+
+		if let Some(thumbnail) = &self.thumbnail {
+			let (width, height) = thumbnail.image.dimensions();
+			let canvas: Canvas<SceneMessage, LinePicker> = Canvas::new(LinePicker {
+				thumbnail,
+				line: self.line,
+			});
+
+			// 500 px wide with the frame's aspect ratio
+			view = view.push(
+				canvas
+					.width(Length::Units(500))
+					.height(Length::Units((500 * height / width.max(1)) as u16)),
+			);
+		}
+
+		if self.line.is_some() && !self.frames.is_empty() {
+			view = view.push(btn(
+				theme,
+				"Estimate frequency",
+				SceneMessage::EstimateFrequency,
+			));
+		}
+
+		if let Err(e) = &self.frequency_error {
+			return view.push(Text::new(e.to_string()));
+		}
+
+		if let Some(estimate) = &self.estimate {
+			view = view
+				.push(text(format!(
+					"{:.1} Hz (95% CI {:.1} – {:.1} Hz) from {} crossings in {} frames",
+					estimate.frequency,
+					estimate.confidence.0,
+					estimate.confidence.1,
+					estimate.crossings.len(),
+					estimate.frames,
+				)))
+				.push(btn(
+					theme,
+					"Use in prediction",
+					SceneMessage::UseFrequency(estimate.frequency),
+				));
+
+			if estimate.undersampled {
+				view = view.push(
+					text("Fewer than two frames per droplet, the frame rate may be too low.")
+						.size(16),
+				);
+			}
+		}
+
+		view
+	}
+
+	fn measurement_view(&self, theme: Theme) -> Column<'_, SceneMessage> {
+		let file_name = self
+			.path
+			.as_ref()
+			.and_then(|path| path.file_name())
+			.map(|name| name.to_string_lossy().into_owned())
+			.unwrap_or_default();
+
+		let settings = column()
+			.spacing(10)
+			.push(
+				row()
+					.push(btn(theme, "Load image", SceneMessage::SelectImage))
+					.push(horizontal_space(Length::Fill))
+					.push(text(file_name).font(BOLD))
+					.align_items(Alignment::Center),
+			)
+			.push(Self::input_row(
+				theme,
+				"Calibration (µm per pixel)",
+				&self.inputs.calibration,
+				|s| SceneMessage::ImagingInputChanged(ImagingInput::Calibration(s)),
+			))
+			.push(Self::input_row(
+				theme,
+				"Smallest droplet (pixels)",
+				&self.inputs.min_area,
+				|s| SceneMessage::ImagingInputChanged(ImagingInput::MinArea(s)),
+			))
+			.push(
+				row()
+					.push(text("Droplets are"))
+					.push(horizontal_space(Length::Fill))
+					.push(
+						drop_down(
+							theme,
+							&Polarity::ALL[..],
+							Some(self.polarity),
+							SceneMessage::PolaritySelected,
+						)
+						.width(Length::Units(200)),
+					)
+					.align_items(Alignment::Center),
+			);
+
+		let mut view = column()
+			.spacing(20)
+			.push(Text::new(
+				"Droplets touching the edge of the frame are ignored. The channel is assumed to run horizontally.",
+			))
+			.push(settings);
+
+		if self.image.is_some() {
+			view = view.push(btn(theme, "Measure", SceneMessage::GoPressed));
+		}
+
+		if let Some(handle) = &self.overlay {
+			view = view.push(Image::new(handle.clone()).width(Length::Fill));
+		}
+
+		if let Err(e) = &self.error {
+			return view.push(Text::new(e.to_string()));
+		}
+
+		let analysis = match &self.analysis {
+			Some(analysis) => analysis,
+			None => return view,
+		};
+
+		// -------------------- RESULTS
+		let (mean, cv) = match (analysis.mean(), analysis.cv()) {
+			(Some(mean), Some(cv)) => (mean, cv),
+			_ => {
+				return view.push(text(format!(
+					"No droplets found (threshold {})",
+					analysis.threshold
+				)))
+			}
+		};
+
+		let mut table = column().spacing(5).push(
+			row()
+				.spacing(10)
+				.push(text("#").font(BOLD).width(Length::Units(60)))
+				.push(text("Length (µm)").font(BOLD).width(Length::Fill))
+				.push(text("Height (µm)").font(BOLD).width(Length::Fill)),
+		);
+
+		for (i, droplet) in analysis.droplets.iter().enumerate() {
+			table = table.push(
+				row()
+					.spacing(10)
+					.push(text(format!("{}", i + 1)).size(16).width(Length::Units(60)))
+					.push(
+						text(format!("{:.1}", droplet.dim_a))
+							.size(16)
+							.width(Length::Fill),
+					)
+					.push(
+						text(format!("{:.1}", droplet.dim_b))
+							.size(16)
+							.width(Length::Fill),
+					),
+			);
+		}
+
+		view.push(horizontal_rule(1))
+			.push(text(format!(
+				"{} droplets, length {:.1} µm (CV {:.1}%), height {:.1} µm (CV {:.1}%)",
+				analysis.droplets.len(),
+				mean.0,
+				cv.0 * 100.0,
+				mean.1,
+				cv.1 * 100.0,
+			)))
+			.push(btn(
+				theme,
+				"Use in prediction",
+				SceneMessage::UseDimensions(mean.0, mean.1),
+			))
+			.push(table)
+	}
+
+	fn input_row<'a>(
+		theme: Theme,
+		label: &str,
+		value: &str,
+		update: impl Fn(String) -> SceneMessage + 'a,
+	) -> Row<'a, SceneMessage> {
+		row()
+			.push(text(label))
+			.push(horizontal_space(Length::Fill))
+			.push(
+				tinput(theme, "", value, update)
+					.width(Length::Units(200))
+					.padding(10),
+			)
+			.align_items(Alignment::Center)
+	}
+}
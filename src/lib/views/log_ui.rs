@@ -0,0 +1,108 @@
+use iced::{
+	pure::{
+		column, horizontal_rule, horizontal_space, row, text,
+		widget::{Column, Row},
+	},
+	time::Duration,
+	Alignment, Length, Subscription,
+};
+use log::Level;
+
+use super::SceneMessage;
+use crate::{
+	logging::{log_path, recent, Entry},
+	styling::{drop_down, Theme, BOLD},
+};
+
+// Newest entries shown, older ones are in the log file
+const SHOWN: usize = 200;
+
+// -------------------------------------------------- LOG UI
+pub struct LogUI {
+	// least severe level shown
+	level: Level,
+}
+
+impl Default for LogUI {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl LogUI {
+	pub const LEVELS: [Level; 5] = [
+		Level::Error,
+		Level::Warn,
+		Level::Info,
+		Level::Debug,
+		Level::Trace,
+	];
+
+	pub fn new() -> Self {
+		LogUI { level: Level::Info }
+	}
+
+	pub fn update(&mut self, msg: SceneMessage) {
+		if let SceneMessage::LogLevelSelected(level) = msg {
+			self.level = level;
+		}
+	}
+
+	// Training logs from another thread, so the view is redrawn to show new entries
+	pub fn subscription(&self) -> Subscription<SceneMessage> {
+		iced::time::every(Duration::from_secs(1)).map(SceneMessage::Tick)
+	}
+
+	pub fn view(&self, theme: Theme) -> Column<'_, SceneMessage> {
+		let entries: Vec<Entry> = recent()
+			.into_iter()
+			.rev()
+			.filter(|entry| entry.level <= self.level)
+			.take(SHOWN)
+			.collect();
+
+		let mut view = column()
+			.spacing(10)
+			.push(
+				row()
+					.spacing(10)
+					.align_items(Alignment::Center)
+					.push(text("Level"))
+					.push(horizontal_space(Length::Fill))
+					.push(
+						drop_down(
+							theme,
+							&Self::LEVELS[..],
+							Some(self.level),
+							SceneMessage::LogLevelSelected,
+						)
+						.width(Length::Units(200)),
+					),
+			)
+			.push(text(format!("Written to {}", log_path().display())).size(16))
+			.push(horizontal_rule(10));
+
+		if entries.is_empty() {
+			view = view.push(text("Nothing logged at this level yet").size(16));
+		}
+
+		for entry in entries {
+			view = view.push(Self::entry_row(theme, entry));
+		}
+
+		view
+	}
+
+	fn entry_row<'a>(theme: Theme, entry: Entry) -> Row<'a, SceneMessage> {
+		let mut level = text(entry.level.to_string()).size(14).font(BOLD);
+		if entry.level <= Level::Warn {
+			level = level.color(theme.palette().danger);
+		}
+
+		row()
+			.spacing(10)
+			.push(text(entry.time).size(14))
+			.push(level)
+			.push(text(format!("{}: {}", entry.target, entry.message)).size(14))
+	}
+}
@@ -1,5 +1,11 @@
 #![allow(unreachable_patterns)]
+pub mod experiments_ui;
+pub mod fluids_ui;
+pub mod imaging_ui;
+pub mod log_ui;
+pub mod optimizer_ui;
 pub mod prediction_ui;
+pub mod settings_ui;
 pub mod training_ui;
 
 // use crate::views::{
@@ -8,22 +14,66 @@ pub mod training_ui;
 // };
 use iced::{
 	pure::{column, text, widget::Column, Element},
-	Length,
+	time::Instant,
+	Length, Subscription,
 };
 
-use prediction_ui::{Device, PredictionInput, PredictionUI};
+use std::{error::Error, path::PathBuf};
+
+use experiments_ui::{ExperimentInput, ExperimentsUI};
+use fluids_ui::{FluidInput, FluidsUI};
+use imaging_ui::{ImagingInput, ImagingUI};
+use log_ui::LogUI;
+use optimizer_ui::{OptimizerInput, OptimizerUI};
+use prediction_ui::{Device, PredictionInput, PredictionUI, SweepInput};
+use settings_ui::SettingsUI;
 use training_ui::{TrainingUI, UserModel};
 
+use crate::{
+	animation::PlaybackSpeed,
+	config::Config,
+	dataset::has_flows,
+	export::ExportFormat,
+	focus::focus_scope,
+	frequency::CrossingLine,
+	imaging::Polarity,
+	optimize::Objective,
+	physics::VolumeModel,
+	project::RecentProject,
+	shortcuts::Shortcut,
+	styling::{TextSize, Theme, UiScale},
+	sweep::Variable,
+	units::{FlowUnit, LengthUnit, UnitChoice},
+};
+
 #[derive(Clone, Debug)]
 pub enum Message {
-	BackPressed,
-	NextPressed,
+	// index into the sidebar's scenes
+	SceneSelected(usize),
+	OpenProject,
+	RecentProjectSelected(RecentProject),
+	SaveProject,
+	SaveProjectAs,
+	CloseProject,
+	DarkThemeToggled(bool),
+	FollowSystemToggled(bool),
+	PaletteSelected(String),
+	// re-reads the desktop's dark mode while following it
+	SystemThemeChecked,
+	HighContrastToggled(bool),
+	UiScaleSelected(UiScale),
+	ShortcutsToggled(bool),
+	Shortcut(Shortcut),
+	// file or directory dropped on the window
+	FileDropped(PathBuf),
 	SceneMessage(SceneMessage),
 }
 
 #[derive(Clone, Debug)]
 pub enum SceneMessage {
 	GoPressed,
+	SeedChanged(String),
+	ReproduceModel,
 	CreateToggled(bool),
 	InputChanged(String),
 	SelectCSV,
@@ -33,21 +83,109 @@ pub enum SceneMessage {
 	PredictionInputChanged(PredictionInput),
 	SelectModel,
 	UserModelToggled(bool),
+	FluidInputChanged(FluidInput),
+	SaveFluid,
+	EditFluid(String),
+	RemoveFluid(String),
+	ContinuousSelected(String),
+	DispersedSelected(String),
+	TensionChanged(String),
+	SaveTension,
+	AssignFluidPair,
+	VolumeModelSelected(VolumeModel),
+	OptimizerInputChanged(OptimizerInput),
+	ObjectiveSelected(Objective),
+	CandidateSelected(usize),
+	SweepToggled(bool),
+	SweepTwoDToggled(bool),
+	SweepXSelected(Variable),
+	SweepYSelected(Variable),
+	SweepInputChanged(SweepInput),
+	SweepOutputSelected(String),
+	RunSweep,
+	ExportSweepCsv,
+	ExportSweepPng,
+	AnimateToggled(bool),
+	PlaybackSpeedSelected(PlaybackSpeed),
+	Tick(Instant),
+	SelectImage,
+	ImagingInputChanged(ImagingInput),
+	PolaritySelected(Polarity),
+	// measured length and height handed to the prediction inputs
+	UseDimensions(f32, f32),
+	SelectFrames,
+	CrossingLineDrawn(CrossingLine),
+	EstimateFrequency,
+	// estimated frequency handed to the prediction inputs
+	UseFrequency(f32),
+	// freshly trained or dropped model handed to the prediction scene
+	UseModel(PathBuf),
+	// device and model the prediction scene predicts with, handed to the optimizer
+	PredictionModel(Option<(Device, PathBuf)>),
+	// dropped CSV with flows, training data or runs for the experiment log
+	UseTrainingData(PathBuf),
+	// dropped CSV of inputs, predicted row by row
+	PredictBatch(PathBuf),
+	ExperimentInputChanged(ExperimentInput),
+	SelectRunImages,
+	AddRun,
+	RemoveRun(usize),
+	ImportSession,
+	ExportDataset,
+	HistoryToggled(bool),
+	// index into the prediction history
+	RestoreRecord(usize),
+	RerunRecord(usize),
+	ExportHistoryCsv,
+	ExportHistoryJson,
+	// sent to every scene when the app's config is replaced
+	ConfigChanged(Config),
+	// sent to every scene when the fluids scene saved the library
+	FluidsChanged,
+	SelectModelDir,
+	ClearModelDir,
+	SelectPython,
+	ClearPython,
+	FlowUnitSelected(FlowUnit),
+	LengthUnitSelected(LengthUnit),
+	TextSizeSelected(TextSize),
+	ResetSettings,
+	ImportSettings,
+	ExportSettings,
+	// edited config handed to the app, which applies and saves it
+	SaveSettings(Config),
+	UnitSelected(UnitChoice),
+	LogLevelSelected(log::Level),
+	ExportFormatSelected(ExportFormat),
+	ExportModel,
 }
 
 // To add a view, declare it here and define it in
 // a new file. Propogate all the messages through `update`
 // method.
+#[allow(clippy::large_enum_variant)]
 pub enum Scene {
 	Training(TrainingUI),
 	Prediction(PredictionUI),
+	Fluids(FluidsUI),
+	Optimizer(OptimizerUI),
+	Imaging(ImagingUI),
+	Experiments(ExperimentsUI),
+	Log(LogUI),
+	Settings(SettingsUI),
 }
 
 impl Scene {
 	pub fn all_scenes() -> Vec<Scene> {
 		vec![
 			Scene::Prediction(PredictionUI::new()),
+			Scene::Optimizer(OptimizerUI::new()),
+			Scene::Imaging(ImagingUI::new()),
+			Scene::Experiments(ExperimentsUI::new()),
 			Scene::Training(TrainingUI::new()),
+			Scene::Fluids(FluidsUI::new()),
+			Scene::Log(LogUI::new()),
+			Scene::Settings(SettingsUI::new()),
 		]
 	}
 
@@ -55,30 +193,67 @@ impl Scene {
 		match self {
 			Scene::Prediction(ui) => ui.update(msg),
 			Scene::Training(ui) => ui.update(msg),
+			Scene::Fluids(ui) => ui.update(msg),
+			Scene::Optimizer(ui) => ui.update(msg),
+			Scene::Imaging(ui) => ui.update(msg),
+			Scene::Experiments(ui) => ui.update(msg),
+			Scene::Log(ui) => ui.update(msg),
+			Scene::Settings(ui) => ui.update(msg),
 			_ => {}
 		}
 	}
 
+	// The prediction scene animates, the log scene follows new entries
+	pub fn subscription(&self) -> Subscription<SceneMessage> {
+		match self {
+			Scene::Prediction(ui) => ui.subscription(),
+			Scene::Log(ui) => ui.subscription(),
+			_ => Subscription::none(),
+		}
+	}
+
 	pub fn title(&self) -> &str {
 		match self {
 			Scene::Prediction { .. } => "Prediction",
 			Scene::Training(_) => "Model creation and Training",
+			Scene::Fluids(_) => "Fluid library",
+			Scene::Optimizer(_) => "Operating point",
+			Scene::Imaging(_) => "Image measurement",
+			Scene::Experiments(_) => "Experiment log",
+			Scene::Log(_) => "Log",
+			Scene::Settings(_) => "Settings",
 		}
 	}
 
-	fn _can_transition(&self) -> bool {
-		false
+	// Font Awesome glyph shown next to the title in the sidebar
+	pub fn icon(&self) -> char {
+		match self {
+			Scene::Prediction(_) => '\u{f201}',  // chart-line
+			Scene::Training(_) => '\u{f5dc}',    // brain
+			Scene::Fluids(_) => '\u{f043}',      // droplet
+			Scene::Optimizer(_) => '\u{f140}',   // bullseye
+			Scene::Imaging(_) => '\u{f610}',     // microscope
+			Scene::Experiments(_) => '\u{f0c3}', // flask
+			Scene::Log(_) => '\u{f15c}',         // file-lines
+			Scene::Settings(_) => '\u{f013}',    // gear
+		}
 	}
 
-	pub fn view(&self) -> Element<SceneMessage> {
+	pub fn view(&self, theme: Theme) -> Element<'_, SceneMessage> {
 		match self {
-			Scene::Prediction(ui) => Self::container(self.title()).push(ui.view()),
-			Scene::Training(ui) => Self::container(self.title()).push(ui.view()),
+			Scene::Prediction(ui) => Self::container(self.title()).push(ui.view(theme)),
+			Scene::Training(ui) => Self::container(self.title()).push(ui.view(theme)),
+			Scene::Fluids(ui) => Self::container(self.title()).push(ui.view(theme)),
+			Scene::Optimizer(ui) => Self::container(self.title()).push(ui.view(theme)),
+			Scene::Imaging(ui) => Self::container(self.title()).push(ui.view(theme)),
+			Scene::Experiments(ui) => Self::container(self.title()).push(ui.view(theme)),
+			Scene::Log(ui) => Self::container(self.title()).push(ui.view(theme)),
+			Scene::Settings(ui) => Self::container(self.title()).push(ui.view(theme)),
 		}
 		.into()
 	}
 
-	fn container(title: &str) -> Column<SceneMessage> {
+	fn container(title: &str) -> Column<'_, SceneMessage> {
 		column()
 			.push(text(title).size(50))
 			// .spacing(20)
@@ -104,18 +279,142 @@ impl Scenes {
 	}
 
 	pub fn update(&mut self, event: SceneMessage) {
-		self.list[self.current].update(event)
+		match event {
+			// goes to the prediction scene, which is then shown
+			SceneMessage::UseDimensions(..)
+			| SceneMessage::UseFrequency(_)
+			| SceneMessage::UseModel(_) => self.show(|scene| matches!(scene, Scene::Prediction(_)), event),
+			_ => {
+				if let Scene::Optimizer(_) = self.list[self.current] {
+					self.share_model();
+				}
+				let saves_fluids = matches!(
+					event,
+					SceneMessage::SaveFluid
+						| SceneMessage::RemoveFluid(_)
+						| SceneMessage::SaveTension
+						| SceneMessage::AssignFluidPair
+				);
+				self.list[self.current].update(event);
+
+				// other scenes list the fluids of the library they loaded
+				if saves_fluids {
+					self.broadcast(SceneMessage::FluidsChanged);
+				}
+			}
+		}
+	}
+
+	// The optimizer searches with the prediction scene's model when both use the same device
+	fn share_model(&mut self) {
+		let model = self.list.iter().find_map(|scene| match scene {
+			Scene::Prediction(ui) => Some(
+				ui.model()
+					.map(|(device, path)| (device, path.to_path_buf())),
+			),
+			_ => None,
+		});
+
+		if let Some(model) = model {
+			for scene in &mut self.list {
+				if let Scene::Optimizer(ui) = scene {
+					ui.update(SceneMessage::PredictionModel(model.clone()));
+				}
+			}
+		}
+	}
+
+	// Sends a message to the first scene matching, and shows that scene
+	pub fn show(&mut self, target: fn(&Scene) -> bool, event: SceneMessage) {
+		if let Some(index) = self.list.iter().position(target) {
+			self.list[index].update(event);
+			self.current = index;
+		}
 	}
 
-	pub fn has_previous(&self) -> bool {
-		self.current > 0
+	pub fn subscription(&self) -> Subscription<SceneMessage> {
+		self.list[self.current].subscription()
+	}
+
+	// Models trained or loaded in this session, without duplicates
+	pub fn models(&self) -> Vec<PathBuf> {
+		let mut models: Vec<PathBuf> = Vec::new();
+
+		for scene in &self.list {
+			let paths = match scene {
+				Scene::Prediction(ui) => ui.session_models(),
+				Scene::Training(ui) => ui.session_models(),
+				_ => &[],
+			};
+
+			for path in paths {
+				if !models.contains(path) {
+					models.push(path.clone());
+				}
+			}
+		}
+
+		models
+	}
+
+	/*
+	 * Models go to the prediction scene. CSVs with flows are training data,
+	 * or runs when the experiment log is shown, and CSVs of inputs only are
+	 * predicted as a batch.
+	 */
+	pub fn drop_file(&mut self, path: PathBuf) -> Result<(), Box<dyn Error>> {
+		let prediction = |scene: &Scene| matches!(scene, Scene::Prediction(_));
+		let training = |scene: &Scene| matches!(scene, Scene::Training(_));
+		let extension = path
+			.extension()
+			.and_then(|extension| extension.to_str())
+			.map(str::to_lowercase);
+
+		match extension.as_deref() {
+			Some("onnx") => self.show(prediction, SceneMessage::UseModel(path)),
+			Some("csv") => match (has_flows(&path)?, &self.list[self.current]) {
+				(true, Scene::Experiments(_)) => {
+					self.list[self.current].update(SceneMessage::UseTrainingData(path))
+				}
+				(true, _) => self.show(training, SceneMessage::UseTrainingData(path)),
+				(false, _) => self.show(prediction, SceneMessage::PredictBatch(path)),
+			},
+			_ => {
+				return Err(format!(
+					"iDrop cannot open {}, drop a model, a CSV or a project",
+					path.display()
+				)
+				.into())
+			}
+		}
+
+		Ok(())
+	}
+
+	pub fn broadcast(&mut self, event: SceneMessage) {
+		for scene in &mut self.list {
+			scene.update(event.clone());
+		}
+	}
+
+	// Hidden scenes keep their state, so any of them can be shown at any time
+	pub fn select(&mut self, index: usize) {
+		if index < self.list.len() {
+			self.current = index;
+		}
 	}
 
-	pub fn can_continue(&self) -> bool {
-		self.current < self.list.len() - 1
+	// Steps through the sidebar's order, wrapping around at either end
+	pub fn step(&mut self, forward: bool) {
+		let count = self.list.len();
+		self.current = if forward {
+			(self.current + 1) % count
+		} else {
+			(self.current + count - 1) % count
+		};
 	}
 
-	pub fn view(&self) -> Element<SceneMessage> {
-		self.list[self.current].view()
+	pub fn view(&self, theme: Theme) -> Element<'_, SceneMessage> {
+		focus_scope(self.list[self.current].view(theme)).into()
 	}
 }
@@ -0,0 +1,426 @@
+use iced::{
+	pure::{
+		column, container, horizontal_rule, horizontal_space, row, text,
+		widget::{Canvas, Column, Row, Text},
+	},
+	Alignment, Length,
+};
+use std::{error::Error, path::PathBuf};
+
+use super::{prediction_ui::Device, SceneMessage};
+use crate::{
+	dataset::{input_bounds, read_csv},
+	fluids::FluidLibrary,
+	optimize::{optimize, Candidate, Goals, Objective},
+	predictor::{OnnxPredictor, Predictor, ScalingLaw},
+	styling::{btn, drop_down, tinput, Theme, BOLD},
+	units::{format_value, Units},
+	views::prediction_ui::DeviceProfiles,
+	Droplet,
+};
+
+// -------------------------------------------------- OPTIMIZER UI
+pub struct OptimizerUI {
+	device: Option<Device>,
+	inputs: OptimizerInputs,
+	objective: Objective,
+	candidates: Vec<Candidate>,
+	selected: usize,
+	// of the goals and the candidates, the settings' length and flow units
+	units: Units,
+	// model of the prediction scene and the device it was chosen for
+	shared_model: Option<(Device, PathBuf)>,
+	model_name: Option<String>,
+	// continuous and dispersed phase names of the device's fluid pair
+	flow_labels: (String, String),
+	error: Result<(), Box<dyn Error>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum OptimizerInput {
+	Length(String),
+	Height(String),
+	MinFreq(String),
+	MaxTotalFlow(String),
+	Flow1Min(String),
+	Flow1Max(String),
+	Flow2Min(String),
+	Flow2Max(String),
+}
+
+#[derive(Default)]
+struct OptimizerInputs {
+	length: String,
+	height: String,
+	min_freq: String,
+	max_total_flow: String,
+	flow1_min: String,
+	flow1_max: String,
+	flow2_min: String,
+	flow2_max: String,
+}
+
+impl OptimizerInputs {
+	// Goals in the base units the models use
+	fn goals(&self, objective: Objective, units: &Units) -> Goals {
+		let parse = |value: &str, to_base: &dyn Fn(f32) -> f32| {
+			value.trim().parse::<f32>().ok().map(to_base)
+		};
+		let length = |value| units.dim_a.to_base(value);
+		let height = |value| units.dim_b.to_base(value);
+		let freq = |value| units.freq.to_base(value);
+		let flow1 = |value| units.flow1.to_base(value);
+		let flow2 = |value| units.flow2.to_base(value);
+
+		Goals {
+			length: parse(&self.length, &length),
+			height: parse(&self.height, &height),
+			min_freq: parse(&self.min_freq, &freq),
+			max_total_flow: parse(&self.max_total_flow, &flow1),
+			flow1: (
+				parse(&self.flow1_min, &flow1),
+				parse(&self.flow1_max, &flow1),
+			),
+			flow2: (
+				parse(&self.flow2_min, &flow2),
+				parse(&self.flow2_max, &flow2),
+			),
+			objective,
+		}
+	}
+
+	// Keeps the entered goals when the units change
+	fn convert(&mut self, old: &Units, new: &Units) {
+		let length = |value| new.dim_a.from_base(old.dim_a.to_base(value));
+		let height = |value| new.dim_b.from_base(old.dim_b.to_base(value));
+		let freq = |value| new.freq.from_base(old.freq.to_base(value));
+		let flow1 = |value| new.flow1.from_base(old.flow1.to_base(value));
+		let flow2 = |value| new.flow2.from_base(old.flow2.to_base(value));
+
+		for (value, convert) in [
+			(&mut self.length, &length as &dyn Fn(f32) -> f32),
+			(&mut self.height, &height),
+			(&mut self.min_freq, &freq),
+			(&mut self.max_total_flow, &flow1),
+			(&mut self.flow1_min, &flow1),
+			(&mut self.flow1_max, &flow1),
+			(&mut self.flow2_min, &flow2),
+			(&mut self.flow2_max, &flow2),
+		] {
+			if let Ok(number) = value.trim().parse::<f32>() {
+				*value = format_value(convert(number));
+			}
+		}
+	}
+}
+
+impl Default for OptimizerUI {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl OptimizerUI {
+	pub fn new() -> Self {
+		OptimizerUI {
+			device: None,
+			inputs: OptimizerInputs::default(),
+			objective: Objective::default(),
+			candidates: Vec::new(),
+			selected: 0,
+			units: Units::default(),
+			shared_model: None,
+			model_name: None,
+			flow_labels: default_labels(),
+			error: Ok(()),
+		}
+	}
+
+	pub fn update(&mut self, msg: SceneMessage) {
+		match msg {
+			SceneMessage::DeviceSelected(device) => {
+				self.device = Some(device);
+				self.flow_labels = FluidLibrary::load()
+					.ok()
+					.and_then(|library| {
+						let pair = library.device_pair(&device.to_string())?;
+						Some((pair.continuous.clone(), pair.dispersed.clone()))
+					})
+					.unwrap_or_else(default_labels);
+			}
+			SceneMessage::PredictionModel(model) => self.shared_model = model,
+			SceneMessage::ConfigChanged(config) => {
+				let units = Units::new(config.length_unit, config.flow_unit);
+				self.inputs.convert(&self.units, &units);
+				self.units = units;
+			}
+			SceneMessage::ObjectiveSelected(objective) => self.objective = objective,
+			SceneMessage::OptimizerInputChanged(input) => {
+				let inputs = &mut self.inputs;
+
+				let (field, value) = match input {
+					OptimizerInput::Length(value) => (&mut inputs.length, value),
+					OptimizerInput::Height(value) => (&mut inputs.height, value),
+					OptimizerInput::MinFreq(value) => (&mut inputs.min_freq, value),
+					OptimizerInput::MaxTotalFlow(value) => (&mut inputs.max_total_flow, value),
+					OptimizerInput::Flow1Min(value) => (&mut inputs.flow1_min, value),
+					OptimizerInput::Flow1Max(value) => (&mut inputs.flow1_max, value),
+					OptimizerInput::Flow2Min(value) => (&mut inputs.flow2_min, value),
+					OptimizerInput::Flow2Max(value) => (&mut inputs.flow2_max, value),
+				};
+
+				if value.parse::<f32>().is_ok() || value.is_empty() {
+					*field = value;
+				}
+			}
+			SceneMessage::CandidateSelected(index) => self.selected = index,
+			SceneMessage::GoPressed => {
+				self.selected = 0;
+				self.error = self.run();
+			}
+			_ => {}
+		}
+	}
+
+	/*
+	 * Searches the device's training domain with the prediction scene's neural
+	 * network if it is for the same device, or the device's, or with the
+	 * scaling law when there is no ONNX model.
+	 */
+	fn run(&mut self) -> Result<(), Box<dyn Error>> {
+		let device = self.device.ok_or("Select a device first")?;
+		let samples = read_csv(&device.data_path())?;
+		let bounds = input_bounds(&samples).ok_or("Training data is empty")?;
+
+		let path = match &self.shared_model {
+			Some((shared, path)) if *shared == device => path.clone(),
+			_ => device.model_path(),
+		};
+		let predictor: Box<dyn Predictor> = if path.exists() {
+			Box::new(OnnxPredictor::load(&path)?)
+		} else {
+			let volume_model = DeviceProfiles::load()?.get(device).volume_model;
+			Box::new(ScalingLaw::fit(&samples, device.channel(), volume_model)?)
+		};
+
+		self.model_name = Some(predictor.name());
+		self.candidates = optimize(
+			predictor.as_ref(),
+			&self.inputs.goals(self.objective, &self.units),
+			bounds,
+			5,
+		)?;
+
+		if self.candidates.is_empty() {
+			return Err("No operating point found".into());
+		}
+
+		Ok(())
+	}
+
+	pub fn view(&self, theme: Theme) -> Column<'_, SceneMessage> {
+		let field = |label: &str, value: &str, input: fn(String) -> OptimizerInput| {
+			Self::input_row(theme, label, value, move |s| {
+				SceneMessage::OptimizerInputChanged(input(s))
+			})
+		};
+
+		let inputs = &self.inputs;
+		let units = &self.units;
+
+		let goals = column()
+			.spacing(10)
+			.push(
+				row()
+					.push(text("Device"))
+					.push(horizontal_space(Length::Fill))
+					.push(
+						drop_down(
+							theme,
+							&Device::ALL[..],
+							self.device,
+							SceneMessage::DeviceSelected,
+						)
+						.placeholder("Choose a device...")
+						.width(Length::Units(200)),
+					)
+					.align_items(Alignment::Center),
+			)
+			.push(field(
+				&format!("Droplet length ({})", units.dim_a),
+				&inputs.length,
+				OptimizerInput::Length,
+			))
+			.push(field(
+				&format!("Droplet height ({})", units.dim_b),
+				&inputs.height,
+				OptimizerInput::Height,
+			))
+			.push(field(
+				&format!("Frequency at least ({})", units.freq),
+				&inputs.min_freq,
+				OptimizerInput::MinFreq,
+			))
+			.push(field(
+				&format!("Total flow at most ({})", units.flow1),
+				&inputs.max_total_flow,
+				OptimizerInput::MaxTotalFlow,
+			))
+			.push(field(
+				&format!("Flow 1 from ({})", units.flow1),
+				&inputs.flow1_min,
+				OptimizerInput::Flow1Min,
+			))
+			.push(field(
+				&format!("Flow 1 to ({})", units.flow1),
+				&inputs.flow1_max,
+				OptimizerInput::Flow1Max,
+			))
+			.push(field(
+				&format!("Flow 2 from ({})", units.flow2),
+				&inputs.flow2_min,
+				OptimizerInput::Flow2Min,
+			))
+			.push(field(
+				&format!("Flow 2 to ({})", units.flow2),
+				&inputs.flow2_max,
+				OptimizerInput::Flow2Max,
+			))
+			.push(
+				row()
+					.push(text("Prefer"))
+					.push(horizontal_space(Length::Fill))
+					.push(
+						drop_down(
+							theme,
+							&Objective::ALL[..],
+							Some(self.objective),
+							SceneMessage::ObjectiveSelected,
+						)
+						.width(Length::Units(200)),
+					)
+					.align_items(Alignment::Center),
+			);
+
+		let mut view = column()
+			.spacing(20)
+			.push(Text::new(
+				"Leave a goal empty to not constrain it. Candidates are searched within the range of the device's training data.",
+			))
+			.push(goals);
+
+		if self.device.is_some() {
+			view = view.push(btn(theme, "Optimise", SceneMessage::GoPressed));
+		}
+
+		if let Err(e) = &self.error {
+			return view.push(Text::new(e.to_string()));
+		}
+
+		if self.candidates.is_empty() {
+			return view;
+		}
+
+		// -------------------- CANDIDATES
+		let (flow1_label, flow2_label) = &self.flow_labels;
+
+		let mut table = column().spacing(5).push(
+			row()
+				.spacing(10)
+				.push(text("Rank").font(BOLD).width(Length::Units(60)))
+				.push(
+					text(format!("Droplet ({})", units.dim_a))
+						.font(BOLD)
+						.width(Length::Fill),
+				)
+				.push(
+					text(format!("Freq ({})", units.freq))
+						.font(BOLD)
+						.width(Length::Fill),
+				)
+				.push(
+					text(format!("{flow1_label} / {flow2_label} ({})", units.flow1))
+						.font(BOLD)
+						.width(Length::Fill),
+				)
+				.push(text("").width(Length::Units(80))),
+		);
+
+		for (i, candidate) in self.candidates.iter().enumerate() {
+			let [dim_a, dim_b, freq] = units.inputs_from_base(candidate.inputs);
+			let [flow1, flow2] = units.flows_from_base(candidate.flows);
+			let rank = if candidate.feasible {
+				format!("{}", i + 1)
+			} else {
+				format!("{} (!)", i + 1)
+			};
+
+			table = table.push(
+				row()
+					.spacing(10)
+					.align_items(Alignment::Center)
+					.push(text(rank).size(16).width(Length::Units(60)))
+					.push(
+						text(format!("{} × {}", format_value(dim_a), format_value(dim_b)))
+							.size(16)
+							.width(Length::Fill),
+					)
+					.push(text(format_value(freq)).size(16).width(Length::Fill))
+					.push(
+						text(format!("{} / {}", format_value(flow1), format_value(flow2)))
+							.size(16)
+							.width(Length::Fill),
+					)
+					.push(
+						btn(theme, "Show", SceneMessage::CandidateSelected(i))
+							.width(Length::Units(80)),
+					),
+			);
+		}
+
+		let selected = self.candidates[self.selected.min(self.candidates.len() - 1)];
+		let [dim_a, dim_b, _] = selected.inputs;
+
+		let droplet: Canvas<SceneMessage, Droplet> = Canvas::new(Droplet {
+			radii: (dim_b, dim_a),
+			range: None,
+			channel: self.device.map(|device| device.channel()),
+			theme,
+		});
+
+		let mut notes = column().spacing(5);
+		if let Some(name) = &self.model_name {
+			notes = notes.push(text(format!("Evaluated with {name}")).size(16));
+		}
+		if self.candidates.iter().any(|candidate| !candidate.feasible) {
+			notes = notes.push(text("(!) misses at least one goal").size(16));
+		}
+
+		view.push(horizontal_rule(1)).push(table).push(notes).push(
+			container(droplet.width(Length::Units(200)).height(Length::Units(200)))
+				.width(Length::Fill)
+				.center_x(),
+		)
+	}
+
+	fn input_row<'a>(
+		theme: Theme,
+		label: &str,
+		value: &str,
+		update: impl Fn(String) -> SceneMessage + 'a,
+	) -> Row<'a, SceneMessage> {
+		row()
+			.push(text(label))
+			.push(horizontal_space(Length::Fill))
+			.push(
+				tinput(theme, "", value, update)
+					.width(Length::Units(200))
+					.padding(10),
+			)
+			.align_items(Alignment::Center)
+	}
+}
+
+fn default_labels() -> (String, String) {
+	(String::from("Flow 1"), String::from("Flow 2"))
+}
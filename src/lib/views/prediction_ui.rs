@@ -1,17 +1,32 @@
 #![allow(unused)]
 use iced::pure::{container, horizontal_rule, horizontal_space, row};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::path::PathBuf;
 
-use super::super::styling::{btn, drop_down, tglr, tinput, BOLD};
+use super::super::styling::{btn, drop_down, tglr, tinput, Theme, BOLD};
 use super::super::Droplet;
 use super::SceneMessage;
+use crate::animation::{DropletTrain, PlaybackSpeed, Train};
 use crate::colors::{Extended, Palette, EXTENDED_DARK, EXTENDED_LIGHT};
+use crate::dataset::{input_bounds, read_csv, read_inputs, write_csv, Sample};
+use crate::fluids::{FluidLibrary, FluidPair};
+use crate::history::{file_hash, timestamp, History, Record};
+use crate::ml::ModelInfo;
+use crate::physics::{conservation_error, Channel, FlowConditions, FluidProperties, VolumeModel};
+use crate::plot::{Heatmap, LinePlot};
+use crate::predictor::{OnnxPredictor, Predictor, ScalingLaw};
+use crate::project::{DATA_DIR, MODELS_DIR};
+use crate::sensitivity::Sensitivity;
+use crate::storage::{data_dir, load_json, project_dir, save_json};
+use crate::sweep::{Axis, Sweep, SweepResult, Variable};
+use crate::units::{format_value, FlowUnit, FrequencyUnit, LengthUnit, UnitChoice, Units};
 
 use iced::pure::widget::{Canvas, PickList, Row, Text};
 use iced::{
 	alignment,
-	canvas::{Cursor, Frame, Geometry, Path, Stroke},
+	canvas::{Cache, Cursor, Frame, Geometry, Path, Stroke},
 	pure::{
 		column, text, vertical_space,
 		widget::{
@@ -20,7 +35,8 @@ use iced::{
 		},
 		Element, Sandbox,
 	},
-	Color, Length, Point, Rectangle, Vector,
+	time::{Duration, Instant},
+	Color, Length, Point, Rectangle, Subscription, Vector,
 };
 
 use iced_style::{button, menu, pick_list, text_input, toggler};
@@ -32,9 +48,36 @@ use tract_onnx::prelude::*;
 pub struct PredictionUI {
 	selection: Option<Device>,
 	input_data: PredictionInputs,
-	prediction_data: Option<(f32, f32)>, // pbs,fluosurf
+	prediction_data: Option<(f32, f32)>, // continuous, dispersed
+	prediction_query: Option<[f32; 3]>,  // dim_a, dim_b, freq
+	baseline: Option<ScalingLaw>,
+	baseline_data: Option<(f32, f32)>,
+	// range of dim_a, dim_b and freq in the device's training data
+	bounds: Option<[(f32, f32); 3]>,
+	sensitivity: Option<Sensitivity>,
+	profiles: DeviceProfiles,
+	fluid_pair: Option<FluidPair>,
 	user_model_path: Option<PathBuf>,
+	// models other than the devices' loaded in this session, bundled into saved projects
+	session_models: Vec<PathBuf>,
 	user_model_toggle: bool,
+	sweep_toggle: bool,
+	sweep: SweepInputs,
+	sweep_result: Option<SweepResult>,
+	// the heatmap of sweep_result, drawn again only after clear()
+	heatmap: Cache,
+	animate_toggle: bool,
+	playback: PlaybackSpeed,
+	animation_time: f32, // simulated seconds
+	last_tick: Option<Instant>,
+	history: History,
+	history_toggle: bool,
+	// outcome of the last batch prediction
+	batch_status: Option<String>,
+	// of the fields, values are kept in the base units once parsed
+	units: Units,
+	// from the config
+	model_dir: Option<PathBuf>,
 	error: Result<(), Box<dyn Error>>,
 }
 
@@ -48,6 +91,16 @@ pub enum PredictionInput {
 	DimA(String),
 	DimB(String),
 	Freq(String),
+	ContViscosity(String),
+	DispViscosity(String),
+	ContDensity(String),
+	DispDensity(String),
+	Interfacial(String),
+	Temperature(String),
+	Tolerance(String),
+	DimAError(String),
+	DimBError(String),
+	FreqError(String),
 }
 
 // For encapsulating all the inputs on the page
@@ -56,6 +109,103 @@ struct PredictionInputs {
 	dim_a: Option<String>,
 	dim_b: Option<String>,
 	freq: Option<String>,
+	cont_viscosity: Option<String>,
+	disp_viscosity: Option<String>,
+	cont_density: Option<String>,
+	disp_density: Option<String>,
+	interfacial: Option<String>,
+	temperature: Option<String>,
+	tolerance: Option<String>,
+	// measurement uncertainty (±) of dim_a, dim_b and freq
+	dim_a_error: Option<String>,
+	dim_b_error: Option<String>,
+	freq_error: Option<String>,
+}
+
+impl PredictionInputs {
+	// Fluid properties needed for the physics panel, if all of them are provided
+	fn flow_conditions(&self, channel: Channel, flows: (f32, f32)) -> Option<FlowConditions> {
+		let parse = |value: &Option<String>| value.as_deref()?.trim().parse::<f32>().ok();
+
+		Some(FlowConditions {
+			continuous: FluidProperties {
+				viscosity: parse(&self.cont_viscosity)?,
+				density: parse(&self.cont_density)?,
+			},
+			dispersed: FluidProperties {
+				viscosity: parse(&self.disp_viscosity)?,
+				density: parse(&self.disp_density)?,
+			},
+			interfacial_tension: parse(&self.interfacial)?,
+			channel,
+			flow_continuous: flows.0,
+			flow_dispersed: flows.1,
+		})
+	}
+}
+
+#[derive(Clone, Debug)]
+pub enum SweepInput {
+	XFrom(String),
+	XTo(String),
+	XSteps(String),
+	YFrom(String),
+	YTo(String),
+	YSteps(String),
+}
+
+// Ranges of the parameter sweep, the remaining inputs are held at their entered values
+struct SweepInputs {
+	x: Variable,
+	y: Variable,
+	two_d: bool,
+	x_range: [String; 3], // from, to, steps
+	y_range: [String; 3],
+	output: usize,
+}
+
+impl Default for SweepInputs {
+	fn default() -> Self {
+		SweepInputs {
+			x: Variable::DimA,
+			y: Variable::Freq,
+			two_d: false,
+			x_range: [String::new(), String::new(), String::from("20")],
+			y_range: [String::new(), String::new(), String::from("20")],
+			output: 0,
+		}
+	}
+}
+
+impl SweepInputs {
+	// The range is entered in the variable's field unit, the axis is in base units
+	fn axis(
+		variable: Variable,
+		[from, to, steps]: &[String; 3],
+		units: &Units,
+	) -> Result<Axis, Box<dyn Error>> {
+		let number = |value: &str| value.trim().parse::<f32>().ok();
+
+		match (number(from), number(to), steps.trim().parse::<usize>()) {
+			(Some(from), Some(to), Ok(steps)) => {
+				let axis = Axis {
+					variable,
+					from: variable.to_base(units, from),
+					to: variable.to_base(units, to),
+					steps,
+				};
+				axis.check()?;
+				Ok(axis)
+			}
+			_ => Err(format!("Enter a range and a number of steps for {variable}").into()),
+		}
+	}
+}
+
+impl Default for PredictionUI {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 impl PredictionUI {
@@ -64,8 +214,29 @@ impl PredictionUI {
 			selection: None,
 			input_data: PredictionInputs::default(),
 			prediction_data: None,
+			prediction_query: None,
+			baseline: None,
+			baseline_data: None,
+			bounds: None,
+			sensitivity: None,
+			profiles: DeviceProfiles::load().unwrap_or_default(),
+			fluid_pair: None,
 			user_model_path: None,
+			session_models: Vec::new(),
 			user_model_toggle: false,
+			sweep_toggle: false,
+			sweep: SweepInputs::default(),
+			sweep_result: None,
+			heatmap: Cache::new(),
+			animate_toggle: false,
+			playback: PlaybackSpeed::default(),
+			animation_time: 0.0,
+			last_tick: None,
+			history: History::load().unwrap_or_default(),
+			history_toggle: false,
+			batch_status: None,
+			units: Units::default(),
+			model_dir: None,
 			error: Ok(()),
 		}
 	}
@@ -77,49 +248,224 @@ impl PredictionUI {
 	pub fn update(&mut self, msg: SceneMessage) {
 		match msg {
 			SceneMessage::SelectModel => {
-				self.user_model_path = FileDialog::new()
-					.add_filter("ONNX File", &["onnx"])
-					.show_open_single_file()
-					.unwrap();
+				let mut dialog = FileDialog::new().add_filter("ONNX File", &["onnx"]);
+				if let Some(dir) = &self.model_dir {
+					dialog = dialog.set_location(dir);
+				}
+
+				self.user_model_path = dialog.show_open_single_file().unwrap();
+				self.remember_model();
+				self.select_fluid_pair();
+			}
+			SceneMessage::ConfigChanged(config) => {
+				self.set_units(Units {
+					freq: self.units.freq,
+					..Units::new(config.length_unit, config.flow_unit)
+				});
+				self.model_dir = config.model_dir;
+
+				if let (None, Some(device)) = (self.selection, config.default_device) {
+					self.update(SceneMessage::DeviceSelected(device));
+				}
 			}
 			SceneMessage::UserModelToggled(value) => self.user_model_toggle = value,
+			SceneMessage::UseModel(path) => {
+				self.user_model_toggle = true;
+				self.user_model_path = Some(path);
+				self.remember_model();
+				self.prediction_data = None;
+				self.select_fluid_pair();
+			}
 			SceneMessage::DeviceSelected(device) => {
-				self.user_model_path = Some(PathBuf::from(device.path()));
+				self.user_model_path = Some(device.model_path());
 				self.selection = Some(device);
+				self.input_data.tolerance =
+					Some((self.profiles.get(device).tolerance * 100.0).to_string());
+				self.select_fluid_pair();
+				self.error = self.fit_baseline();
+			}
+			SceneMessage::VolumeModelSelected(volume_model) => {
+				if let Some(device) = self.selection {
+					self.profiles.entry(device).volume_model = volume_model;
+					self.error = match self.profiles.save() {
+						Ok(()) => self.fit_baseline(),
+						Err(e) => Err(e),
+					};
+				}
+			}
+			// a tolerance of 0% or less would flag every row, so only positive ones are kept
+			SceneMessage::PredictionInputChanged(PredictionInput::Tolerance(value))
+				if value
+					.parse::<f32>()
+					.is_ok_and(|percent| percent.is_finite() && percent >= 0.0)
+					|| value.is_empty() =>
+			{
+				if let (Some(device), Ok(percent)) = (self.selection, value.parse::<f32>()) {
+					self.error = if percent > 0.0 {
+						self.profiles.entry(device).tolerance = percent / 100.0;
+						self.profiles.save()
+					} else {
+						Err("The tolerance must be above 0%".into())
+					};
+				}
+				self.input_data.tolerance = Some(value);
+			}
+			SceneMessage::PredictionInputChanged(PredictionInput::Temperature(value))
+				if value.parse::<f32>().is_ok() || value.is_empty() =>
+			{
+				self.input_data.temperature = Some(value);
+				self.select_fluid_pair();
 			}
 			SceneMessage::PredictionInputChanged(input) => {
-				let PredictionInputs { dim_a, dim_b, freq } = &mut self.input_data;
+				let PredictionInputs {
+					dim_a,
+					dim_b,
+					freq,
+					cont_viscosity,
+					disp_viscosity,
+					cont_density,
+					disp_density,
+					interfacial,
+					dim_a_error,
+					dim_b_error,
+					freq_error,
+					..
+				} = &mut self.input_data;
 
-				match input {
-					PredictionInput::DimA(value) => {
-						if value.parse::<f32>().is_ok() || value.is_empty() {
-							*dim_a = Some(value)
-						};
-					}
-					PredictionInput::DimB(value) => {
-						if value.parse::<f32>().is_ok() || value.is_empty() {
-							*dim_b = Some(value);
-						};
-					}
+				let (field, value) = match input {
+					PredictionInput::DimA(value) => (dim_a, value),
+					PredictionInput::DimB(value) => (dim_b, value),
 					PredictionInput::Freq(value) => {
 						if value.parse::<f32>().is_ok() {
 							*freq = Some(value);
 						};
+						return;
 					}
-				}
+					PredictionInput::ContViscosity(value) => (cont_viscosity, value),
+					PredictionInput::DispViscosity(value) => (disp_viscosity, value),
+					PredictionInput::ContDensity(value) => (cont_density, value),
+					PredictionInput::DispDensity(value) => (disp_density, value),
+					PredictionInput::Interfacial(value) => (interfacial, value),
+					PredictionInput::DimAError(value) => (dim_a_error, value),
+					PredictionInput::DimBError(value) => (dim_b_error, value),
+					PredictionInput::FreqError(value) => (freq_error, value),
+					PredictionInput::Temperature(_) | PredictionInput::Tolerance(_) => return,
+				};
+
+				if value.parse::<f32>().is_ok() || value.is_empty() {
+					*field = Some(value);
+				};
 			}
 			SceneMessage::GoPressed => {
-				if let Err(e) = self.get_inference() {
-					self.error = Err(Box::from(e));
+				self.error = self.get_inference();
+			}
+			SceneMessage::SweepToggled(value) => self.sweep_toggle = value,
+			SceneMessage::AnimateToggled(value) => {
+				self.animate_toggle = value;
+				self.last_tick = None;
+			}
+			SceneMessage::PlaybackSpeedSelected(speed) => self.playback = speed,
+			SceneMessage::UseDimensions(dim_a, dim_b) => {
+				self.input_data.dim_a = Some(format_value(self.units.dim_a.from_base(dim_a)));
+				self.input_data.dim_b = Some(format_value(self.units.dim_b.from_base(dim_b)));
+			}
+			SceneMessage::UseFrequency(freq) => {
+				self.input_data.freq = Some(format_value(self.units.freq.from_base(freq)));
+			}
+			SceneMessage::UnitSelected(choice) => self.set_units(self.units.with(choice)),
+			SceneMessage::PredictBatch(path) => {
+				self.batch_status = None;
+				self.error = self.predict_batch(&path);
+			}
+			SceneMessage::Tick(now) => {
+				if let Some(last) = self.last_tick {
+					self.animation_time += (now - last).as_secs_f32() * self.playback.factor();
+				}
+				self.last_tick = Some(now);
+			}
+			SceneMessage::SweepTwoDToggled(value) => self.sweep.two_d = value,
+			SceneMessage::SweepXSelected(variable) => self.sweep.x = variable,
+			SceneMessage::SweepYSelected(variable) => self.sweep.y = variable,
+			SceneMessage::SweepOutputSelected(name) => {
+				let (continuous, _) = self.phase_labels();
+				self.sweep.output = if name == continuous { 0 } else { 1 };
+				self.heatmap.clear();
+			}
+			SceneMessage::SweepInputChanged(input) => {
+				// steps are whole numbers, the range ends any number
+				let (field, value, whole) = match input {
+					SweepInput::XFrom(value) => (&mut self.sweep.x_range[0], value, false),
+					SweepInput::XTo(value) => (&mut self.sweep.x_range[1], value, false),
+					SweepInput::XSteps(value) => (&mut self.sweep.x_range[2], value, true),
+					SweepInput::YFrom(value) => (&mut self.sweep.y_range[0], value, false),
+					SweepInput::YTo(value) => (&mut self.sweep.y_range[1], value, false),
+					SweepInput::YSteps(value) => (&mut self.sweep.y_range[2], value, true),
+				};
+
+				let valid = if whole {
+					value.parse::<usize>().is_ok()
+				} else {
+					value.parse::<f32>().is_ok()
 				};
+
+				if valid || value.is_empty() {
+					*field = value;
+				}
+			}
+			SceneMessage::RunSweep => {
+				self.error = self.run_sweep();
+			}
+			SceneMessage::ExportSweepCsv => {
+				if let Some(result) = &self.sweep_result {
+					if let Ok(Some(path)) = FileDialog::new()
+						.add_filter("CSV File", &["csv"])
+						.show_save_single_file()
+					{
+						self.error = result.save_csv(&path.with_extension("csv"), &self.units);
+					}
+				}
+			}
+			SceneMessage::ExportSweepPng => {
+				if let Some(result) = &self.sweep_result {
+					if let Ok(Some(path)) = FileDialog::new()
+						.add_filter("PNG Image", &["png"])
+						.show_save_single_file()
+					{
+						self.error =
+							result.save_png(&path.with_extension("png"), self.sweep.output);
+					}
+				}
+			}
+			SceneMessage::HistoryToggled(value) => self.history_toggle = value,
+			SceneMessage::RestoreRecord(index) => self.restore(index),
+			SceneMessage::RerunRecord(index) => {
+				self.restore(index);
+				self.error = self.get_inference();
+			}
+			SceneMessage::ExportHistoryCsv => {
+				if let Ok(Some(path)) = FileDialog::new()
+					.add_filter("CSV File", &["csv"])
+					.show_save_single_file()
+				{
+					self.error = self.history.save_csv(&path.with_extension("csv"));
+				}
+			}
+			SceneMessage::ExportHistoryJson => {
+				if let Ok(Some(path)) = FileDialog::new()
+					.add_filter("JSON File", &["json"])
+					.show_save_single_file()
+				{
+					self.error = self.history.save_json(&path.with_extension("json"));
+				}
 			}
 			_ => {}
 		}
 	}
 
-	pub fn view(&self) -> Column<SceneMessage> {
+	pub fn view(&self, theme: Theme) -> Column<'_, SceneMessage> {
 		// Dropdown
 		let pick_list: PickList<'_, Device, SceneMessage> = drop_down(
+			theme,
 			&Device::ALL[..],
 			self.selection,
 			SceneMessage::DeviceSelected,
@@ -130,6 +476,7 @@ impl PredictionUI {
 		let mut model_selection = row()
 			.push(
 				tglr(
+					theme,
 					"Load own model?",
 					self.user_model_toggle,
 					SceneMessage::UserModelToggled,
@@ -140,8 +487,9 @@ impl PredictionUI {
 			.push(horizontal_space(Length::Fill));
 
 		model_selection = if self.user_model_toggle {
-			model_selection
-				.push(btn("Select model", SceneMessage::SelectModel).width(Length::Units(200)))
+			model_selection.push(
+				btn(theme, "Select model", SceneMessage::SelectModel).width(Length::Units(200)),
+			)
 		} else {
 			model_selection.push(pick_list)
 		};
@@ -156,63 +504,161 @@ impl PredictionUI {
 
 		let mut inputs = column()
 			.push(horizontal_rule(1))
-			.push(Self::input_row("Droplet length (µm)", dim_a, move |s| {
-				SceneMessage::PredictionInputChanged(PredictionInput::DimA(s))
-			}))
-			.push(Self::input_row("Droplet height (µm)", dim_b, move |s| {
-				SceneMessage::PredictionInputChanged(PredictionInput::DimB(s))
-			}))
-			.push(Self::input_row("Frequency (Hz)", freq, move |s| {
-				SceneMessage::PredictionInputChanged(PredictionInput::Freq(s))
-			}))
-			// .push(Self::input_row("Capillary number", capillary, move |s| {
-			// 	SceneMessage::PredictionInputChanged(PredictionInput::Capillary(s))
-			// }))
-			// .push(Self::input_row(
-			// 	"Interfacial tension (mN/m)",
-			// 	interfacial,
-			// 	move |s| SceneMessage::PredictionInputChanged(PredictionInput::Interfacial(s)),
-			// ))
+			.push(
+				Self::input_row(theme, "Droplet length", dim_a, move |s| {
+					SceneMessage::PredictionInputChanged(PredictionInput::DimA(s))
+				})
+				.push(Self::unit_picker(
+					theme,
+					&LengthUnit::ALL[..],
+					self.units.dim_a,
+					UnitChoice::DimA,
+				)),
+			)
+			.push(
+				Self::input_row(theme, "Droplet height", dim_b, move |s| {
+					SceneMessage::PredictionInputChanged(PredictionInput::DimB(s))
+				})
+				.push(Self::unit_picker(
+					theme,
+					&LengthUnit::ALL[..],
+					self.units.dim_b,
+					UnitChoice::DimB,
+				)),
+			)
+			.push(
+				Self::input_row(theme, "Frequency", freq, move |s| {
+					SceneMessage::PredictionInputChanged(PredictionInput::Freq(s))
+				})
+				.push(Self::unit_picker(
+					theme,
+					&FrequencyUnit::ALL[..],
+					self.units.freq,
+					UnitChoice::Freq,
+				)),
+			)
 			.spacing(10);
 
-		if self.user_model_path.is_some()
+		// -------------------- FLUID PROPERTIES
+		let field = |value: &'_ Option<String>| value.as_deref().unwrap_or("").to_owned();
+
+		let pair = match &self.fluid_pair {
+			Some(pair) => format!("Fluid properties ({pair})"),
+			None => String::from("Fluid properties (optional)"),
+		};
+
+		let fluids = column()
+			.push(text(pair).font(BOLD))
+			.push(Self::input_row(
+				theme,
+				"Temperature (°C)",
+				&field(&self.input_data.temperature),
+				move |s| SceneMessage::PredictionInputChanged(PredictionInput::Temperature(s)),
+			))
+			.push(Self::input_row(
+				theme,
+				"Continuous viscosity (mPa·s)",
+				&field(&self.input_data.cont_viscosity),
+				move |s| SceneMessage::PredictionInputChanged(PredictionInput::ContViscosity(s)),
+			))
+			.push(Self::input_row(
+				theme,
+				"Continuous density (kg/m³)",
+				&field(&self.input_data.cont_density),
+				move |s| SceneMessage::PredictionInputChanged(PredictionInput::ContDensity(s)),
+			))
+			.push(Self::input_row(
+				theme,
+				"Dispersed viscosity (mPa·s)",
+				&field(&self.input_data.disp_viscosity),
+				move |s| SceneMessage::PredictionInputChanged(PredictionInput::DispViscosity(s)),
+			))
+			.push(Self::input_row(
+				theme,
+				"Dispersed density (kg/m³)",
+				&field(&self.input_data.disp_density),
+				move |s| SceneMessage::PredictionInputChanged(PredictionInput::DispDensity(s)),
+			))
+			.push(Self::input_row(
+				theme,
+				"Interfacial tension (mN/m)",
+				&field(&self.input_data.interfacial),
+				move |s| SceneMessage::PredictionInputChanged(PredictionInput::Interfacial(s)),
+			))
+			.spacing(10);
+
+		inputs = inputs.push(fluids);
+
+		if let Some(device) = self.selection {
+			let volume_model = row()
+				.push(text("Droplet geometry"))
+				.push(horizontal_space(Length::Fill))
+				.push(
+					drop_down(
+						theme,
+						&VolumeModel::ALL[..],
+						Some(self.profiles.get(device).volume_model),
+						SceneMessage::VolumeModelSelected,
+					)
+					.width(Length::Units(200)),
+				)
+				.align_items(iced::Alignment::Center);
+
+			inputs = inputs.push(volume_model).push(Self::input_row(
+				theme,
+				"Conservation tolerance (%)",
+				&field(&self.input_data.tolerance),
+				move |s| SceneMessage::PredictionInputChanged(PredictionInput::Tolerance(s)),
+			));
+		}
+
+		if (self.user_model_path.is_some() || self.baseline.is_some())
 			&& self.input_data.dim_a.is_some()
 			&& self.input_data.dim_b.is_some()
 			&& self.input_data.freq.is_some()
 		{
-			inputs = inputs.push(btn("Predict Flow", SceneMessage::GoPressed));
+			inputs = inputs.push(btn(theme, "Predict Flow", SceneMessage::GoPressed));
 		}
 
 		inputs = inputs.push(horizontal_rule(1));
 
 		// -------------------- CANVAS
-		let (dim_a, dim_b) = (dim_a.trim().parse::<f32>(), dim_b.trim().parse::<f32>());
+		// the canvas draws in µm
+		let number = |value: &str| value.trim().parse::<f32>().unwrap_or(0.);
+		let (dim_a, dim_b) = (
+			self.units.dim_a.to_base(number(dim_a)),
+			self.units.dim_b.to_base(number(dim_b)),
+		);
 
 		let graphical_element: Canvas<SceneMessage, Droplet> = Canvas::new(Droplet {
-			radii: (
-				*dim_b.as_ref().unwrap_or(&0.),
-				*dim_a.as_ref().unwrap_or(&0.),
-			),
+			radii: (dim_b, dim_a),
+			range: self.bounds.map(|[length, height, _]| [length, height]),
+			channel: self.selection.map(|device| device.channel()),
+			theme,
 		});
 
 		// -------------------- RESULT
 		let mut inference_res: Column<SceneMessage> = column();
-		if let Some((pbs, flu)) = self.prediction_data {
-			inference_res = inference_res
-				.push(
-					row()
-						.push(text("PBS:"))
-						.push(horizontal_space(Length::Fill))
-						.push(text(format!("{pbs} µL/min"))),
-				)
-				.push(
-					row()
-						.push(text("FluoSurf:"))
-						.push(horizontal_space(Length::Fill))
-						.push(text(format!("{flu} µL/min"))),
-				);
+		if let Some(flows) = self.prediction_data {
+			inference_res = inference_res.push(self.flow_rows(theme, flows));
 		};
 
+		if let Some(flows) = self.baseline_data {
+			inference_res = inference_res
+				.push(text("Scaling law").font(BOLD).size(16))
+				.push(self.flow_rows(theme, flows));
+		}
+
+		if let Some((continuous, dispersed)) = self.prediction_data.or(self.baseline_data) {
+			inference_res = inference_res
+				.push(self.conservation_panel(dispersed))
+				.push(self.physics_panel((continuous, dispersed)));
+		}
+
+		if let Some(status) = &self.batch_status {
+			inference_res = inference_res.push(text(status).size(16));
+		}
+
 		if let Err(e) = &self.error {
 			inference_res = column().push(Text::new(e.to_string()));
 		};
@@ -237,16 +683,8 @@ impl PredictionUI {
 
 		let mut view = column().spacing(10).push(model_selection);
 
-		if self.user_model_path.is_some() {
-			let file_name = self
-				.user_model_path
-				.as_ref()
-				.unwrap()
-				.file_name()
-				.unwrap()
-				.to_str()
-				.unwrap()
-				.to_owned();
+		if let Some(model_path) = &self.user_model_path {
+			let file_name = model_path.file_name().unwrap().to_str().unwrap().to_owned();
 
 			view = view.push(
 				row()
@@ -256,45 +694,870 @@ impl PredictionUI {
 			);
 		}
 
-		view.push(Text::new("Please select the model and enter the following parameters. The button will not be available until all required parameters are provided.")).push(inputs).push(result)
+		view = view.push(Text::new("Please select the model and enter the following parameters. The button will not be available until all required parameters are provided.")).push(inputs).push(result).push(self.sensitivity_panel(theme));
+
+		view.push(horizontal_rule(1))
+			.push(
+				tglr(
+					theme,
+					"Droplet train",
+					self.animate_toggle,
+					SceneMessage::AnimateToggled,
+				)
+				.width(Length::Shrink),
+			)
+			.push(if self.animate_toggle {
+				self.animation_panel(theme)
+			} else {
+				column()
+			})
+			.push(
+				tglr(
+					theme,
+					"Sweep mode",
+					self.sweep_toggle,
+					SceneMessage::SweepToggled,
+				)
+				.width(Length::Shrink),
+			)
+			.push(if self.sweep_toggle {
+				self.sweep_panel(theme)
+			} else {
+				column()
+			})
+			.push(
+				tglr(
+					theme,
+					"History",
+					self.history_toggle,
+					SceneMessage::HistoryToggled,
+				)
+				.width(Length::Shrink),
+			)
+			.push(if self.history_toggle {
+				self.history_panel(theme)
+			} else {
+				column()
+			})
+	}
+
+	// -------------------- ANIMATION
+	pub fn subscription(&self) -> Subscription<SceneMessage> {
+		if self.animate_toggle && self.droplet_train().is_some() {
+			iced::time::every(Duration::from_millis(30)).map(SceneMessage::Tick)
+		} else {
+			Subscription::none()
+		}
+	}
+
+	// Stream of the predicted droplets, preferring the neural network's flows
+	fn droplet_train(&self) -> Option<DropletTrain> {
+		let (continuous, dispersed) = self.prediction_data.or(self.baseline_data)?;
+
+		DropletTrain::new(
+			self.prediction_query?,
+			[continuous, dispersed],
+			self.selection?.channel(),
+		)
+	}
+
+	fn animation_panel(&self, theme: Theme) -> Column<'_, SceneMessage> {
+		let train = match self.droplet_train() {
+			Some(train) if self.error.is_ok() => train,
+			_ => return column().push(text("Predict a flow to see the droplet train.").size(16)),
+		};
+
+		let canvas: Canvas<SceneMessage, Train> = Canvas::new(Train {
+			train,
+			time: self.animation_time,
+			theme,
+		});
+
+		column()
+			.spacing(10)
+			.push(canvas.width(Length::Fill).height(Length::Units(100)))
+			.push(
+				text(format!(
+					"Spacing {:.0} µm, velocity {:.1} mm/s, {:.0} droplets/s",
+					train.spacing(),
+					train.velocity / 1000.0,
+					train.frequency,
+				))
+				.size(16),
+			)
+			.push(
+				row()
+					.push(text("Playback"))
+					.push(horizontal_space(Length::Fill))
+					.push(
+						drop_down(
+							theme,
+							&PlaybackSpeed::ALL[..],
+							Some(self.playback),
+							SceneMessage::PlaybackSpeedSelected,
+						)
+						.width(Length::Units(200)),
+					)
+					.align_items(iced::Alignment::Center),
+			)
+	}
+
+	// -------------------- SWEEP
+	fn sweep_panel(&self, theme: Theme) -> Column<'_, SceneMessage> {
+		let sweep = &self.sweep;
+
+		let axis_row = |variable: Variable,
+		                on_select: fn(Variable) -> SceneMessage,
+		                range: &[String; 3],
+		                inputs: [fn(String) -> SweepInput; 3]| {
+			row()
+				.spacing(10)
+				.align_items(iced::Alignment::Center)
+				.push(
+					drop_down(theme, &Variable::ALL[..], Some(variable), on_select)
+						.width(Length::Units(180)),
+				)
+				.push(
+					tinput(
+						theme,
+						&format!("from ({})", variable.unit(&self.units)),
+						&range[0],
+						move |s| SceneMessage::SweepInputChanged(inputs[0](s)),
+					)
+					.padding(10),
+				)
+				.push(
+					tinput(
+						theme,
+						&format!("to ({})", variable.unit(&self.units)),
+						&range[1],
+						move |s| SceneMessage::SweepInputChanged(inputs[1](s)),
+					)
+					.padding(10),
+				)
+				.push(
+					tinput(theme, "steps", &range[2], move |s| {
+						SceneMessage::SweepInputChanged(inputs[2](s))
+					})
+					.padding(10),
+				)
+		};
+
+		let mut panel = column()
+			.spacing(10)
+			.push(text("Inputs that are not swept keep the values entered above.").size(16))
+			.push(axis_row(
+				sweep.x,
+				SceneMessage::SweepXSelected,
+				&sweep.x_range,
+				[SweepInput::XFrom, SweepInput::XTo, SweepInput::XSteps],
+			))
+			.push(
+				tglr(
+					theme,
+					"Second variable",
+					sweep.two_d,
+					SceneMessage::SweepTwoDToggled,
+				)
+				.width(Length::Shrink),
+			);
+
+		if sweep.two_d {
+			panel = panel.push(axis_row(
+				sweep.y,
+				SceneMessage::SweepYSelected,
+				&sweep.y_range,
+				[SweepInput::YFrom, SweepInput::YTo, SweepInput::YSteps],
+			));
+		}
+
+		panel = panel.push(btn(theme, "Run sweep", SceneMessage::RunSweep));
+
+		let result = match &self.sweep_result {
+			Some(result) => result,
+			None => return panel,
+		};
+
+		let (continuous, dispersed) = self.phase_labels();
+
+		if result.sweep.y.is_some() {
+			let outputs = vec![continuous.clone(), dispersed.clone()];
+			let selected = outputs[sweep.output].clone();
+
+			panel = panel
+				.push(
+					row()
+						.push(text("Colour by"))
+						.push(horizontal_space(Length::Fill))
+						.push(
+							drop_down(
+								theme,
+								outputs,
+								Some(selected.clone()),
+								SceneMessage::SweepOutputSelected,
+							)
+							.width(Length::Units(200)),
+						)
+						.align_items(iced::Alignment::Center),
+				)
+				.push(
+					Canvas::new(Heatmap {
+						result,
+						output: sweep.output,
+						output_name: selected,
+						units: self.units,
+						cache: &self.heatmap,
+					})
+					.width(Length::Fill)
+					.height(Length::Units(350)),
+				);
+		} else {
+			panel = panel.push(
+				Canvas::new(LinePlot {
+					result,
+					output_names: (continuous, dispersed),
+					units: self.units,
+				})
+				.width(Length::Fill)
+				.height(Length::Units(300)),
+			);
+		}
+
+		panel.push(
+			row()
+				.spacing(10)
+				.push(btn(theme, "Export CSV", SceneMessage::ExportSweepCsv))
+				.push(btn(theme, "Export PNG", SceneMessage::ExportSweepPng)),
+		)
+	}
+
+	fn run_sweep(&mut self) -> Result<(), Box<dyn Error>> {
+		let x = SweepInputs::axis(self.sweep.x, &self.sweep.x_range, &self.units)?;
+		let y = if self.sweep.two_d {
+			Some(SweepInputs::axis(
+				self.sweep.y,
+				&self.sweep.y_range,
+				&self.units,
+			)?)
+		} else {
+			None
+		};
+
+		let values = [
+			&self.input_data.dim_a,
+			&self.input_data.dim_b,
+			&self.input_data.freq,
+		];
+		let mut fixed = [0.0; 3];
+
+		for (variable, value) in Variable::ALL.iter().zip(values) {
+			let swept = *variable == x.variable || Some(*variable) == y.map(|y| y.variable);
+			if swept {
+				continue;
+			}
+
+			fixed[variable.index()] = value
+				.as_deref()
+				.and_then(|v| v.trim().parse::<f32>().ok())
+				.ok_or_else(|| format!("Enter a value for {variable} to hold it fixed"))?;
+		}
+
+		let fixed = self.units.inputs_to_base(fixed);
+		let sweep = Sweep { x, y, fixed };
+		self.sweep_result = Some(sweep.run(self.predictor()?.as_ref())?);
+		self.heatmap.clear();
+
+		Ok(())
+	}
+
+	// Neural network if its file exists, scaling law otherwise
+	fn predictor(&self) -> Result<Box<dyn Predictor>, Box<dyn Error>> {
+		match self.user_model_path.as_deref() {
+			Some(path) if path.exists() => Ok(Box::new(OnnxPredictor::load(path)?)),
+			_ => match self.baseline {
+				Some(law) => Ok(Box::new(law)),
+				None => Err("No model available for this device".into()),
+			},
+		}
+	}
+
+	fn phase_labels(&self) -> (String, String) {
+		match &self.fluid_pair {
+			Some(pair) => (pair.continuous.clone(), pair.dispersed.clone()),
+			None => (String::from("Continuous"), String::from("Dispersed")),
+		}
+	}
+
+	/*
+	 * The fluid pair comes from the loaded model's metadata if it has any,
+	 * otherwise from the pair recorded for the selected device. Fluid
+	 * properties are filled in from the library at the entered temperature.
+	 */
+	fn select_fluid_pair(&mut self) {
+		// the heatmap's legend names the phases
+		self.heatmap.clear();
+
+		let library = match FluidLibrary::load() {
+			Ok(library) => library,
+			Err(e) => {
+				self.error = Err(e);
+				return;
+			}
+		};
+
+		let from_model = self
+			.user_model_path
+			.as_deref()
+			.and_then(|path| ModelInfo::load(path).ok())
+			.and_then(|info| info.fluid_pair);
+
+		let from_device = self
+			.selection
+			.and_then(|device| library.device_pair(&device.to_string()).cloned());
+
+		self.fluid_pair = from_model.or(from_device);
+
+		let pair = match &self.fluid_pair {
+			Some(pair) => pair,
+			None => return,
+		};
+
+		let temperature = self
+			.input_data
+			.temperature
+			.as_deref()
+			.and_then(|t| t.parse::<f32>().ok())
+			.unwrap_or(20.0);
+
+		let inputs = &mut self.input_data;
+
+		if let Some(fluid) = library.fluid(&pair.continuous) {
+			let properties = fluid.properties_at(temperature);
+			inputs.cont_viscosity = Some(format!("{:.3}", properties.viscosity));
+			inputs.cont_density = Some(format!("{:.1}", properties.density));
+		}
+
+		if let Some(fluid) = library.fluid(&pair.dispersed) {
+			let properties = fluid.properties_at(temperature);
+			inputs.disp_viscosity = Some(format!("{:.3}", properties.viscosity));
+			inputs.disp_density = Some(format!("{:.1}", properties.density));
+		}
+
+		if let Some(tension) = library.tension(pair) {
+			inputs.interfacial = Some(tension.to_string());
+		}
+	}
+
+	fn flow_rows(
+		&self,
+		theme: Theme,
+		(continuous, dispersed): (f32, f32),
+	) -> Column<'_, SceneMessage> {
+		let (continuous_label, dispersed_label) = self.phase_labels();
+		let [continuous, dispersed] = self.units.flows_from_base([continuous, dispersed]);
+
+		let flow = |value: f32| {
+			if value.is_finite() {
+				value.to_string()
+			} else {
+				String::from("out of range")
+			}
+		};
+
+		column()
+			.spacing(5)
+			.push(
+				row()
+					.spacing(10)
+					.align_items(iced::Alignment::Center)
+					.push(text(format!("{continuous_label}:")))
+					.push(horizontal_space(Length::Fill))
+					.push(text(flow(continuous)))
+					.push(Self::unit_picker(
+						theme,
+						&FlowUnit::ALL[..],
+						self.units.flow1,
+						UnitChoice::Flow1,
+					)),
+			)
+			.push(
+				row()
+					.spacing(10)
+					.align_items(iced::Alignment::Center)
+					.push(text(format!("{dispersed_label}:")))
+					.push(horizontal_space(Length::Fill))
+					.push(text(flow(dispersed)))
+					.push(Self::unit_picker(
+						theme,
+						&FlowUnit::ALL[..],
+						self.units.flow2,
+						UnitChoice::Flow2,
+					)),
+			)
+	}
+
+	// Fits the scaling-law baseline to the selected device's training data
+	fn fit_baseline(&mut self) -> Result<(), Box<dyn Error>> {
+		self.baseline = None;
+		self.baseline_data = None;
+		self.bounds = None;
+
+		if let Some(device) = self.selection {
+			let volume_model = self.profiles.get(device).volume_model;
+			let samples = read_csv(&device.data_path())?;
+
+			self.bounds = input_bounds(&samples);
+			self.baseline = Some(ScalingLaw::fit(&samples, device.channel(), volume_model)?);
+		}
+
+		Ok(())
+	}
+
+	// Dispersed flow implied by droplet volume × frequency next to the predicted one
+	fn conservation_panel(&self, dispersed: f32) -> Column<'_, SceneMessage> {
+		let (device, [dim_a, dim_b, freq]) = match (self.selection, self.prediction_query) {
+			(Some(device), Some(query)) => (device, query),
+			_ => return column(),
+		};
+
+		let profile = self.profiles.get(device);
+		let implied = profile
+			.volume_model
+			.implied_flow(dim_a, dim_b, freq, device.channel());
+		let status = match conservation_error(implied, dispersed) {
+			Some(error) if error > profile.tolerance => {
+				format!("Mass conservation broken ({:.0}% off)", error * 100.0)
+			}
+			Some(error) => format!("Mass conservation OK ({:.0}% off)", error * 100.0),
+			None => String::from("Mass conservation cannot be checked (flow not positive)"),
+		};
+
+		column()
+			.spacing(5)
+			.push(
+				row()
+					.push(text("Implied:").size(16))
+					.push(horizontal_space(Length::Fill))
+					.push(
+						text(format!(
+							"{:.3} {}",
+							self.units.flow2.from_base(implied),
+							self.units.flow2
+						))
+						.size(16),
+					),
+			)
+			.push(text(status).size(16))
+	}
+
+	// Dimensionless numbers and droplet regime for a predicted operating point
+	fn physics_panel(&self, flows: (f32, f32)) -> Column<'_, SceneMessage> {
+		let panel = column().spacing(5);
+
+		let channel = match self.selection {
+			Some(device) => device.channel(),
+			None => return panel.push(text("Select a device to see the flow regime").size(16)),
+		};
+
+		let conditions = match self.input_data.flow_conditions(channel, flows) {
+			Some(conditions) => conditions,
+			None => {
+				return panel.push(text("Enter fluid properties to see the flow regime").size(16))
+			}
+		};
+
+		let numbers = match conditions.dimensionless() {
+			Some(numbers) => numbers,
+			None => {
+				return panel.push(
+					text("Regime: n/a, flows and interfacial tension must be positive").size(16),
+				)
+			}
+		};
+
+		let entry = |label: &str, value: String| {
+			row()
+				.push(text(label).size(16))
+				.push(horizontal_space(Length::Fill))
+				.push(text(value).size(16))
+		};
+
+		panel
+			.push(
+				row()
+					.push(text("Regime:"))
+					.push(horizontal_space(Length::Fill))
+					.push(text(numbers.regime().to_string()).font(BOLD)),
+			)
+			.push(entry("Capillary", format!("{:.2e}", numbers.capillary)))
+			.push(entry("Reynolds", format!("{:.2e}", numbers.reynolds)))
+			.push(entry("Weber", format!("{:.2e}", numbers.weber)))
+			.push(entry("Qd/Qc", format!("{:.3}", numbers.flow_ratio)))
+	}
+
+	// Selected device and the ONNX model predictions for it run with
+	pub fn model(&self) -> Option<(Device, &std::path::Path)> {
+		Some((self.selection?, self.user_model_path.as_deref()?))
+	}
+
+	pub fn session_models(&self) -> &[PathBuf] {
+		&self.session_models
+	}
+
+	fn remember_model(&mut self) {
+		if let Some(path) = &self.user_model_path {
+			if !self.session_models.contains(path) {
+				self.session_models.push(path.clone());
+			}
+		}
 	}
 
 	// -------------------- UTILITY
-	fn get_inference(&mut self) -> TractResult<()> {
-		let path = self.user_model_path.as_ref();
+	/*
+	 * Runs the neural network if its ONNX file exists and the scaling law if
+	 * one was fitted, so a device without a trained model still gets a result.
+	 */
+	fn get_inference(&mut self) -> Result<(), Box<dyn Error>> {
+		let parse = |value: &Option<String>| value.as_deref().unwrap_or("").trim().parse::<f32>();
+
+		let (dim_a, dim_b, freq) = match (
+			parse(&self.input_data.dim_a),
+			parse(&self.input_data.dim_b),
+			parse(&self.input_data.freq),
+		) {
+			(Ok(dim_a), Ok(dim_b), Ok(freq)) => (dim_a, dim_b, freq),
+			_ => return Err("Droplet dimensions and frequency must be numbers".into()),
+		};
+
+		let query = self.units.inputs_to_base([dim_a, dim_b, freq]);
+		self.prediction_query = Some(query);
+		self.animation_time = 0.0;
 
-		let (dim_a, dim_b, freq) = (
-			self.input_data.dim_a.as_ref().unwrap().parse::<f32>(),
-			self.input_data.dim_b.as_ref().unwrap().parse::<f32>(),
-			self.input_data.freq.as_ref().unwrap().parse::<f32>(),
+		self.baseline_data = match &self.baseline {
+			Some(law) => law.predict(query).map(|[c, d]| Some((c, d)))?,
+			None => None,
+		};
+
+		self.prediction_data = match self.user_model_path.as_deref() {
+			Some(path) if path.exists() => {
+				let [continuous, dispersed] = OnnxPredictor::load(path)?.predict(query)?;
+				Some((continuous, dispersed))
+			}
+			_ => None,
+		};
+
+		// the network's flows when it ran, the scaling law's otherwise
+		let (flows, model_path) = match (self.prediction_data, self.baseline_data) {
+			(Some(flows), _) => (flows, self.user_model_path.clone()),
+			(None, Some(flows)) => (flows, None),
+			(None, None) => return Err("No model available for this device".into()),
+		};
+
+		let predictor = self.predictor()?;
+		self.sensitivity = Some(Sensitivity::analyse(predictor.as_ref(), query, 0.01)?);
+
+		self.history.push(Record {
+			timestamp: timestamp(),
+			device: self.selection.map(|device| device.to_string()),
+			model: predictor.name(),
+			model_hash: model_path.as_deref().map(file_hash).transpose()?,
+			model_path,
+			inputs: query,
+			flows: [flows.0, flows.1],
+			baseline: self
+				.prediction_data
+				.and(self.baseline_data)
+				.map(|(continuous, dispersed)| [continuous, dispersed]),
+		});
+
+		self.history.save()
+	}
+
+	// Predicts every row of a CSV of inputs into `name-predicted.csv` next to it
+	fn predict_batch(&mut self, path: &std::path::Path) -> Result<(), Box<dyn Error>> {
+		let inputs = read_inputs(path)?;
+		let flows = self.predictor()?.predict_batch(&inputs)?;
+
+		let samples: Vec<Sample> = inputs
+			.iter()
+			.zip(flows)
+			.map(|(&[dim_a, dim_b, freq], [flow1, flow2])| Sample {
+				dim_a,
+				dim_b,
+				flow1,
+				flow2,
+				freq,
+			})
+			.collect();
+
+		let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+		let output = path.with_file_name(format!("{stem}-predicted.csv"));
+		write_csv(&output, &samples)?;
+
+		self.batch_status = Some(format!(
+			"Predicted {} rows into {}",
+			samples.len(),
+			output.display()
+		));
+
+		Ok(())
+	}
+
+	// -------------------- HISTORY
+	// Puts the device, model and inputs of a past prediction back
+	fn restore(&mut self, index: usize) {
+		let record = match self.history.records.get(index) {
+			Some(record) => record.clone(),
+			None => return,
+		};
+
+		if let Some(device) = Device::ALL
+			.into_iter()
+			.find(|device| record.device.as_deref() == Some(&device.to_string()))
+		{
+			self.update(SceneMessage::DeviceSelected(device));
+		}
+
+		// no model file means the scaling law made the prediction
+		self.user_model_toggle = record.model_path.is_some()
+			&& record.model_path != self.selection.map(|device| device.model_path());
+		self.user_model_path = record.model_path;
+		self.select_fluid_pair();
+
+		let [dim_a, dim_b, freq] = self
+			.units
+			.inputs_from_base(record.inputs)
+			.map(|value| Some(format_value(value)));
+		self.input_data.dim_a = dim_a;
+		self.input_data.dim_b = dim_b;
+		self.input_data.freq = freq;
+
+		self.prediction_data = None;
+		self.baseline_data = None;
+		self.prediction_query = None;
+		self.sensitivity = None;
+		self.error = Ok(());
+	}
+
+	fn history_panel(&self, theme: Theme) -> Column<'_, SceneMessage> {
+		if self.history.records.is_empty() {
+			return column().push(text("Predictions made are listed here.").size(16));
+		}
+
+		let (continuous, dispersed) = self.phase_labels();
+
+		let mut table = column().spacing(5).push(
+			row()
+				.spacing(10)
+				.push(text("Time (UTC)").font(BOLD).width(Length::Units(160)))
+				.push(text("Device").font(BOLD).width(Length::Units(60)))
+				.push(text("Model").font(BOLD).width(Length::Fill))
+				.push(
+					text(format!(
+						"Inputs ({}, {})",
+						self.units.dim_a, self.units.freq
+					))
+					.font(BOLD)
+					.width(Length::Fill),
+				)
+				.push(
+					text(format!("{continuous} / {dispersed} ({})", self.units.flow1))
+						.font(BOLD)
+						.width(Length::Fill),
+				)
+				.push(text("").width(Length::Units(200))),
 		);
 
-		if path.is_some() && dim_a.is_ok() && dim_b.is_ok() && freq.is_ok() {
-			let path = path.unwrap().to_str().unwrap();
+		// newest first
+		for (i, record) in self.history.records.iter().enumerate().rev() {
+			let [dim_a, dim_b, freq] = self.units.inputs_from_base(record.inputs);
+			let [flow1, flow2] = self.units.flows_from_base(record.flows);
+
+			table = table.push(
+				row()
+					.spacing(10)
+					.align_items(iced::Alignment::Center)
+					.push(
+						text(record.timestamp.replacen('T', " ", 1).trim_end_matches('Z'))
+							.size(16)
+							.width(Length::Units(160)),
+					)
+					.push(
+						text(record.device.as_deref().unwrap_or("–"))
+							.size(16)
+							.width(Length::Units(60)),
+					)
+					.push(
+						text(format!("{} {}", record.model, record.short_hash()))
+							.size(16)
+							.width(Length::Fill),
+					)
+					.push(
+						text(format!(
+							"{} × {}, {}",
+							format_value(dim_a),
+							format_value(dim_b),
+							format_value(freq)
+						))
+						.size(16)
+						.width(Length::Fill),
+					)
+					.push(
+						text(format!("{} / {}", format_value(flow1), format_value(flow2)))
+							.size(16)
+							.width(Length::Fill),
+					)
+					.push(
+						row()
+							.spacing(10)
+							.width(Length::Units(200))
+							.push(btn(theme, "Restore", SceneMessage::RestoreRecord(i)))
+							.push(btn(theme, "Re-run", SceneMessage::RerunRecord(i))),
+					),
+			);
+		}
+
+		column()
+			.spacing(10)
+			.push(
+				row()
+					.spacing(10)
+					.push(btn(theme, "Export CSV", SceneMessage::ExportHistoryCsv))
+					.push(btn(theme, "Export JSON", SceneMessage::ExportHistoryJson)),
+			)
+			.push(table)
+	}
+
+	// Elasticities of the prediction and error bars from input uncertainty
+	fn sensitivity_panel(&self, theme: Theme) -> Column<'_, SceneMessage> {
+		let sensitivity = match &self.sensitivity {
+			Some(sensitivity) if self.error.is_ok() => sensitivity,
+			_ => return column(),
+		};
+
+		let (continuous, dispersed) = self.phase_labels();
+		let inputs = ["length", "height", "frequency"];
+
+		let mut panel = column()
+			.spacing(5)
+			.push(horizontal_rule(1))
+			.push(text("Sensitivity").font(BOLD));
 
-			let model = tract_onnx::onnx()
-				.model_for_path(path)?
-				.with_input_fact(0, f32::fact(&[1, 3]).into())?
-				.into_optimized()?
-				.into_runnable()?;
+		for (j, input) in inputs.iter().enumerate() {
+			panel = panel.push(
+				text(format!(
+					"1% larger {input} → {:+.2}% {continuous}, {:+.2}% {dispersed}",
+					sensitivity.elasticity(j, 0),
+					sensitivity.elasticity(j, 1),
+				))
+				.size(16),
+			);
+		}
 
-			let vals: Vec<f32> = vec![dim_a.unwrap(), dim_b.unwrap(), freq.unwrap()];
+		let field = |value: &Option<String>| value.as_deref().unwrap_or("").to_owned();
+		let number = |value: &Option<String>| {
+			value
+				.as_deref()
+				.and_then(|v| v.trim().parse::<f32>().ok())
+				.unwrap_or(0.0)
+		};
 
-			let input = tract_ndarray::arr1(&vals).into_shape((1, 3)).unwrap();
+		// uncertainties are entered in the units of the fields they belong to
+		let units = self.units;
+		let [sigma_continuous, sigma_dispersed] =
+			units.flows_from_base(sensitivity.propagate(units.inputs_to_base([
+				number(&self.input_data.dim_a_error),
+				number(&self.input_data.dim_b_error),
+				number(&self.input_data.freq_error),
+			])));
+		let [flow_continuous, flow_dispersed] = units.flows_from_base(sensitivity.flows);
 
-			// Input the generated data into the model
-			let result = model.run(tvec!(input.into())).unwrap();
+		panel
+			.push(Self::input_row(
+				theme,
+				&format!("Length uncertainty ± ({})", units.dim_a),
+				&field(&self.input_data.dim_a_error),
+				|s| SceneMessage::PredictionInputChanged(PredictionInput::DimAError(s)),
+			))
+			.push(Self::input_row(
+				theme,
+				&format!("Height uncertainty ± ({})", units.dim_b),
+				&field(&self.input_data.dim_b_error),
+				|s| SceneMessage::PredictionInputChanged(PredictionInput::DimBError(s)),
+			))
+			.push(Self::input_row(
+				theme,
+				&format!("Frequency uncertainty ± ({})", units.freq),
+				&field(&self.input_data.freq_error),
+				|s| SceneMessage::PredictionInputChanged(PredictionInput::FreqError(s)),
+			))
+			.push(text(format!(
+				"{continuous}: {flow_continuous:.2} ± {sigma_continuous:.2} {}",
+				units.flow1
+			)))
+			.push(text(format!(
+				"{dispersed}: {flow_dispersed:.2} ± {sigma_dispersed:.2} {}",
+				units.flow2
+			)))
+	}
 
-			let to_show = result[0].to_array_view::<f32>()?;
+	// Unit picked next to a field
+	fn unit_picker<'a, U>(
+		theme: Theme,
+		all: &'a [U],
+		selected: U,
+		choice: fn(U) -> UnitChoice,
+	) -> PickList<'a, U, SceneMessage>
+	where
+		U: ToString + Eq + Clone + 'static,
+	{
+		drop_down(theme, all, Some(selected), move |unit| {
+			SceneMessage::UnitSelected(choice(unit))
+		})
+		.width(Length::Units(100))
+	}
 
-			let out = to_show.as_slice().unwrap();
+	// Entered values are converted, so they keep describing the same droplet
+	fn set_units(&mut self, units: Units) {
+		let old = self.units;
+		let inputs = &mut self.input_data;
 
-			self.prediction_data = Some((out[0], out[1]));
+		for (index, value) in [
+			&mut inputs.dim_a,
+			&mut inputs.dim_b,
+			&mut inputs.freq,
+			&mut inputs.dim_a_error,
+			&mut inputs.dim_b_error,
+			&mut inputs.freq_error,
+		]
+		.into_iter()
+		.enumerate()
+		{
+			if let Some(number) = value.as_deref().and_then(|v| v.trim().parse::<f32>().ok()) {
+				let mut base = [0.0; 3];
+				base[index % 3] = number;
+				let converted = units.inputs_from_base(old.inputs_to_base(base))[index % 3];
+				*value = Some(format_value(converted));
+			}
 		}
-		Ok(())
+
+		// sweep ranges are in the units of the variable they sweep
+		let sweep = &mut self.sweep;
+		for (variable, range) in [(sweep.x, &mut sweep.x_range), (sweep.y, &mut sweep.y_range)] {
+			for value in &mut range[..2] {
+				if let Ok(number) = value.trim().parse::<f32>() {
+					*value =
+						format_value(variable.from_base(&units, variable.to_base(&old, number)));
+				}
+			}
+		}
+
+		self.units = units;
+		self.heatmap.clear();
 	}
 
 	fn input_row<'a>(
+		theme: Theme,
 		label: &str,
 		dis_val: &str,
 		update: impl Fn(String) -> SceneMessage + 'a,
@@ -303,7 +1566,7 @@ impl PredictionUI {
 			.push(text(label))
 			.push(horizontal_space(Length::Fill))
 			.push(
-				tinput("", dis_val, update)
+				tinput(theme, "", dis_val, update)
 					.width(Length::Units(200))
 					.padding(10),
 			)
@@ -312,7 +1575,7 @@ impl PredictionUI {
 }
 
 // Microfluidic Devicede finitions
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Device {
 	CH100,
 	CH190,
@@ -320,7 +1583,7 @@ pub enum Device {
 }
 
 impl Device {
-	const ALL: [Self; 3] = [Device::CH100, Device::CH190, Device::CH275];
+	pub const ALL: [Self; 3] = [Device::CH100, Device::CH190, Device::CH275];
 
 	fn max_value(&self) -> f32 {
 		match self {
@@ -330,17 +1593,43 @@ impl Device {
 		}
 	}
 
-	fn path(&self) -> String {
+	// Nominal cross-section of the generator channel, taken as square
+	pub fn channel(&self) -> Channel {
+		let width = self.max_value();
+
+		Channel {
+			width,
+			height: width,
+		}
+	}
+
+	// Training data of the device, the open project's copy if it has one
+	pub fn data_path(&self) -> PathBuf {
+		let file = format!("{self}.csv");
+
+		match project_dir().map(|dir| dir.join(DATA_DIR).join(&file)) {
+			Some(path) if path.exists() => path,
+			_ => PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+				.join("assets/data")
+				.join(file),
+		}
+	}
+
+	// Model trained for the device, the open project's copy if it has one
+	pub fn model_path(&self) -> PathBuf {
 		let model_file = match self {
 			Device::CH100 => "100.onnx",
 			Device::CH190 => "190.onnx",
 			Device::CH275 => "275.onnx",
 		};
 
-		format!(
-			"{}{model_file}",
-			concat!(env!("CARGO_MANIFEST_DIR"), "/assets/models/")
-		)
+		match project_dir().map(|dir| dir.join(MODELS_DIR).join(model_file)) {
+			Some(path) if path.exists() => path,
+			_ => PathBuf::from(format!(
+				"{}{model_file}",
+				concat!(env!("CARGO_MANIFEST_DIR"), "/assets/models/")
+			)),
+		}
 	}
 }
 impl std::fmt::Display for Device {
@@ -356,3 +1645,45 @@ impl std::fmt::Display for Device {
 		)
 	}
 }
+
+// Per-device options kept between sessions
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DeviceProfile {
+	pub volume_model: VolumeModel,
+	// accepted relative mismatch between implied and actual dispersed flow
+	pub tolerance: f32,
+}
+
+impl Default for DeviceProfile {
+	fn default() -> Self {
+		DeviceProfile {
+			volume_model: VolumeModel::default(),
+			tolerance: 0.25,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeviceProfiles(BTreeMap<String, DeviceProfile>);
+
+impl DeviceProfiles {
+	pub fn path() -> PathBuf {
+		data_dir().join("devices.json")
+	}
+
+	pub fn load() -> Result<Self, Box<dyn Error>> {
+		load_json(&Self::path())
+	}
+
+	pub fn save(&self) -> Result<(), Box<dyn Error>> {
+		save_json(&Self::path(), self)
+	}
+
+	pub fn get(&self, device: Device) -> DeviceProfile {
+		self.0.get(&device.to_string()).copied().unwrap_or_default()
+	}
+
+	pub fn entry(&mut self, device: Device) -> &mut DeviceProfile {
+		self.0.entry(device.to_string()).or_default()
+	}
+}
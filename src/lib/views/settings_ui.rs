@@ -0,0 +1,221 @@
+use iced::{
+	pure::{
+		column, horizontal_rule, horizontal_space, row, text,
+		widget::{Column, Row},
+	},
+	Alignment, Length,
+};
+use native_dialog::FileDialog;
+use std::{error::Error, path::Path};
+
+use super::{prediction_ui::Device, SceneMessage};
+use crate::{
+	config::Config,
+	ml::interpreter_paths,
+	styling::{btn, drop_down, TextSize, Theme, BOLD},
+	units::{FlowUnit, LengthUnit},
+};
+
+// -------------------------------------------------- SETTINGS UI
+/*
+ * Edits a copy of the config, which the app applies and saves. Appearance
+ * changes are applied by the app right away and copied in here.
+ */
+pub struct SettingsUI {
+	saved: Config,
+	draft: Config,
+	error: Result<(), Box<dyn Error>>,
+}
+
+impl Default for SettingsUI {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl SettingsUI {
+	pub fn new() -> Self {
+		SettingsUI {
+			saved: Config::default(),
+			draft: Config::default(),
+			error: Ok(()),
+		}
+	}
+
+	pub fn update(&mut self, msg: SceneMessage) {
+		match msg {
+			SceneMessage::ConfigChanged(config) => {
+				// unsaved edits are kept
+				if self.draft == self.saved {
+					self.draft = config.clone();
+				} else {
+					self.draft.theme = config.theme.clone();
+					self.draft.palettes = config.palettes.clone();
+					self.draft.ui_scale = config.ui_scale;
+				}
+				self.saved = config;
+				return;
+			}
+			SceneMessage::SelectModelDir => {
+				if let Ok(Some(dir)) = FileDialog::new().show_open_single_dir() {
+					self.draft.model_dir = Some(dir);
+				}
+			}
+			SceneMessage::SelectPython => {
+				if let Ok(Some(python)) = FileDialog::new().show_open_single_file() {
+					self.draft.python = Some(python);
+				}
+			}
+			SceneMessage::ClearModelDir => self.draft.model_dir = None,
+			SceneMessage::ClearPython => self.draft.python = None,
+			SceneMessage::FlowUnitSelected(unit) => self.draft.flow_unit = unit,
+			SceneMessage::LengthUnitSelected(unit) => self.draft.length_unit = unit,
+			SceneMessage::DeviceSelected(device) => self.draft.default_device = Some(device),
+			SceneMessage::TextSizeSelected(size) => self.draft.text_size = size,
+			SceneMessage::ResetSettings => self.draft = Config::default(),
+			SceneMessage::ImportSettings => {
+				if let Ok(Some(path)) = FileDialog::new()
+					.add_filter("JSON", &["json"])
+					.show_open_single_file()
+				{
+					match Config::import(&path) {
+						Ok(config) => self.draft = config,
+						Err(e) => {
+							self.error = Err(e);
+							return;
+						}
+					}
+				}
+			}
+			SceneMessage::ExportSettings => {
+				if let Ok(Some(path)) = FileDialog::new()
+					.add_filter("JSON", &["json"])
+					.show_save_single_file()
+				{
+					self.error = self.saved.export(&path);
+				}
+				return;
+			}
+			_ => return,
+		}
+
+		self.error = self.check();
+	}
+
+	// Checks the interpreter runs as well, which validate leaves out as it is slow
+	fn check(&self) -> Result<(), Box<dyn Error>> {
+		self.draft.validate()?;
+
+		if let Some(python) = &self.draft.python {
+			if Some(python) != self.saved.python.as_ref() {
+				interpreter_paths(python)?;
+			}
+		}
+
+		Ok(())
+	}
+
+	pub fn view(&self, theme: Theme) -> Column<'_, SceneMessage> {
+		let path_name = |path: Option<&Path>, none: &str| match path {
+			Some(path) => path.display().to_string(),
+			None => none.to_owned(),
+		};
+
+		let mut view = column()
+			.spacing(15)
+			.push(
+				Self::setting_row("Model directory")
+					.push(text(path_name(self.draft.model_dir.as_deref(), "Not set")).size(16))
+					.push(btn(theme, "Choose", SceneMessage::SelectModelDir))
+					.push(btn(theme, "Clear", SceneMessage::ClearModelDir)),
+			)
+			.push(
+				Self::setting_row("Python interpreter")
+					.push(text(path_name(self.draft.python.as_deref(), "Embedded")).size(16))
+					.push(btn(theme, "Choose", SceneMessage::SelectPython))
+					.push(btn(theme, "Clear", SceneMessage::ClearPython)),
+			)
+			.push(
+				Self::setting_row("Default device").push(
+					drop_down(
+						theme,
+						&Device::ALL[..],
+						self.draft.default_device,
+						SceneMessage::DeviceSelected,
+					)
+					.placeholder("None")
+					.width(Length::Units(200)),
+				),
+			)
+			.push(
+				Self::setting_row("Default flow unit").push(
+					drop_down(
+						theme,
+						&FlowUnit::ALL[..],
+						Some(self.draft.flow_unit),
+						SceneMessage::FlowUnitSelected,
+					)
+					.width(Length::Units(200)),
+				),
+			)
+			.push(
+				Self::setting_row("Default length unit").push(
+					drop_down(
+						theme,
+						&LengthUnit::ALL[..],
+						Some(self.draft.length_unit),
+						SceneMessage::LengthUnitSelected,
+					)
+					.width(Length::Units(200)),
+				),
+			)
+			.push(
+				Self::setting_row("Text size").push(
+					drop_down(
+						theme,
+						&TextSize::ALL[..],
+						Some(self.draft.text_size),
+						SceneMessage::TextSizeSelected,
+					)
+					.width(Length::Units(200)),
+				),
+			);
+
+		if self.draft.text_size != self.saved.text_size {
+			view = view.push(text("The text size changes when iDrop is restarted").size(16));
+		}
+
+		let mut actions = row()
+			.spacing(10)
+			.push(btn(theme, "Reset to defaults", SceneMessage::ResetSettings))
+			.push(btn(theme, "Import", SceneMessage::ImportSettings))
+			.push(btn(theme, "Export", SceneMessage::ExportSettings))
+			.push(horizontal_space(Length::Fill));
+
+		// the app re-validates, but only offer saving what it will accept
+		if self.draft != self.saved && self.error.is_ok() {
+			actions = actions.push(btn(
+				theme,
+				"Save",
+				SceneMessage::SaveSettings(self.draft.clone()),
+			));
+		}
+
+		view = view.push(actions);
+
+		if let Err(e) = &self.error {
+			view = view.push(text(e.to_string()).color(theme.palette().danger));
+		}
+
+		view.push(horizontal_rule(10))
+			.push(text("Appearance").font(BOLD))
+	}
+
+	fn setting_row<'a>(label: &'a str) -> Row<'a, SceneMessage> {
+		row()
+			.spacing(10)
+			.align_items(Alignment::Center)
+			.push(text(label))
+			.push(horizontal_space(Length::Fill))
+	}
+}
@@ -3,17 +3,33 @@ use iced::{
 		column, horizontal_rule, row, text_input,
 		widget::{Column, Row, Text},
 	},
-	Color, Length, Space,
+	Length, Space,
 };
 
 use crate::{
-	ml::create_model,
+	config::Config,
+	dataset::{read_csv, write_csv},
+	export::{export, ExportFormat},
+	fluids::{FluidLibrary, FluidPair},
+	history::{file_hash, timestamp},
+	ml::{create_model, git_commit, Fingerprint, ModelInfo},
+	physics::conservation_error,
 	styling::{btn, drop_down, tglr, Theme, BOLD},
+	units::Units,
+	views::prediction_ui::{Device, DeviceProfiles},
 	views::SceneMessage,
 };
 
 use native_dialog::FileDialog;
-use std::{error::Error, path::PathBuf, thread};
+use std::{
+	error::Error,
+	path::{Path, PathBuf},
+	sync::atomic::{AtomicUsize, Ordering},
+	thread,
+};
+
+// Numbers the copies of training data, so trainings never share one
+static TRAINING_COPIES: AtomicUsize = AtomicUsize::new(0);
 
 // -------------------------------------------------- TRAINING UI
 pub struct TrainingUI {
@@ -24,8 +40,33 @@ pub struct TrainingUI {
 	pub models: Vec<UserModel>,
 	pub creation_toggle: bool,
 	model_save_path: Option<PathBuf>,
+	// model created in this session, offered to the prediction scene
+	trained: Option<PathBuf>,
+	// every model created in this session, bundled into saved projects
+	session_models: Vec<PathBuf>,
+	fluids: Vec<String>,
+	continuous: Option<String>,
+	dispersed: Option<String>,
+	device: Option<Device>,
+	seed: String,
+	// last model trained again from its fingerprint
+	reproduction: Option<Reproduction>,
+	export_format: ExportFormat,
+	// (weights, model card) last written
+	exported: Option<(PathBuf, PathBuf)>,
+	// model_save_path takes precedence over its model directory
+	config: Config,
+	// (rows, 1-based rows breaking mass conservation, rows without a positive flow)
+	conservation: Option<(usize, Vec<usize>, Vec<usize>)>,
 	error: Result<(), Box<dyn Error>>,
 }
+
+impl Default for TrainingUI {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 impl TrainingUI {
 	pub fn new() -> Self {
 		TrainingUI {
@@ -36,6 +77,18 @@ impl TrainingUI {
 			models: Vec::new(), // add persistance later
 			creation_toggle: true,
 			model_save_path: None,
+			trained: None,
+			session_models: Vec::new(),
+			fluids: FluidLibrary::load().unwrap_or_default().names(),
+			continuous: None,
+			dispersed: None,
+			device: None,
+			seed: String::from("0"),
+			reproduction: None,
+			export_format: ExportFormat::Json,
+			exported: None,
+			config: Config::default(),
+			conservation: None,
 			error: Ok(()),
 		}
 	}
@@ -56,20 +109,57 @@ impl TrainingUI {
 					.add_filter("", &["csv"])
 					.show_open_single_file()
 					.unwrap();
+				self.check_conservation();
+			}
+
+			SceneMessage::UseTrainingData(path) => {
+				self.data_path = Some(path);
+				self.check_conservation();
+			}
+
+			SceneMessage::DeviceSelected(device) => {
+				self.device = Some(device);
+				self.check_conservation();
 			}
 
 			SceneMessage::CreateToggled(value) => self.creation_toggle = value,
 
+			SceneMessage::FluidsChanged => {
+				self.fluids = FluidLibrary::load().unwrap_or_default().names();
+
+				// a removed fluid can no longer be chosen
+				let fluids = &self.fluids;
+				let listed = |name: &String| fluids.contains(name);
+				self.continuous = self.continuous.take().filter(listed);
+				self.dispersed = self.dispersed.take().filter(listed);
+			}
+
+			SceneMessage::ConfigChanged(config) => {
+				if self.device.is_none() {
+					self.device = config.default_device;
+					self.check_conservation();
+				}
+
+				self.config = config;
+			}
+
+			SceneMessage::SeedChanged(seed) if seed.is_empty() || seed.parse::<u64>().is_ok() => {
+				self.seed = seed
+			}
+
+			SceneMessage::ReproduceModel => self.error = self.reproduce(),
+
+			SceneMessage::ExportFormatSelected(format) => self.export_format = format,
+
+			SceneMessage::ExportModel => self.error = self.export_model(),
+
+			SceneMessage::ContinuousSelected(name) => self.continuous = Some(name),
+
+			SceneMessage::DispersedSelected(name) => self.dispersed = Some(name),
+
 			SceneMessage::GoPressed => {
-				if let Some(pathbuf) = &self.data_path {
-					let csv_path = pathbuf
-						.to_str()
-						.expect("CSV path not valid or empty")
-						.to_string();
-					// let _x = create_model(String::from(path), &self.model_name);
-					// let model_name = format!("{}", self.model_name);
-
-					let model_name_path: String = if let Some(save_path) = &self.model_save_path {
+				if let Some(data) = self.data_path.clone() {
+					let model_name_path: String = if let Some(save_path) = self.save_dir() {
 						save_path
 							.join(&self.model_name)
 							.to_str()
@@ -79,36 +169,53 @@ impl TrainingUI {
 						String::from(&self.model_name)
 					};
 
-					dbg!(&csv_path, &model_name_path);
+					let model_path = PathBuf::from(format!("{model_name_path}.onnx"));
 
-					let x = thread::spawn(move || {
-						create_model(&csv_path, &model_name_path).expect("create model failed");
-					});
+					self.trained = None;
+					self.error = self
+						.seed
+						.parse::<u64>()
+						.map_err(|_| "Enter a seed for training".into())
+						.and_then(|seed| self.train(&data, &model_name_path, seed, &self.config))
+						.and_then(|fingerprint| {
+							ModelInfo {
+								fluid_pair: self.fluid_pair(),
+								units: Units::default(),
+								fingerprint: Some(fingerprint),
+								device: self.device,
+							}
+							.save(&model_path)
+						});
 
-					if x.join().is_err() {
-						self.error = Err(String::from("Model creation failed").into());
+					match &self.error {
+						Ok(()) => {
+							self.session_models.push(model_path.clone());
+							self.trained = Some(model_path);
+						}
+						Err(e) => log::error!("Creating {model_name_path} failed: {e}"),
 					}
 				} else {
-					println!("path to training data not set");
+					log::warn!("Path to training data not set");
 				};
 			}
 			_ => {}
 		};
 	}
 
-	pub fn view(&self) -> Column<SceneMessage> {
+	pub fn view(&self, theme: Theme) -> Column<'_, SceneMessage> {
 		// --------------------COMPONENTS--------------------
 		let pick_list = drop_down(
+			theme,
 			&self.models,
 			self.selected_model.clone(),
 			SceneMessage::ModelSelected,
 		)
-		.style(Theme::Light)
 		.placeholder("Pick a model")
 		.padding(10);
 
 		let toggle_create: Row<SceneMessage> = row().push(
 			tglr(
+				theme,
 				"New model",
 				self.creation_toggle,
 				SceneMessage::CreateToggled,
@@ -125,11 +232,57 @@ impl TrainingUI {
 		.width(Length::Units(250));
 
 		let mut file_selection: Row<SceneMessage> = row()
-			.push(btn("Load data", SceneMessage::SelectCSV))
+			.push(btn(theme, "Load data", SceneMessage::SelectCSV))
 			.push(Space::with_width(Length::Fill));
 
+		let fluid_selection: Row<SceneMessage> = row()
+			.spacing(10)
+			.push(Text::new("Fluids"))
+			.push(Space::with_width(Length::Fill))
+			.push(
+				drop_down(
+					theme,
+					self.fluids.clone(),
+					self.continuous.clone(),
+					SceneMessage::ContinuousSelected,
+				)
+				.placeholder("Continuous"),
+			)
+			.push(
+				drop_down(
+					theme,
+					self.fluids.clone(),
+					self.dispersed.clone(),
+					SceneMessage::DispersedSelected,
+				)
+				.placeholder("Dispersed"),
+			);
+
+		let device_selection: Row<SceneMessage> = row()
+			.push(Text::new("Device"))
+			.push(Space::with_width(Length::Fill))
+			.push(
+				drop_down(
+					theme,
+					&Device::ALL[..],
+					self.device,
+					SceneMessage::DeviceSelected,
+				)
+				.placeholder("For consistency check"),
+			);
+
+		let seed_selection: Row<SceneMessage> = row()
+			.push(Text::new("Seed"))
+			.push(Space::with_width(Length::Fill))
+			.push(
+				iced::pure::text_input("0", &self.seed, SceneMessage::SeedChanged)
+					.padding(10)
+					.width(Length::Units(250)),
+			);
+
 		let mut save_path: Row<SceneMessage> = row()
 			.push(btn(
+				theme,
 				"Model save location",
 				SceneMessage::SelectModelSavePath,
 			))
@@ -153,34 +306,386 @@ impl TrainingUI {
 			file_selection = file_selection.push(Text::new(file_name).font(BOLD));
 		}
 
-		if let Some(path) = &self.model_save_path {
+		if let Some(path) = self.save_dir() {
 			let path = path.to_str().unwrap();
 			save_path = save_path.push(Text::new(format!("Saving to {}", path)));
 		}
 
-		let create_model_btn = btn("Create model", SceneMessage::GoPressed);
+		let create_model_btn = btn(theme, "Create model", SceneMessage::GoPressed);
 
 		let mut view = column()
 			.height(Length::Fill)
 			.spacing(25)
 			.push(controls)
 			.push(file_selection)
+			.push(fluid_selection)
+			.push(device_selection)
+			.push(seed_selection)
 			.push(save_path)
 			.spacing(20)
 			.push(horizontal_rule(10));
 
-		if *&self.data_path.is_some() && !*&self.model_name.is_empty() {
+		if let Some((total, flagged, unchecked)) = &self.conservation {
+			let summary = if flagged.is_empty() {
+				format!("All {} checked rows conserve mass", total - unchecked.len())
+			} else {
+				format!(
+					"{} of {total} rows break mass conservation, rows {}",
+					flagged.len(),
+					row_list(flagged)
+				)
+			};
+
+			view = view.push(Text::new(summary).size(16));
+
+			if !unchecked.is_empty() {
+				view = view.push(
+					Text::new(format!(
+						"{} rows cannot be checked (flow not positive), rows {}",
+						unchecked.len(),
+						row_list(unchecked)
+					))
+					.size(16),
+				);
+			}
+		}
+
+		if self.data_path.is_some() && !self.model_name.is_empty() {
 			view = view.push(create_model_btn);
 		}
 
+		if let Some(path) = &self.trained {
+			view = view.push(
+				row()
+					.push(Text::new(format!(
+						"Created {}",
+						path.file_name().unwrap_or_default().to_string_lossy()
+					)))
+					.push(Space::with_width(Length::Fill))
+					.push(btn(
+						theme,
+						"Use for prediction",
+						SceneMessage::UseModel(path.clone()),
+					)),
+			);
+		}
+
+		view = view
+			.push(
+				row()
+					.push(Text::new("Train a model again from its fingerprint"))
+					.push(Space::with_width(Length::Fill))
+					.push(btn(
+						theme,
+						"Reproduce a model",
+						SceneMessage::ReproduceModel,
+					)),
+			)
+			.push(self.reproduction_panel())
+			.push(
+				row()
+					.spacing(10)
+					.push(Text::new("Export a model"))
+					.push(Space::with_width(Length::Fill))
+					.push(drop_down(
+						theme,
+						&ExportFormat::ALL[..],
+						Some(self.export_format),
+						SceneMessage::ExportFormatSelected,
+					))
+					.push(btn(theme, "Export", SceneMessage::ExportModel)),
+			);
+
+		if let Some((weights, card)) = &self.exported {
+			let name = |path: &PathBuf| {
+				path.file_name()
+					.unwrap_or_default()
+					.to_string_lossy()
+					.into_owned()
+			};
+			view = view.push(
+				Text::new(format!(
+					"Exported {} with model card {}",
+					name(weights),
+					name(card)
+				))
+				.size(16),
+			);
+		}
+
 		if let Err(e) = &self.error {
-			view = view.push(Text::new(e.to_string()).color(Color::from_rgb(255., 0., 0.)));
+			view = view.push(Text::new(e.to_string()).color(theme.palette().danger));
 		};
 
 		view.height(Length::Shrink)
 	}
 }
 
+impl TrainingUI {
+	pub fn session_models(&self) -> &[PathBuf] {
+		&self.session_models
+	}
+
+	fn save_dir(&self) -> Option<&PathBuf> {
+		self.model_save_path
+			.as_ref()
+			.or(self.config.model_dir.as_ref())
+	}
+
+	// Trains model_name.onnx, model_name being a path without the extension
+	fn train(
+		&self,
+		data: &Path,
+		model_name: &str,
+		seed: u64,
+		config: &Config,
+	) -> Result<Fingerprint, Box<dyn Error>> {
+		/*
+		 * The script takes columns by position and values as they are, so it
+		 * trains on a copy in the base units and in the column order it expects.
+		 */
+		let normalized = std::env::temp_dir().join(format!(
+			"idrop-training-{}-{}.csv",
+			std::process::id(),
+			TRAINING_COPIES.fetch_add(1, Ordering::Relaxed)
+		));
+		write_csv(&normalized, &read_csv(data)?)?;
+
+		let csv_path = normalized
+			.to_str()
+			.ok_or("CSV path not valid or empty")?
+			.to_owned();
+
+		log::debug!("{} normalized to {csv_path}", data.display());
+
+		let model_name = model_name.to_owned();
+		let python = config.python.clone();
+
+		let training = thread::spawn(move || {
+			create_model(&csv_path, &model_name, seed, python.as_deref()).map_err(|e| e.to_string())
+		})
+		.join()
+		.map_err(|_| "Model creation failed");
+
+		// a copy left behind only takes space, so a failed removal is ignored
+		let _ = std::fs::remove_file(&normalized);
+		let training = training??;
+
+		Ok(Fingerprint {
+			trained: timestamp(),
+			seed,
+			idrop: git_commit(),
+			versions: training.versions,
+			data: data.to_owned(),
+			data_hash: file_hash(data)?,
+			config: config.clone(),
+			metrics: training.metrics,
+		})
+	}
+
+	// Picks a model, starting in the directory models are saved to
+	fn model_dialog(&self) -> FileDialog<'_> {
+		let dialog = FileDialog::new().add_filter("ONNX File", &["onnx"]);
+
+		match self.save_dir() {
+			Some(dir) => dialog.set_location(dir),
+			None => dialog,
+		}
+	}
+
+	// Writes a model's weights in the chosen format, with its model card
+	fn export_model(&mut self) -> Result<(), Box<dyn Error>> {
+		let model = match self.model_dialog().show_open_single_file()? {
+			Some(model) => model,
+			None => return Ok(()),
+		};
+
+		let format = self.export_format;
+		let description = format.to_string();
+		let extensions = [format.extension()];
+		let file_name = format.file_name(&model);
+
+		let target = match FileDialog::new()
+			.add_filter(&description, &extensions)
+			.set_location(model.parent().unwrap_or(Path::new(".")))
+			.set_filename(&file_name)
+			.show_save_single_file()?
+		{
+			Some(target) => target,
+			None => return Ok(()),
+		};
+
+		let card = export(&model, format, &target)?;
+		self.exported = Some((target, card));
+
+		Ok(())
+	}
+
+	/*
+	 * Trains a model again with the seed, data and settings in its
+	 * fingerprint, as name-reproduced.onnx next to it, and keeps both
+	 * fingerprints to compare.
+	 */
+	fn reproduce(&mut self) -> Result<(), Box<dyn Error>> {
+		let model = match self.model_dialog().show_open_single_file()? {
+			Some(model) => model,
+			None => return Ok(()),
+		};
+
+		let info = ModelInfo::load(&model)?;
+		let original = info
+			.fingerprint
+			.clone()
+			.ok_or("The model was trained before fingerprints were recorded")?;
+
+		if !original.data.exists() {
+			return Err(format!("Training data {} is gone", original.data.display()).into());
+		}
+
+		if file_hash(&original.data)? != original.data_hash {
+			return Err(format!(
+				"Training data {} changed since the model was trained",
+				original.data.display()
+			)
+			.into());
+		}
+
+		log::info!(
+			"Reproducing {} with seed {}",
+			model.display(),
+			original.seed
+		);
+
+		let name = format!("{}-reproduced", model.with_extension("").display());
+		let reproduced = self.train(&original.data, &name, original.seed, &original.config)?;
+		let reproduced_path = PathBuf::from(format!("{name}.onnx"));
+
+		ModelInfo {
+			fingerprint: Some(reproduced.clone()),
+			..info
+		}
+		.save(&reproduced_path)?;
+		self.session_models.push(reproduced_path);
+
+		self.reproduction = Some(Reproduction {
+			model,
+			original,
+			reproduced,
+		});
+
+		Ok(())
+	}
+
+	fn reproduction_panel(&self) -> Column<'_, SceneMessage> {
+		let Reproduction {
+			model,
+			original,
+			reproduced,
+		} = match &self.reproduction {
+			Some(reproduction) => reproduction,
+			None => return column(),
+		};
+
+		let (before, after) = (original.metrics, reproduced.metrics);
+		let differences = original.differences(reproduced);
+
+		let verdict = if before == after {
+			"Identical metrics, the model was reproduced"
+		} else if differences.is_empty() {
+			"Metrics differ in the same environment, training is not deterministic here"
+		} else {
+			"Metrics differ, and so does the environment"
+		};
+
+		let mut panel = column()
+			.spacing(5)
+			.push(
+				Text::new(format!(
+					"Reproduced {} with seed {}",
+					model.file_name().unwrap_or_default().to_string_lossy(),
+					original.seed
+				))
+				.font(BOLD),
+			)
+			.push(Text::new(format!("Loss {} → {}", before.loss, after.loss)).size(16))
+			.push(
+				Text::new(format!(
+					"Validation loss {} → {}",
+					before.val_loss, after.val_loss
+				))
+				.size(16),
+			)
+			.push(Text::new(verdict).size(16));
+
+		if before != after {
+			for difference in differences {
+				panel = panel.push(Text::new(difference).size(16));
+			}
+		}
+
+		panel
+	}
+
+	// Flags rows whose droplet volume × frequency disagrees with flow2
+	fn check_conservation(&mut self) {
+		self.conservation = None;
+
+		let (path, device) = match (&self.data_path, self.device) {
+			(Some(path), Some(device)) => (path, device),
+			_ => return,
+		};
+
+		let samples = match read_csv(path) {
+			Ok(samples) => samples,
+			Err(e) => {
+				self.error = Err(e);
+				return;
+			}
+		};
+
+		let profile = DeviceProfiles::load().unwrap_or_default().get(device);
+
+		let (mut flagged, mut unchecked) = (Vec::new(), Vec::new());
+		for (i, sample) in samples.iter().enumerate() {
+			let implied = profile.volume_model.implied_flow(
+				sample.dim_a,
+				sample.dim_b,
+				sample.freq,
+				device.channel(),
+			);
+
+			match conservation_error(implied, sample.flow2) {
+				Some(error) if error > profile.tolerance => flagged.push(i + 1),
+				Some(_) => {}
+				None => unchecked.push(i + 1),
+			}
+		}
+
+		self.conservation = Some((samples.len(), flagged, unchecked));
+	}
+
+	fn fluid_pair(&self) -> Option<FluidPair> {
+		Some(FluidPair {
+			continuous: self.continuous.clone()?,
+			dispersed: self.dispersed.clone()?,
+		})
+	}
+}
+
+// 1-based row numbers as "1, 4, 7"
+fn row_list(rows: &[usize]) -> String {
+	rows.iter()
+		.map(|row| row.to_string())
+		.collect::<Vec<_>>()
+		.join(", ")
+}
+
+struct Reproduction {
+	model: PathBuf,
+	original: Fingerprint,
+	reproduced: Fingerprint,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct UserModel {
 	pub name: String,
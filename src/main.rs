@@ -1,10 +1,14 @@
-use drop_gui::app;
+use drop_gui::{app, logging};
 use std::process;
 
 // Following examples on
 // https://github.com/iced-rs/iced/tree/master/examples
 
 fn main() {
+	if let Err(e) = logging::init() {
+		eprintln!("Logging to {} failed: {e}", logging::log_path().display());
+	}
+
 	if let Err(e) = app::App::launch() {
 		eprintln!("{e}");
 		process::exit(1);